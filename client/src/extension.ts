@@ -11,6 +11,8 @@ import { createHash } from "node:crypto";
 import {
 	LanguageClient,
 	RevealOutputChannelOn,
+	type Location,
+	type Position,
 	type TextDocumentPositionParams,
 	type LanguageClientOptions,
 	type ServerOptions,
@@ -378,6 +380,7 @@ export async function activate(context: vscode.ExtensionContext) {
 			{ language: "xml", scheme: "file" },
 			{ language: "python", scheme: "file" },
 			{ language: "javascript", scheme: "file" },
+			{ scheme: "file", pattern: "**/*.csv" },
 		],
 		outputChannel: binaryOutputChannel,
 		traceOutputChannel,
@@ -417,6 +420,21 @@ export async function activate(context: vscode.ExtensionContext) {
 		}),
 	);
 
+	context.subscriptions.push(
+		vscode.commands.registerCommand(
+			"odoo-lsp.showReferences",
+			async (uri: string, position: Position, locations: Location[]) => {
+				const converter = client.protocol2CodeConverter;
+				await vscode.commands.executeCommand(
+					"editor.action.showReferences",
+					converter.asUri(uri),
+					converter.asPosition(position),
+					await converter.asLocations(locations),
+				);
+			},
+		),
+	);
+
 	context.subscriptions.push(
 		vscode.commands.registerCommand("odoo-lsp.restart-lsp", async () => {
 			await client.restart();
@@ -463,6 +481,16 @@ export async function activate(context: vscode.ExtensionContext) {
 		}),
 	);
 
+	context.subscriptions.push(
+		vscode.workspace.registerTextDocumentContentProvider("odoo-lsp-arch", {
+			async provideTextDocumentContent(uri) {
+				const id = uri.path.replace(/\.xml$/, "");
+				const arch: string | null = await client.sendRequest("odoo-lsp/view-arch", { id });
+				return arch ?? `<!-- Could not compute the arch of ${id} -->`;
+			},
+		}),
+	);
+
 	client = new LanguageClient("odoo-lsp", "Odoo LSP", serverOptions, clientOptions);
 	await client.start();
 	traceOutputChannel.info("Odoo LSP started");
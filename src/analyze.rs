@@ -316,13 +316,11 @@ impl Index {
 		//    Self-type analysis only uses a small part of the class definition.
 		// 3. Parameters, e.g. self which always has a fixed type
 		// 4. Assignments (including walrus-assignment)
-		let mut scope = Scope::default();
 		let self_type = match self_type {
 			Some(type_) => &contents[type_.byte_range().shrink(1)],
 			None => "",
 		};
-		scope.insert(self_param.to_string(), Type::Model(self_type.into()));
-		scope.super_ = Some(self_param.into());
+		let scope = method_scope(self_type, self_param);
 
 		// special case: is this a property?
 		// TODO: fields
@@ -340,6 +338,53 @@ impl Index {
 		// Phase 3: With the proper context available, determine the type of the node.
 		self.type_of_node(Some(scope), fn_scope, range, contents)
 	}
+	/// Like [`Index::type_of_range`] for many `ranges` sorted by position, only walking the scope of each method once.
+	pub fn type_of_ranges(&self, root: Node<'_>, ranges: &[ByteRange], contents: &str) -> Vec<Option<TypeId>> {
+		let mut types = Vec::with_capacity(ranges.len());
+		while let Some(first) = ranges.get(types.len()) {
+			let Some((self_type, fn_scope, self_param)) = determine_scope(root, contents, first.start.0) else {
+				types.push(None);
+				continue;
+			};
+			let self_type = self_type.map(|type_| &contents[type_.byte_range().shrink(1)]);
+			let scope = method_scope(self_type.unwrap_or(""), self_param);
+			let rest = &ranges[types.len()..];
+			let len = (rest.iter())
+				.take_while(|range| fn_scope.byte_range().contains_end(range.end.0))
+				.count();
+			types.extend(self.type_of_method_ranges(fn_scope, scope, &rest[..len.max(1)], contents));
+		}
+		types
+	}
+	fn type_of_method_ranges(
+		&self,
+		fn_scope: Node<'_>,
+		scope: Scope,
+		ranges: &[ByteRange],
+		contents: &str,
+	) -> Vec<Option<TypeId>> {
+		let type_at = |scope: &Scope, range: &ByteRange| {
+			let node = fn_scope.descendant_for_byte_range(range.start.0, range.end.0)?;
+			self.type_of(node, scope, contents)
+		};
+		let mut types = Vec::with_capacity(ranges.len());
+		let (orig_scope, scope) = Self::walk_scope(fn_scope, Some(scope), |scope, node| {
+			// the scope is complete for every range ending before this node
+			while let Some(range) = ranges.get(types.len())
+				&& node.start_byte() > range.end.0
+			{
+				types.push(type_at(scope, range));
+			}
+			match ranges.get(types.len()) {
+				Some(range) => self.build_scope(scope, node, range.end.0, contents),
+				None => ControlFlow::Break(None),
+			}
+		});
+		let scope = scope.unwrap_or(orig_scope);
+		let rest = &ranges[types.len()..];
+		types.extend(rest.iter().map(|range| type_at(&scope, range)));
+		types
+	}
 	pub fn type_of_node(
 		&self,
 		scope: Option<Scope>,
@@ -1537,6 +1582,14 @@ impl Index {
 	}
 }
 
+/// The scope at the start of a method, where `self_param` is a recordset of `self_type`.
+fn method_scope(self_type: &str, self_param: &str) -> Scope {
+	let mut scope = Scope::default();
+	scope.insert(self_param.to_string(), Type::Model(self_type.into()));
+	scope.super_ = Some(self_param.into());
+	scope
+}
+
 /// Returns `(self_type, fn_scope, self_param)`.
 ///
 /// `fn_scope` is customarily a `function_definition` node.
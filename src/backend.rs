@@ -9,6 +9,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::Ordering::Relaxed;
 use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::time::Duration;
 
 use dashmap::DashMap;
 use derive_more::{Deref, DerefMut};
@@ -32,11 +33,11 @@ use crate::record::Record;
 use crate::utils::{MaxVec, Semaphore, strict_canonicalize, to_display_path};
 use crate::{errloc, format_loc, some};
 
-#[derive(Deref)]
+#[derive(Deref, Clone)]
 pub struct Backend {
 	pub client: Client,
 	#[deref]
-	pub inner: BackendInner,
+	pub inner: Arc<BackendInner>,
 }
 
 #[derive(SmartDefault)]
@@ -48,6 +49,21 @@ pub struct BackendInner {
 	pub record_ranges: DashMap<String, Box<[ByteRange]>>,
 	#[default(_code = "DashMap::with_shard_amount(4)")]
 	pub ast_map: DashMap<String, Tree>,
+	/// Bumped after any document is updated, invalidating [`reference_lenses`][Self::reference_lenses].
+	pub documents_generation: AtomicUsize,
+	/// (model, property) -> references counted by a code lens, and the generation they were counted at.
+	#[default(_code = "DashMap::with_shard_amount(4)")]
+	pub reference_lenses: DashMap<(ModelName, ImStr), (usize, Vec<Location>)>,
+	/// Bumped after any document is saved, invalidating the workspace diagnostics of closed files.
+	pub saved_generation: AtomicUsize,
+	/// Closed files without diagnostics, and the result ID they were last diagnosed at.
+	#[default(_code = "DashMap::with_shard_amount(4)")]
+	pub clean_files: DashMap<PathBuf, String>,
+	/// Manifest -> the save generation and contents its paths were last checked at, and their diagnostics.
+	///
+	/// Checking paths walks the files of the module, which is only repeated once either has changed.
+	#[default(_code = "DashMap::with_shard_amount(4)")]
+	pub manifest_diagnostics: DashMap<String, (usize, String, Vec<Diagnostic>)>,
 	pub index: Index,
 	pub workspaces: Workspaces,
 	#[default(_code = "Semaphore::init_semaphore()")]
@@ -102,6 +118,7 @@ pub struct Capabilities {
 	/// Whether [`workspace/workspaceFolders`][request::WorkspaceFoldersRequest] can be called.
 	pub workspace_folders: AtomicBool,
 	pub can_create_wdp: AtomicBool,
+	pub can_register_type_hierarchy: AtomicBool,
 }
 
 pub struct TextDocumentItem {
@@ -123,6 +140,7 @@ pub enum Language {
 	Python,
 	Xml,
 	Javascript,
+	Csv,
 }
 
 pub struct Document {
@@ -131,6 +149,8 @@ pub struct Document {
 	pub diagnostics_cache: Vec<Diagnostic>,
 	/// The current range of unsynced changes.
 	pub damage_zone: Option<ByteRange>,
+	/// Bumped with each change, so that only the last of a burst of changes pushes diagnostics.
+	pub diagnostics_epoch: u64,
 	pub setup: Arc<Semaphore>,
 }
 
@@ -140,6 +160,7 @@ impl Document {
 			rope,
 			diagnostics_cache: vec![],
 			damage_zone: None,
+			diagnostics_epoch: 0,
 			setup: Default::default(),
 		}
 	}
@@ -156,8 +177,8 @@ impl Backend {
 	/// Maximum number of descendants to show in docstring.
 	const INHERITS_LIMIT: usize = 3;
 
-	/// Maximum file line count to process diagnostics each on_change
-	pub const DIAGNOSTICS_LINE_LIMIT: usize = 1200;
+	/// Delay after the last change before diagnostics are pushed to clients without pull diagnostics.
+	pub const DIAGNOSTICS_DEBOUNCE: Duration = Duration::from_millis(300);
 
 	#[inline]
 	pub fn new(client: Client) -> Self {
@@ -255,7 +276,7 @@ impl Backend {
 				path = params.uri.to_file_path().unwrap();
 				let root_path = ok!(self.index.find_root_of(&path), "file not under any root");
 				root = _P(root_path);
-				eager_diagnostics = self.eager_diagnostics(params.open, &rope);
+				eager_diagnostics = self.eager_diagnostics(params.open);
 			}
 
 			let _blocker = if params.open {
@@ -286,12 +307,31 @@ impl Backend {
 			}
 			(Some((_, "xml")), _) | (_, Some(Language::Xml)) => {
 				self.update_xml(root, &params.text, &params.uri, slice, false)?;
+				let mut document = self.document_map.get_mut(params.uri.path().as_str()).unwrap();
+				if eager_diagnostics {
+					self.diagnose_xml(
+						&params.uri,
+						slice,
+						params.text.damage_zone(slice, None),
+						&mut document.diagnostics_cache,
+					);
+				} else {
+					document.damage_zone = params.text.damage_zone(slice, document.damage_zone.take());
+				}
 			}
 			(Some((_, "js")), _) | (_, Some(Language::Javascript)) => {
 				self.on_change_js(&params.text, &params.uri, slice, params.old_rope)?;
 			}
+			(Some((_, "csv")), _) | (_, Some(Language::Csv)) => {
+				self.update_csv(root, &params.uri, slice)?;
+				if eager_diagnostics {
+					let mut document = self.document_map.get_mut(params.uri.path().as_str()).unwrap();
+					self.diagnose_csv(&params.uri, slice, &mut document.diagnostics_cache);
+				}
+			}
 			other => return Err(errloc!("Unhandled language: {:?}", other)),
 		}
+		self.documents_generation.fetch_add(1, Relaxed);
 
 		if eager_diagnostics {
 			let client = self.client.clone();
@@ -307,6 +347,8 @@ impl Backend {
 					.publish_diagnostics(params.uri, diagnostics, Some(params.version))
 					.await
 			});
+		} else if !self.capabilities.pull_diagnostics.load(Relaxed) {
+			self.schedule_diagnostics(params.uri, params.version);
 		}
 
 		Ok(())
@@ -324,12 +366,82 @@ impl Backend {
 			"xml" => self.did_save_xml(uri, root)?,
 			_ => {}
 		}
+		self.documents_generation.fetch_add(1, Relaxed);
+		self.saved_generation.fetch_add(1, Relaxed);
 		Ok(())
 	}
 	/// Whether diagnostics should be processed/pushed with each `on_change`.
-	pub fn eager_diagnostics(&self, open: bool, rope: &Rope) -> bool {
-		!self.capabilities.pull_diagnostics.load(Relaxed)
-			&& (open || rope.len_lines(LINE_TYPE) < Self::DIAGNOSTICS_LINE_LIMIT)
+	/// Subsequent changes are debounced by [`schedule_diagnostics`][Self::schedule_diagnostics] instead.
+	pub fn eager_diagnostics(&self, open: bool) -> bool {
+		open && !self.capabilities.pull_diagnostics.load(Relaxed)
+	}
+	/// Processes the pending damage zone of a Python, XML or CSV document, and returns its updated diagnostics.
+	pub fn refresh_diagnostics(&self, uri: &Uri) -> Option<Vec<Diagnostic>> {
+		let path = uri.path().as_str();
+		let Some((_, ext @ ("py" | "xml" | "csv"))) = path.rsplit_once('.') else {
+			return None;
+		};
+		let mut document = self.document_map.get_mut(path)?;
+		let damage_zone = document.damage_zone.take();
+		let rope = document.rope.clone();
+		match ext {
+			"py" => {
+				let file_path = uri.to_file_path()?;
+				self.diagnose_python(
+					file_path.to_str()?,
+					rope.slice(..),
+					damage_zone,
+					&mut document.diagnostics_cache,
+				);
+			}
+			"xml" => self.diagnose_xml(uri, rope.slice(..), damage_zone, &mut document.diagnostics_cache),
+			_ => self.diagnose_csv(uri, rope.slice(..), &mut document.diagnostics_cache),
+		}
+		Some(document.diagnostics_cache.clone())
+	}
+	/// Refreshes and publishes the diagnostics of a document, for clients without pull diagnostics.
+	pub async fn push_diagnostics(&self, uri: Uri, version: Option<i32>) {
+		if let Some(diagnostics) = self.refresh_diagnostics(&uri) {
+			self.client.publish_diagnostics(uri, diagnostics, version).await;
+		}
+	}
+	/// Pushes diagnostics once no other change has been made to the document for [`Self::DIAGNOSTICS_DEBOUNCE`].
+	pub fn schedule_diagnostics(&self, uri: Uri, version: i32) {
+		let epoch = {
+			let Some(mut document) = self.document_map.get_mut(uri.path().as_str()) else {
+				return;
+			};
+			document.diagnostics_epoch += 1;
+			document.diagnostics_epoch
+		};
+		let backend = self.clone();
+		tokio::spawn(async move {
+			tokio::time::sleep(Self::DIAGNOSTICS_DEBOUNCE).await;
+			let latest = (backend.document_map.get(uri.path().as_str()))
+				.is_some_and(|document| document.diagnostics_epoch == epoch);
+			if latest {
+				backend.push_diagnostics(uri, Some(version)).await;
+			}
+		});
+	}
+	/// Pushes diagnostics again for the other open documents of modules depending on the module of `uri`,
+	/// since they might refer to models and records changed by it.
+	pub async fn republish_dependents(&self, uri: &Uri) {
+		let Some(module) = uri.to_file_path().and_then(|path| self.index.find_module_of(&path)) else {
+			return;
+		};
+		let dependents = (self.document_map.iter())
+			.filter(|entry| entry.key() != uri.path().as_str())
+			.filter_map(|entry| Uri::from_file_path(entry.key()))
+			.collect::<Vec<_>>();
+		for dependent in dependents {
+			let depends = (dependent.to_file_path())
+				.and_then(|path| self.index.find_module_of(&path))
+				.is_some_and(|dependent| self.index.resolve_transitive_dependencies(dependent).contains(&module));
+			if depends {
+				self.push_diagnostics(dependent, None).await;
+			}
+		}
 	}
 	#[instrument(skip_all, fields(uri))]
 	pub fn update_ast(
@@ -960,6 +1072,10 @@ impl Index {
 				match &field.kind {
 					FieldKind::Value | FieldKind::Related(_) => {}
 					FieldKind::Relational(relation) => { "\"" (_R(*relation)) "\", " }
+					FieldKind::Reference(models) => {
+						if !models.is_empty() { "selection=[" for model in models.iter() { "\"" (model) "\"" } sep { ", " } "], " }
+					}
+					FieldKind::ModelReference(model_field) => { "model_field=\"" (model_field) "\", " }
 				}
 				"…)\n```  \n"
 			}
@@ -967,6 +1083,9 @@ impl Index {
 			{
 				"*Defined in:* `" (_R(module)) "`  \n"
 			}
+			if let Some(delegate) = &field.delegate {
+				"*Delegated from:* `" (_R(delegate.model)) "` via `" (delegate.field) "`  \n"
+			}
 			if let Some(help) = &field.help { (help.to_string()) }
 		}
 	}
@@ -993,7 +1112,7 @@ impl Index {
 			drop(entry);
 			let rtype = self.eval_method_rtype(prop.into(), model_key, None);
 			let rtype = rtype.and_then(|rtype| self.type_display(rtype));
-			let model = self.models.get(&model_key).unwrap();
+			let model = some!(self.models.populate_properties(model_key.into(), &[]));
 			let method = model.methods.as_ref().unwrap().get(&prop).unwrap();
 			Ok(Some(Hover {
 				range,
@@ -0,0 +1,320 @@
+//! Call hierarchy of model methods.
+//!
+//! Each item of a model method stands for one of its overrides as listed in [`Method::locations`],
+//! ordered from the base definition to the most derived one. An override is called through `super()`
+//! by the override right after it, and every other call site of the method is shared by all overrides.
+//!
+//! [`Method::locations`]: crate::model::Method::locations
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::model::ModelName;
+use crate::python::PythonFunction;
+use crate::xml::function_tag_calls;
+
+/// Identifies the model method behind a [`CallHierarchyItem`].
+#[derive(Serialize, Deserialize)]
+struct MethodData {
+	model: String,
+	method: String,
+}
+
+/// Call sites grouped by their caller or callee.
+#[derive(Default)]
+struct CallGroups(Vec<(CallHierarchyItem, Vec<Range>)>);
+
+impl CallGroups {
+	fn push(&mut self, item: CallHierarchyItem, range: Range) {
+		let existing = (self.0.iter_mut())
+			.find(|(other, _)| other.uri == item.uri && other.selection_range == item.selection_range);
+		match existing {
+			Some((_, ranges)) => ranges.push(range),
+			None => self.0.push((item, vec![range])),
+		}
+	}
+}
+
+impl Backend {
+	pub fn prepare_call_hierarchy_impl(
+		&self,
+		params: CallHierarchyPrepareParams,
+	) -> anyhow::Result<Option<Vec<CallHierarchyItem>>> {
+		let uri = &params.text_document_position_params.text_document.uri;
+		let position = params.text_document_position_params.position;
+		let rope = {
+			let document = some!(self.document_map.get(uri.path().as_str()));
+			document.rope.clone()
+		};
+		let target = some!(self.property_target_at(uri, position, rope.slice(..))?);
+		let locations = some!(self.method_overrides(target.model, &target.prop));
+		let path = some!(uri.to_file_path());
+		// Prefer the override under the cursor, falling back to the most derived one.
+		let location = (locations.iter())
+			.find(|loc| {
+				loc.path.to_path().as_path() == &*path && loc.range.start <= position && position <= loc.range.end
+			})
+			.or(locations.last());
+		let location = some!(location);
+		Ok(Some(vec![self.method_item(target.model, &target.prop, location)]))
+	}
+	pub fn incoming_calls_impl(
+		&self,
+		params: CallHierarchyIncomingCallsParams,
+	) -> anyhow::Result<Option<Vec<CallHierarchyIncomingCall>>> {
+		let item = params.item;
+		let (model, method) = some!(method_data(&item));
+		let method = method.as_str();
+		let locations = some!(self.method_overrides(model, method));
+		let item_path = some!(item.uri.to_file_path());
+		let index = (locations.iter())
+			.position(|loc| loc.path.to_path().as_path() == &*item_path && loc.range.start == item.range.start);
+		let next = index.and_then(|index| locations.get(index + 1));
+		let family = self.index.property_family(model, method);
+
+		let mut groups = CallGroups::default();
+		for path in self.index.module_files("**/*.py") {
+			let Some(rope) = self.rope_of(&path) else { continue };
+			let rope = rope.slice(..);
+			for call in self.python_method_calls(&path, rope, &family, method) {
+				let caller_range: Range = rope_conv(call.caller.range.clone(), rope);
+				// `super()` only reaches this override from the one right after it
+				let reaches_item = !call.via_super
+					|| next.is_some_and(|next| next.path.to_path() == path && next.range.start == caller_range.start);
+				if !reaches_item {
+					continue;
+				}
+				let Some(caller) = self.python_function_item(&path, rope, &call.caller) else {
+					continue;
+				};
+				groups.push(caller, rope_conv(call.range, rope));
+			}
+		}
+
+		for (path, records) in self.index.records_of_models(&family) {
+			let Some(rope) = self.rope_of(&path) else { continue };
+			let rope = rope.slice(..);
+			let Some(uri) = Uri::from_file_path(&path) else {
+				continue;
+			};
+			let module = self.index.find_module_of(&path);
+			for call in self.xml_method_calls(rope, &records, &family, method) {
+				let id = match (call.id.contains('.'), module) {
+					(false, Some(module)) => format!("{}.{}", _R(module), call.id),
+					_ => call.id.to_string(),
+				};
+				let caller = call_item(
+					id,
+					SymbolKind::VARIABLE,
+					None,
+					uri.clone(),
+					rope_conv(call.range, rope),
+					rope_conv(call.id_range, rope),
+					None,
+				);
+				for range in call.calls {
+					groups.push(caller.clone(), rope_conv(range, rope));
+				}
+			}
+		}
+
+		for path in self.index.module_files("**/*.xml") {
+			let Some(rope) = self.rope_of(&path) else { continue };
+			let rope = rope.slice(..);
+			let calls = function_tag_calls(&Cow::from(rope), &family, method);
+			if calls.is_empty() {
+				continue;
+			}
+			let Some(uri) = Uri::from_file_path(&path) else {
+				continue;
+			};
+			let detail = self.file_detail(&path);
+			for range in calls {
+				let range: Range = rope_conv(range, rope);
+				let caller = call_item(
+					"<function>",
+					SymbolKind::FUNCTION,
+					detail.clone(),
+					uri.clone(),
+					range,
+					range,
+					None,
+				);
+				groups.push(caller, range);
+			}
+		}
+
+		for path in self.index.module_files("static/src/**/*.js") {
+			let Some(rope) = self.rope_of(&path) else { continue };
+			let rope = rope.slice(..);
+			let calls = self.js_property_edits(rope, &family, method);
+			if calls.is_empty() {
+				continue;
+			}
+			let Some(uri) = Uri::from_file_path(&path) else {
+				continue;
+			};
+			let name = path.file_name().unwrap_or_default().to_string_lossy();
+			let caller = call_item(
+				name,
+				SymbolKind::FILE,
+				self.file_detail(&path),
+				uri,
+				Range::default(),
+				Range::default(),
+				None,
+			);
+			for range in calls {
+				groups.push(caller.clone(), rope_conv(range, rope));
+			}
+		}
+
+		Ok(Some(
+			(groups.0.into_iter())
+				.map(|(from, from_ranges)| CallHierarchyIncomingCall { from, from_ranges })
+				.collect(),
+		))
+	}
+	pub fn outgoing_calls_impl(
+		&self,
+		params: CallHierarchyOutgoingCallsParams,
+	) -> anyhow::Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+		let item = params.item;
+		let path = some!(item.uri.to_file_path());
+		if path.extension().is_none_or(|ext| ext != "py") {
+			return Ok(None);
+		}
+		let rope = some!(self.rope_of(&path));
+		let rope = rope.slice(..);
+		let ByteOffset(offset) = rope_conv(item.selection_range.start, rope);
+
+		let mut groups = CallGroups::default();
+		for callee in self.python_method_callees(&path, rope, offset) {
+			let Some(locations) = self.method_overrides(callee.model, &callee.method) else {
+				continue;
+			};
+			let target = if callee.via_super {
+				// `super()` resolves to the override right before the caller
+				(locations.iter())
+					.position(|loc| loc.path.to_path().as_path() == &*path && loc.range.start == item.range.start)
+					.and_then(|index| index.checked_sub(1))
+					.and_then(|index| locations.get(index))
+			} else {
+				locations.last()
+			};
+			let Some(target) = target else { continue };
+			groups.push(
+				self.method_item(callee.model, &callee.method, target),
+				rope_conv(callee.range, rope),
+			);
+		}
+
+		Ok(Some(
+			(groups.0.into_iter())
+				.map(|(to, from_ranges)| CallHierarchyOutgoingCall { to, from_ranges })
+				.collect(),
+		))
+	}
+	/// Overrides of `method` as seen from `model`, including those of its ancestors.
+	fn method_overrides(&self, model: ModelName, method: &str) -> Option<Vec<MinLoc>> {
+		let entry = self.index.models.populate_properties(model, &[])?;
+		let key = _G(method)?;
+		let method = entry.methods.as_ref()?.get(&key)?;
+		Some(method.locations.iter().map(|loc| MinLoc::clone(loc)).collect())
+	}
+	fn method_item(&self, model: ModelName, method: &str, location: &MinLoc) -> CallHierarchyItem {
+		let path = location.path.to_path();
+		let Location { uri, range } = location.clone().into();
+		// method locations span the entire definition
+		let selection_range = self.python_function_name_range(&path, range.start).unwrap_or(range);
+		call_item(
+			method,
+			SymbolKind::METHOD,
+			Some(self.model_detail(model, &path)),
+			uri,
+			range,
+			selection_range,
+			serde_json::to_value(MethodData {
+				model: _R(model).to_string(),
+				method: method.to_string(),
+			})
+			.ok(),
+		)
+	}
+	fn python_function_item(
+		&self,
+		path: &Path,
+		rope: RopeSlice<'_>,
+		function: &PythonFunction,
+	) -> Option<CallHierarchyItem> {
+		let uri = Uri::from_file_path(path)?;
+		let range = rope_conv(function.range.clone(), rope);
+		let selection_range = rope_conv(function.name_range.clone(), rope);
+		let name = function.name.as_str();
+		Some(match function.model {
+			Some(model) => call_item(
+				name,
+				SymbolKind::METHOD,
+				Some(self.model_detail(model, path)),
+				uri,
+				range,
+				selection_range,
+				serde_json::to_value(MethodData {
+					model: _R(model).to_string(),
+					method: name.to_string(),
+				})
+				.ok(),
+			),
+			None => call_item(
+				name,
+				SymbolKind::FUNCTION,
+				self.file_detail(path),
+				uri,
+				range,
+				selection_range,
+				None,
+			),
+		})
+	}
+	/// The model along with the module contributing the definition at `path`.
+	fn model_detail(&self, model: ModelName, path: &Path) -> String {
+		match self.index.find_module_of(path) {
+			Some(module) => format!("{} ({})", _R(model), _R(module)),
+			None => _R(model).to_string(),
+		}
+	}
+	fn file_detail(&self, path: &Path) -> Option<String> {
+		self.index.find_module_of(path).map(|module| _R(module).to_string())
+	}
+}
+
+fn method_data(item: &CallHierarchyItem) -> Option<(ModelName, String)> {
+	let MethodData { model, method } = serde_json::from_value(item.data.clone()?).ok()?;
+	Some((_G(model)?.into(), method))
+}
+
+fn call_item(
+	name: impl Into<String>,
+	kind: SymbolKind,
+	detail: Option<String>,
+	uri: Uri,
+	range: Range,
+	selection_range: Range,
+	data: Option<serde_json::Value>,
+) -> CallHierarchyItem {
+	CallHierarchyItem {
+		name: name.into(),
+		kind,
+		tags: None,
+		detail,
+		uri,
+		range,
+		selection_range,
+		data,
+	}
+}
@@ -0,0 +1,234 @@
+//! Diagnostics over whole modules, i.e. `odoo-lsp check` and `workspace/diagnostic`.
+//!
+//! The same passes that are pushed to or pulled by an editor are run over every Python and XML
+//! file of each module under the given roots, without involving a client.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::Ordering::Relaxed;
+use std::time::UNIX_EPOCH;
+
+use tower_lsp_server::ls_types::*;
+use tracing::warn;
+use tree_sitter::{Parser, Tree};
+
+use crate::prelude::*;
+
+use crate::backend::{Backend, Text};
+
+/// Diagnostics reported for a single file.
+pub struct FileDiagnostics {
+	pub path: PathBuf,
+	pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Backend {
+	/// Indexes every module under `roots` and diagnoses each of their files, sorted by path.
+	///
+	/// Files without any diagnostics are omitted.
+	pub async fn check_roots(&self, roots: &[PathBuf]) -> anyhow::Result<Vec<FileDiagnostics>> {
+		for root in roots {
+			self.index.add_root(root, None).await?;
+		}
+
+		let manifests = self
+			.index
+			.roots
+			.iter()
+			.flat_map(|root| {
+				let root_path = root.key().clone();
+				root.values()
+					.map(|module| root_path.join(module.path.as_str()).join("__manifest__.py"))
+					.collect::<Vec<_>>()
+			})
+			.collect::<Vec<_>>();
+		// everything must be loaded upfront so that cross-module references can be resolved
+		for manifest in &manifests {
+			self.index.load_modules_for_document(manifest).await;
+		}
+
+		let mut out = vec![];
+		let mut files = self.index.module_files("**/*.py");
+		files.extend(self.index.module_files("**/*.xml"));
+		files.extend(self.index.module_files("**/*.csv"));
+		for path in files {
+			match self.check_file(&path) {
+				Ok(Some(file)) if !file.diagnostics.is_empty() => out.push(file),
+				Ok(_) => {}
+				Err(err) => warn!("could not check {}: {err}", path.display()),
+			}
+		}
+		out.sort_unstable_by(|lhs, rhs| lhs.path.cmp(&rhs.path));
+		Ok(out)
+	}
+	/// Diagnoses every file of the loaded modules that is not open in the editor.
+	///
+	/// Result IDs identify the contents of each file on disk along with the documents saved so far,
+	/// so that files are only diagnosed again once they or any of their possible dependencies change.
+	/// Files are checked in batches on blocking threads, reporting progress to the client in between.
+	pub async fn workspace_diagnostic_impl(&self, params: WorkspaceDiagnosticParams) -> WorkspaceDiagnosticReport {
+		let previous = (params.previous_result_ids.into_iter())
+			.map(|PreviousResultId { uri, value }| (uri, value))
+			.collect::<HashMap<_, _>>();
+		let previous = Arc::new(previous);
+
+		let mut files = self.index.module_files("**/*.py");
+		files.extend(self.index.module_files("**/*.xml"));
+		files.extend(self.index.module_files("**/*.csv"));
+
+		let progress = match params.work_done_progress_params.work_done_token {
+			Some(token) => Some(
+				self.client
+					.progress(token, "Diagnosing workspace")
+					.with_percentage(0)
+					.begin()
+					.await,
+			),
+			None => None,
+		};
+		let mut items = vec![];
+		let mut checked = 0;
+		for batch in files.chunks(WORKSPACE_BATCH_SIZE) {
+			let backend = self.clone();
+			let batch = batch.to_vec();
+			let previous = previous.clone();
+			match tokio::task::spawn_blocking(move || backend.workspace_diagnostic_batch(&batch, &previous)).await {
+				Ok(batch) => items.extend(batch),
+				Err(err) => warn!("workspace diagnostics panicked: {err}"),
+			}
+			checked += batch.len();
+			if let Some(progress) = &progress {
+				progress.report((checked * 100 / files.len()) as u32).await;
+			}
+		}
+		if let Some(progress) = progress {
+			progress.finish().await;
+		}
+		WorkspaceDiagnosticReport { items }
+	}
+	fn workspace_diagnostic_batch(
+		&self,
+		files: &[PathBuf],
+		previous: &HashMap<Uri, String>,
+	) -> Vec<WorkspaceDocumentDiagnosticReport> {
+		let saves = self.saved_generation.load(Relaxed);
+		let mut items = vec![];
+		for path in files {
+			let Some(uri) = Uri::from_file_path(path) else {
+				continue;
+			};
+			if self.document_map.contains_key(uri.path().as_str()) {
+				// open documents are covered by `textDocument/diagnostic`
+				continue;
+			}
+			let Some(result_id) = result_id(path, saves) else {
+				continue;
+			};
+			let previous = previous.get(&uri);
+			if previous == Some(&result_id) {
+				items.push(WorkspaceDocumentDiagnosticReport::Unchanged(
+					WorkspaceUnchangedDocumentDiagnosticReport {
+						uri,
+						version: None,
+						unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id },
+					},
+				));
+				continue;
+			}
+			if previous.is_none() && (self.clean_files.get(path)).is_some_and(|clean| *clean == result_id) {
+				// still nothing to report
+				continue;
+			}
+			let diagnostics = match self.check_file(path) {
+				Ok(Some(file)) => file.diagnostics,
+				Ok(None) => continue,
+				Err(err) => {
+					warn!("could not check {}: {err}", path.display());
+					continue;
+				}
+			};
+			if diagnostics.is_empty() {
+				self.clean_files.insert(path.clone(), result_id.clone());
+				if previous.is_none() {
+					// nothing to report, nor to clear
+					continue;
+				}
+			} else {
+				self.clean_files.remove(path);
+			}
+			items.push(WorkspaceDocumentDiagnosticReport::Full(
+				WorkspaceFullDocumentDiagnosticReport {
+					uri,
+					version: None,
+					full_document_diagnostic_report: FullDocumentDiagnosticReport {
+						result_id: Some(result_id),
+						items: diagnostics,
+					},
+				},
+			));
+		}
+		items
+	}
+	fn check_file(&self, path: &Path) -> anyhow::Result<Option<FileDiagnostics>> {
+		let contents = std::fs::read_to_string(path)?;
+		let uri = some!(Uri::from_file_path(path));
+		let root = _P(some!(self.index.find_root_of(path)));
+		let rope = Rope::from_str(&contents);
+		let slice = rope.slice(..);
+		let text = Text::Full(contents);
+
+		let mut diagnostics = vec![];
+		match path.extension().and_then(|ext| ext.to_str()) {
+			Some("py") => {
+				let path_str = ok!(path.to_str(), "non-utf8 path");
+				let ast = self.python_file_ast(path, &rope)?;
+				self.diagnose_python_tree(path_str, &ast, slice, None, &mut diagnostics);
+			}
+			Some("xml") => {
+				let record_ranges = self.index_xml_records(root, &text, &uri, slice, false)?;
+				self.diagnose_xml_ranges(&uri, slice, &record_ranges, None, &mut diagnostics);
+			}
+			Some("csv") => {
+				self.update_csv(root, &uri, slice)?;
+				self.diagnose_csv(&uri, slice, &mut diagnostics);
+			}
+			_ => return Ok(None),
+		}
+		Ok(Some(FileDiagnostics {
+			path: path.to_path_buf(),
+			diagnostics,
+		}))
+	}
+	/// Parses a Python file into a tree of its own, reusing the one in
+	/// [`Index::ast_cache`][crate::index::Index::ast_cache] if the file has not changed since it was cached.
+	///
+	/// Trees parsed here are not cached, as most files checked are never opened.
+	fn python_file_ast(&self, path: &Path, rope: &Rope) -> anyhow::Result<Tree> {
+		let contents = Cow::from(rope.slice(..));
+		if let Some(cached) = self.index.ast_cache.get(path)
+			&& Cow::from(cached.rope.slice(..)) == contents
+		{
+			return Ok(cached.tree.clone());
+		}
+		let mut parser = Parser::new();
+		parser.set_language(&tree_sitter_python::LANGUAGE.into())?;
+		let tree = ok!(
+			parser.parse(contents.as_bytes(), None),
+			"could not parse {}",
+			path.display()
+		);
+		Ok(tree)
+	}
+}
+
+const WORKSPACE_BATCH_SIZE: usize = 64;
+
+/// Identifies the contents of `path` on disk by its size and modification time,
+/// along with the number of documents saved at the time it is diagnosed.
+fn result_id(path: &Path, saves: usize) -> Option<String> {
+	let metadata = std::fs::metadata(path).ok()?;
+	let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+	Some(format!("{:x}-{:x}-{saves:x}", metadata.len(), modified.as_nanos()))
+}
@@ -1,17 +1,20 @@
 //! One-shot commands.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::{env::current_dir, io::stdout, process::exit, sync::Arc};
 
 use anyhow::Context;
 use globwalk::FileType;
+use odoo_lsp::backend::Backend;
 use odoo_lsp::config::{CompletionsConfig, Config, ModuleConfig, ReferencesConfig, SymbolsConfig};
 use odoo_lsp::index::{_I, _R, Index};
 use odoo_lsp::utils::strict_canonicalize;
 use odoo_lsp::{GITVER, NAME, VERSION, errloc, format_loc, loc, ok};
 use serde_json::Value;
+use tower_lsp_server::LspService;
 use tracing::{debug, warn};
 
+mod check;
 mod tsconfig;
 
 #[derive(Default)]
@@ -34,6 +37,9 @@ pub enum Command {
 	SelfUpdate {
 		nightly: bool,
 	},
+	Check {
+		format: check::Format,
+	},
 }
 
 #[derive(Default, Clone, Copy)]
@@ -61,6 +67,12 @@ pub fn parse_args<'r>(mut args: &[&'r str]) -> Args<'r> {
 				args = rest;
 				out.command = Command::SelfUpdate { nightly: false };
 			}
+			["check", rest @ ..] => {
+				args = rest;
+				out.command = Command::Check {
+					format: Default::default(),
+				};
+			}
 			["-h" | "--help", ..] => {
 				if print_usage().is_err() {
 					eprintln!("{HELP}");
@@ -91,6 +103,21 @@ pub fn parse_args<'r>(mut args: &[&'r str]) -> Args<'r> {
 					*nightly = true;
 				}
 			}
+			["--format", value, rest @ ..] => {
+				args = rest;
+				let value = match *value {
+					"text" => check::Format::Text,
+					"json" => check::Format::Json,
+					"sarif" => check::Format::Sarif,
+					_ => {
+						eprintln!("Unknown format {value}, expected one of text, json or sarif");
+						exit(1);
+					}
+				};
+				if let Command::Check { format } = &mut out.command {
+					*format = value;
+				}
+			}
 			["-j" | "--threads", threads, rest @ ..] => {
 				args = rest;
 				match threads.parse::<usize>() {
@@ -154,6 +181,14 @@ pub async fn run(args: Args<'_>) -> bool {
 					.inspect_err(|err| eprintln!("{} tsconfig failed: {err}", loc!()));
 			}
 		}
+		Command::Check { format } => match check(&args.addons_path, args.output, format).await {
+			Ok(true) => {}
+			Ok(false) => exit(1),
+			Err(err) => {
+				eprintln!("{} check failed: {err}", loc!());
+				exit(2);
+			}
+		},
 		#[cfg(feature = "self-update")]
 		Command::SelfUpdate { nightly } => {
 			_ = tokio::task::spawn_blocking(move || self_update(nightly))
@@ -316,6 +351,36 @@ async fn tsconfig(addons_path: &[&str], output: Option<&str>) -> anyhow::Result<
 	Ok(())
 }
 
+/// Returns false if any problems were found.
+async fn check(addons_path: &[&str], output: Option<&str>, format: check::Format) -> anyhow::Result<bool> {
+	if addons_path.is_empty() {
+		return Err(errloc!("No roots were given, pass them with --addons-path"));
+	}
+	let addons_path = addons_path
+		.iter()
+		.map(|path| strict_canonicalize(path).with_context(|| format_loc!("{} could not be canonicalized", path)))
+		.collect::<anyhow::Result<Vec<PathBuf>>>()?;
+
+	// The client is never connected, since every pass is run directly on the backend.
+	let (service, _) = LspService::new(Backend::new);
+	let files = service.inner().check_roots(&addons_path).await?;
+
+	let pwd = current_dir()?;
+	let report = match format {
+		check::Format::Text => check::to_text(&files, &pwd),
+		check::Format::Json => serde_json::to_string_pretty(&check::to_json(&files, &pwd))?,
+		check::Format::Sarif => serde_json::to_string_pretty(&check::to_sarif(&files, &pwd))?,
+	};
+	match output {
+		None | Some("-") => print!("{report}"),
+		Some(output) => {
+			std::fs::write(output, report)?;
+			eprintln!("Report written to {output}");
+		}
+	}
+	Ok(!check::has_problems(&files))
+}
+
 fn init(addons_path: &[&str], output: Option<&str>) -> anyhow::Result<()> {
 	let config = Config {
 		module: Some(ModuleConfig {
@@ -433,6 +498,18 @@ mod tests {
 		assert!(matches!(args.command, Command::SelfUpdate { nightly: true }));
 	}
 
+	#[test]
+	fn parse_args_check_format() {
+		let args = parse_args(&["check", "--addons-path", "a", "--format", "sarif"]);
+		assert!(matches!(
+			args.command,
+			Command::Check {
+				format: check::Format::Sarif
+			}
+		));
+		assert_eq!(args.addons_path, vec!["a"]);
+	}
+
 	#[test]
 	fn init_writes_config() {
 		let tmp = std::env::temp_dir().join("odoo_lsp_test_init.json");
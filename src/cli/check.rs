@@ -0,0 +1,189 @@
+use std::path::Path;
+
+use odoo_lsp::check::FileDiagnostics;
+use odoo_lsp::{NAME, VERSION};
+use serde_json::{Value, json};
+use tower_lsp_server::ls_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+	#[default]
+	Text,
+	Json,
+	Sarif,
+}
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/Desdaemon/odoo-lsp";
+
+/// Whether any of the diagnostics should fail the check.
+pub fn has_problems(files: &[FileDiagnostics]) -> bool {
+	files
+		.iter()
+		.flat_map(|file| &file.diagnostics)
+		.any(|diag| matches!(severity(diag), DiagnosticSeverity::ERROR | DiagnosticSeverity::WARNING))
+}
+
+/// `path:line:column: severity: message`, with one-based lines and columns.
+pub fn to_text(files: &[FileDiagnostics], base: &Path) -> String {
+	let mut out = String::new();
+	let mut count = 0;
+	for file in files {
+		let path = display_path(&file.path, base);
+		for diag in &file.diagnostics {
+			count += 1;
+			let start = diag.range.start;
+			out.push_str(&format!(
+				"{path}:{}:{}: {}: {}\n",
+				start.line + 1,
+				start.character + 1,
+				severity_name(severity(diag)),
+				diag.message
+			));
+		}
+	}
+	match count {
+		0 => out.push_str("No problems found.\n"),
+		1 => out.push_str("1 problem found.\n"),
+		_ => out.push_str(&format!("{count} problems found.\n")),
+	}
+	out
+}
+
+pub fn to_json(files: &[FileDiagnostics], base: &Path) -> Value {
+	let diagnostics = files
+		.iter()
+		.flat_map(|file| {
+			let path = display_path(&file.path, base);
+			file.diagnostics.iter().map(move |diag| {
+				json!({
+					"path": path,
+					"range": diag.range,
+					"severity": severity_name(severity(diag)),
+					"message": diag.message,
+				})
+			})
+		})
+		.collect::<Vec<_>>();
+	Value::Array(diagnostics)
+}
+
+/// A [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html) log with a single run.
+pub fn to_sarif(files: &[FileDiagnostics], base: &Path) -> Value {
+	let results = files
+		.iter()
+		.flat_map(|file| {
+			let path = display_path(&file.path, base);
+			file.diagnostics.iter().map(move |diag| {
+				let mut result = json!({
+					"level": sarif_level(severity(diag)),
+					"message": { "text": diag.message },
+					"locations": [{
+						"physicalLocation": {
+							"artifactLocation": { "uri": path },
+							"region": {
+								"startLine": diag.range.start.line + 1,
+								"startColumn": diag.range.start.character + 1,
+								"endLine": diag.range.end.line + 1,
+								"endColumn": diag.range.end.character + 1,
+							},
+						},
+					}],
+				});
+				if let Some(code) = &diag.code {
+					result["ruleId"] = match code {
+						NumberOrString::Number(code) => code.to_string().into(),
+						NumberOrString::String(code) => code.as_str().into(),
+					};
+				}
+				result
+			})
+		})
+		.collect::<Vec<_>>();
+	json!({
+		"$schema": SARIF_SCHEMA,
+		"version": "2.1.0",
+		"runs": [{
+			"tool": {
+				"driver": {
+					"name": NAME,
+					"version": VERSION,
+					"informationUri": INFORMATION_URI,
+				},
+			},
+			"results": results,
+		}],
+	})
+}
+
+/// Paths are reported relative to `base` where possible, with forward slashes.
+fn display_path(path: &Path, base: &Path) -> String {
+	let path = pathdiff::diff_paths(path, base)
+		.filter(|path| !path.starts_with(".."))
+		.unwrap_or_else(|| path.to_path_buf());
+	path.to_string_lossy().replace('\\', "/")
+}
+
+fn severity(diag: &Diagnostic) -> DiagnosticSeverity {
+	diag.severity.unwrap_or(DiagnosticSeverity::ERROR)
+}
+
+fn severity_name(severity: DiagnosticSeverity) -> &'static str {
+	match severity {
+		DiagnosticSeverity::ERROR => "error",
+		DiagnosticSeverity::WARNING => "warning",
+		DiagnosticSeverity::INFORMATION => "info",
+		_ => "hint",
+	}
+}
+
+fn sarif_level(severity: DiagnosticSeverity) -> &'static str {
+	match severity {
+		DiagnosticSeverity::ERROR => "error",
+		DiagnosticSeverity::WARNING => "warning",
+		_ => "note",
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::PathBuf;
+
+	use tower_lsp_server::ls_types::{Position, Range};
+
+	use super::*;
+
+	fn files() -> Vec<FileDiagnostics> {
+		vec![FileDiagnostics {
+			path: PathBuf::from("/addons/foo/views/views.xml"),
+			diagnostics: vec![Diagnostic {
+				range: Range::new(Position::new(2, 4), Position::new(2, 16)),
+				severity: Some(DiagnosticSeverity::WARNING),
+				message: "No XML record with ID `base.missing` found".into(),
+				..Default::default()
+			}],
+		}]
+	}
+
+	#[test]
+	fn check_text() {
+		let text = to_text(&files(), Path::new("/addons"));
+		assert_eq!(
+			text,
+			"foo/views/views.xml:3:5: warning: No XML record with ID `base.missing` found\n1 problem found.\n"
+		);
+		assert!(has_problems(&files()));
+	}
+
+	#[test]
+	fn check_sarif() {
+		let sarif = to_sarif(&files(), Path::new("/addons"));
+		assert_eq!(sarif["version"], "2.1.0");
+		let result = &sarif["runs"][0]["results"][0];
+		assert_eq!(result["level"], "warning");
+		let location = &result["locations"][0]["physicalLocation"];
+		assert_eq!(location["artifactLocation"]["uri"], "foo/views/views.xml");
+		assert_eq!(location["region"]["startLine"], 3);
+		assert_eq!(location["region"]["endColumn"], 17);
+	}
+}
@@ -0,0 +1,170 @@
+//! Code lenses above Python model classes, fields and methods.
+//!
+//! Lenses are listed without a command, and only counted once the client resolves them,
+//! since finding references visits every file of the loaded modules. Field and method references
+//! are then reused until a document is updated.
+
+use std::sync::atomic::Ordering::Relaxed;
+
+use serde_json::Value;
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::model::ModelName;
+
+/// A model class or property declared in a Python file.
+pub(crate) enum LensTarget {
+	Model(ModelName),
+	Field(ModelName, ImStr),
+	Method(ModelName, ImStr),
+}
+
+/// What a [`CodeLens`] counts once resolved.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum LensData {
+	Views { uri: Uri, model: String },
+	Overrides { uri: Uri, model: String, method: String },
+	References { uri: Uri, model: String, prop: String },
+}
+
+impl Backend {
+	pub fn code_lens_impl(&self, params: CodeLensParams) -> anyhow::Result<Option<Vec<CodeLens>>> {
+		let uri = params.text_document.uri;
+		let rope = {
+			let document = some!(self.document_map.get(uri.path().as_str()));
+			document.rope.clone()
+		};
+		let rope = rope.slice(..);
+		let path = some!(uri.to_file_path());
+
+		let mut lenses = vec![];
+		for (range, target) in self.python_lens_targets(&path, rope) {
+			let range = rope_conv(range, rope);
+			let mut push = |data: LensData| {
+				lenses.push(CodeLens {
+					range,
+					command: None,
+					data: serde_json::to_value(data).ok(),
+				})
+			};
+			match target {
+				LensTarget::Model(model) => push(LensData::Views {
+					uri: uri.clone(),
+					model: _R(model).to_string(),
+				}),
+				LensTarget::Field(model, field) => push(LensData::References {
+					uri: uri.clone(),
+					model: _R(model).to_string(),
+					prop: field.to_string(),
+				}),
+				LensTarget::Method(model, method) => {
+					push(LensData::Overrides {
+						uri: uri.clone(),
+						model: _R(model).to_string(),
+						method: method.to_string(),
+					});
+					push(LensData::References {
+						uri: uri.clone(),
+						model: _R(model).to_string(),
+						prop: method.to_string(),
+					});
+				}
+			}
+		}
+		Ok(Some(lenses))
+	}
+	pub fn code_lens_resolve_impl(&self, mut lens: CodeLens) -> anyhow::Result<CodeLens> {
+		let Some(data) = lens.data.clone() else {
+			return Ok(lens);
+		};
+		let position = lens.range.start;
+		let command = match serde_json::from_value(data)? {
+			LensData::Views { model, .. } => {
+				let views = self.index.records.views_by_model(&model_key(&model)?).count();
+				Command {
+					title: plural(views, "view"),
+					command: "jump_view".to_string(),
+					arguments: Some(vec![Value::String(model)]),
+				}
+			}
+			LensData::Overrides { uri, model, method } => {
+				let locations = self.index.method_references(&method, &model)?.unwrap_or_default();
+				let overrides = locations.len().saturating_sub(1);
+				show_references(plural(overrides, "override"), uri, position, locations)
+			}
+			LensData::References { uri, model, prop } => {
+				let locations = self.property_lens_references(model_key(&model)?, &prop);
+				show_references(plural(locations.len(), "reference"), uri, position, locations)
+			}
+		};
+		lens.command = Some(command);
+		Ok(lens)
+	}
+	/// References to `prop` on the family of `model`, reused until any document is updated
+	/// since the lenses of a file are resolved again each time it is scrolled or edited.
+	fn property_lens_references(&self, model: ModelName, prop: &str) -> Vec<Location> {
+		let generation = self.documents_generation.load(Relaxed);
+		let key = (model, ImStr::from(prop));
+		if let Some(cached) = self.reference_lenses.get(&key)
+			&& cached.0 == generation
+		{
+			return cached.1.clone();
+		}
+		let family = self.index.property_family(model, prop);
+		let mut locations = vec![];
+		for (path, rope, ranges) in self.property_ranges(&family, prop, false) {
+			let Some(uri) = Uri::from_file_path(&path) else {
+				continue;
+			};
+			for range in ranges {
+				locations.push(Location {
+					uri: uri.clone(),
+					range: rope_conv(range, rope.slice(..)),
+				});
+			}
+		}
+		self.reference_lenses.insert(key, (generation, locations.clone()));
+		locations
+	}
+}
+
+/// Opens a peek of `locations` on the client, see `odoo-lsp.showReferences` in the VSCode extension.
+fn show_references(title: String, uri: Uri, position: Position, locations: Vec<Location>) -> Command {
+	let arguments = [
+		serde_json::to_value(uri),
+		serde_json::to_value(position),
+		serde_json::to_value(locations),
+	];
+	Command {
+		title,
+		command: "odoo-lsp.showReferences".to_string(),
+		arguments: Some(arguments.into_iter().flatten().collect()),
+	}
+}
+
+fn model_key(model: &str) -> anyhow::Result<ModelName> {
+	let key = _G(model).ok_or_else(|| anyhow!("Model `{model}` is not indexed"))?;
+	Ok(key.into())
+}
+
+fn plural(count: usize, noun: &str) -> String {
+	match count {
+		1 => format!("1 {noun}"),
+		_ => format!("{count} {noun}s"),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::plural;
+
+	#[test]
+	fn test_plural() {
+		assert_eq!(plural(0, "view"), "0 views");
+		assert_eq!(plural(1, "override"), "1 override");
+		assert_eq!(plural(3, "reference"), "3 references");
+	}
+}
@@ -2,6 +2,7 @@
 //!
 //! [Owl]: https://odoo.github.io/owl/
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use tower_lsp_server::ls_types::Range;
 
 use crate::index::{Symbol, SymbolMap, TemplateIndex};
@@ -10,7 +11,7 @@ use crate::utils::MinLoc;
 
 pub type ComponentName = Symbol<Component>;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Serialize, Deserialize)]
 pub struct Component {
 	pub location: Option<MinLoc>,
 	pub subcomponents: Vec<ComponentName>,
@@ -22,7 +23,7 @@ pub struct Component {
 	pub template: Option<ComponentTemplate>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum ComponentTemplate {
 	Name(TemplateName),
 	Inline(Range),
@@ -44,7 +45,7 @@ impl Component {
 #[derive(Debug)]
 pub enum Prop {}
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PropDescriptor {
 	pub location: MinLoc,
 	pub type_: PropType,
@@ -65,3 +66,15 @@ bitflags::bitflags! {
 		const Any = !0;
 	}
 }
+
+impl Serialize for PropType {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_u8(self.bits())
+	}
+}
+
+impl<'de> Deserialize<'de> for PropType {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		Ok(PropType::from_bits_retain(u8::deserialize(deserializer)?))
+	}
+}
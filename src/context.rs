@@ -0,0 +1,236 @@
+//! Context keys, e.g. `'active_test'` in `self.env.context.get('active_test')`.
+//!
+//! The keys read from the context are indexed for every file. A context applied to a model also
+//! accepts `default_<field>` for each of its fields, and `search_default_<filter>` for each filter
+//! of its search views.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use dashmap::DashMap;
+use derive_more::Deref;
+use smart_default::SmartDefault;
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::index::Index;
+use crate::model::ModelName;
+
+#[rustfmt::skip]
+query! {
+	ContextReads(Context, Key);
+
+((call
+  (attribute
+    (_) @CONTEXT (identifier) @_get)
+  (argument_list . (string) @KEY))
+  (#eq? @_get "get")
+  (#match? @CONTEXT "(^|\\.)_?context$"))
+
+((subscript
+  (_) @CONTEXT (string) @KEY)
+  (#match? @CONTEXT "(^|\\.)_?context$"))
+
+((comparison_operator
+  (string) @KEY (_) @CONTEXT)
+  (#match? @CONTEXT "(^|\\.)_?context$"))
+}
+
+const DEFAULT_PREFIX: &str = "default_";
+const SEARCH_DEFAULT_PREFIX: &str = "search_default_";
+
+/// Context key -> where it is read.
+#[derive(SmartDefault, Deref)]
+pub struct ContextIndex {
+	#[deref]
+	#[default(_code = "DashMap::with_shard_amount(4)")]
+	inner: DashMap<ImStr, Vec<MinLoc>>,
+}
+
+impl ContextIndex {
+	/// Records the keys read in `path`. If `replace` is set, the keys previously read there are forgotten.
+	pub fn append(&self, path: PathSymbol, replace: bool, keys: &[(ImStr, Range)]) {
+		if replace {
+			self.inner.retain(|_, locations| {
+				locations.retain(|location| location.path != path);
+				!locations.is_empty()
+			});
+		}
+		for (key, range) in keys {
+			let location = MinLoc { path, range: *range };
+			self.inner.entry(key.clone()).or_default().push(location);
+		}
+	}
+}
+
+/// The keys read from the context in `contents`, e.g. `self.env.context.get('key')`,
+/// `self._context['key']` or `'key' in self.env.context`.
+pub fn index_context_keys(contents: &str) -> anyhow::Result<Vec<(ImStr, Range)>> {
+	// most files never look at the context
+	if !contents.contains("context") {
+		return Ok(vec![]);
+	}
+	let mut parser = Parser::new();
+	parser.set_language(&tree_sitter_python::LANGUAGE.into())?;
+	let ast = parser
+		.parse(contents, None)
+		.ok_or_else(|| anyhow!("{} AST not parsed", loc!()))?;
+	let mut cursor = QueryCursor::new();
+	let mut matches = cursor.matches(ContextReads::query(), ast.root_node(), contents.as_bytes());
+	let mut keys = vec![];
+	while let Some(match_) = matches.next() {
+		for key in match_.nodes_for_capture_index(ContextReads::Key as _) {
+			if let Some(content) = string_content(key) {
+				keys.push((contents[content.byte_range()].into(), span_conv(content.range())));
+			}
+		}
+	}
+	Ok(keys)
+}
+
+/// The content of `string`, unless it is empty or interpolated.
+fn string_content(string: Node) -> Option<Node> {
+	let mut content = None;
+	for child in string.named_children(&mut string.walk()) {
+		match child.kind() {
+			"string_content" if content.is_none() => content = Some(child),
+			"string_start" | "string_end" => {}
+			_ => return None,
+		}
+	}
+	content
+}
+
+impl Index {
+	/// Completes context keys starting with `needle`, including the implicit keys of `model` if known.
+	///
+	/// `read` provides the contents of view files, from which search filters are gathered.
+	pub fn complete_context_key(
+		&self,
+		needle: &str,
+		model: Option<ModelName>,
+		range: Range,
+		read: &dyn Fn(&Path) -> Option<Rope>,
+		items: &mut MaxVec<CompletionItem>,
+	) {
+		let mut seen = HashSet::new();
+		if let Some(model) = model {
+			let could_match = |prefix: &str| prefix.starts_with(needle) || needle.starts_with(prefix);
+			if could_match(DEFAULT_PREFIX)
+				&& let Some(entry) = self.models.populate_properties(model, &[])
+				&& let Some(fields) = &entry.fields
+			{
+				let mut fields = (fields.iter())
+					.map(|(name, field)| (format!("{DEFAULT_PREFIX}{}", _R(name)), _R(field.type_)))
+					.filter(|(key, _)| key.starts_with(needle))
+					.collect::<Vec<_>>();
+				fields.sort_unstable();
+				for (key, type_) in fields {
+					seen.insert(key.clone());
+					items.push_checked(CompletionItem {
+						label: key.clone(),
+						label_details: Some(CompletionItemLabelDetails {
+							detail: Some(format!(" {type_}")),
+							description: None,
+						}),
+						kind: Some(CompletionItemKind::FIELD),
+						text_edit: Some(CompletionTextEdit::Edit(TextEdit { range, new_text: key })),
+						..Default::default()
+					});
+				}
+			}
+			if could_match(SEARCH_DEFAULT_PREFIX) {
+				for (filter, _) in self.search_filters(model, read) {
+					let key = format!("{SEARCH_DEFAULT_PREFIX}{filter}");
+					if !key.starts_with(needle) || !seen.insert(key.clone()) {
+						continue;
+					}
+					items.push_checked(CompletionItem {
+						label: key.clone(),
+						detail: Some(format!("Search filter `{filter}`")),
+						kind: Some(CompletionItemKind::CONSTANT),
+						text_edit: Some(CompletionTextEdit::Edit(TextEdit { range, new_text: key })),
+						..Default::default()
+					});
+				}
+			}
+		}
+		let mut keys = (self.contexts.iter())
+			.filter(|entry| entry.key().starts_with(needle) && !seen.contains(entry.key().as_str()))
+			.map(|entry| (entry.key().to_string(), entry.value().len()))
+			.collect::<Vec<_>>();
+		keys.sort_unstable();
+		items.extend(keys.into_iter().map(|(key, reads)| CompletionItem {
+			label: key.clone(),
+			detail: Some(match reads {
+				1 => "Read in 1 place".to_string(),
+				_ => format!("Read in {reads} places"),
+			}),
+			kind: Some(CompletionItemKind::VARIABLE),
+			text_edit: Some(CompletionTextEdit::Edit(TextEdit { range, new_text: key })),
+			..Default::default()
+		}));
+	}
+	/// Goes to the field or search filter behind an implicit key, otherwise to where `key` is first read.
+	pub fn jump_def_context_key(
+		&self,
+		key: &str,
+		model: Option<ModelName>,
+		read: &dyn Fn(&Path) -> Option<Rope>,
+	) -> anyhow::Result<Option<Location>> {
+		if let Some(model) = model {
+			if let Some(field) = key.strip_prefix(DEFAULT_PREFIX)
+				&& let Some(location) = self.jump_def_property_name(field, _R(model))?
+			{
+				return Ok(Some(location));
+			}
+			if let Some(filter) = key.strip_prefix(SEARCH_DEFAULT_PREFIX)
+				&& let Some((_, location)) =
+					(self.search_filters(model, read).into_iter()).find(|(name, _)| name == filter)
+			{
+				return Ok(Some(location.into()));
+			}
+		}
+		let reads = some!(self.contexts.get(key.as_bytes()));
+		Ok(reads.first().cloned().map(Into::into))
+	}
+	/// All places where `key` is read from the context.
+	pub fn context_key_references(&self, key: &str) -> Option<Vec<Location>> {
+		let reads = self.contexts.get(key.as_bytes())?;
+		Some(reads.iter().cloned().map(Into::into).collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+
+	use super::index_context_keys;
+
+	#[test]
+	fn test_index_context_keys() {
+		let contents = r#"
+def foo(self):
+    a = self.env.context.get('active_test', True)
+    b = self._context['tz']
+    if 'lang' in self.env.context and context.get("legacy") and ctx.get('local'):
+        pass
+    c = self.env.context.get(f'dynamic_{a}')
+    d = self.env.context.get('')
+"#;
+		let keys = (index_context_keys(contents).unwrap().into_iter())
+			.map(|(key, range)| (key.to_string(), range.start.line, range.start.character))
+			.collect::<Vec<_>>();
+		let key = |key: &str, line: u32, character: u32| (key.to_string(), line, character);
+		assert_eq!(
+			keys,
+			[
+				key("active_test", 2, 30),
+				key("tz", 3, 23),
+				key("lang", 4, 8),
+				key("legacy", 4, 51),
+			]
+		);
+	}
+}
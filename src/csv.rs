@@ -0,0 +1,427 @@
+//! CSV data files such as `ir.model.access.csv`, whose rows are records of the model named by the file.
+//!
+//! The `id` column holds the XML ID of each row, and columns named `field:id` or `field/id` hold
+//! XML IDs of the comodel of `field`. The `model_*` IDs of `ir.model` are not declared in any file,
+//! and are instead derived from model names.
+
+use std::borrow::Cow;
+use std::ops::Range as StdRange;
+use std::path::Path;
+use std::sync::atomic::Ordering::Relaxed;
+
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::index::{Index, ModuleName};
+use crate::model::ModelName;
+use crate::record::{Record, RecordMetadata};
+
+/// Comodels of common columns, for when the model of a file is not indexed.
+const COMMON_COLUMNS: &[(&str, &str)] = &[("model_id", "ir.model"), ("group_id", "res.groups")];
+
+/// A cell of a CSV file, without its surrounding quotes.
+pub struct Cell<'a> {
+	pub value: &'a str,
+	pub range: StdRange<usize>,
+}
+
+/// What the cells of a column refer to.
+#[derive(Debug, PartialEq)]
+enum Column {
+	/// The XML ID of each row.
+	Id,
+	/// XML IDs of `ir.model`, derived from model names.
+	Model,
+	/// XML IDs of records, of a specific model if known.
+	Record(Option<ModelName>),
+	Value,
+}
+
+/// Splits `contents` into rows of cells. Quoted cells may contain separators and newlines.
+pub fn parse_csv(contents: &str) -> Vec<Vec<Cell<'_>>> {
+	let mut rows = vec![];
+	let mut row = vec![];
+	let mut start = 0;
+	let mut quoted = false;
+	for (idx, byte) in contents.bytes().enumerate() {
+		match byte {
+			// escaped quotes toggle twice
+			b'"' => quoted = !quoted,
+			b',' | b'\n' if !quoted => {
+				row.push(cell(contents, start..idx));
+				start = idx + 1;
+				if byte == b'\n' {
+					rows.push(core::mem::take(&mut row));
+				}
+			}
+			_ => {}
+		}
+	}
+	if start < contents.len() || !row.is_empty() {
+		row.push(cell(contents, start..contents.len()));
+		rows.push(row);
+	}
+	rows
+}
+
+fn cell(contents: &str, mut range: StdRange<usize>) -> Cell<'_> {
+	if contents[range.clone()].ends_with('\r') {
+		range.end -= 1;
+	}
+	let raw = &contents[range.clone()];
+	if raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"') {
+		range = range.start + 1..range.end - 1;
+	}
+	Cell {
+		value: &contents[range.clone()],
+		range,
+	}
+}
+
+/// The model whose records are held by a CSV file, i.e. its name without the extension.
+pub fn csv_model(path: &Path) -> Option<&str> {
+	let stem = path.file_stem()?.to_str()?;
+	stem.contains('.').then_some(stem)
+}
+
+/// The XML ID of `model` in `ir.model`, e.g. `model_res_partner`, without its module.
+pub fn model_xml_id(model: &str) -> String {
+	format!("model_{}", model.replace('.', "_"))
+}
+
+/// Rows of a CSV file with an `id` column as records of `model`.
+pub fn csv_records(
+	contents: &str,
+	model: Option<&str>,
+	module: ModuleName,
+	path: PathSymbol,
+	rope: RopeSlice<'_>,
+) -> Vec<(Record, Option<RecordMetadata>)> {
+	let mut rows = parse_csv(contents).into_iter();
+	let Some(header) = rows.next() else {
+		return vec![];
+	};
+	let column = |name: &str| header.iter().position(|cell| cell.value == name);
+	let Some(id_column) = column("id") else {
+		return vec![];
+	};
+	let access_column = (model == Some("ir.model.access"))
+		.then(|| column("model_id:id").or_else(|| column("model_id/id")))
+		.flatten();
+
+	let mut out = vec![];
+	for row in rows {
+		let (Some(id), Some(first), Some(last)) = (row.get(id_column), row.first(), row.last()) else {
+			continue;
+		};
+		if id.value.is_empty() {
+			continue;
+		}
+		let metadata = access_column
+			.and_then(|column| row.get(column))
+			.filter(|cell| !cell.value.is_empty())
+			.map(|cell| {
+				let model_id = cell.value.rsplit_once('.').map_or(cell.value, |(_, id)| id);
+				RecordMetadata::Access(model_id.into())
+			});
+		let range = ByteOffset(first.range.start)..ByteOffset(last.range.end);
+		let record = Record {
+			deleted: false,
+			id: id.value.rsplit_once('.').map_or(id.value, |(_, id)| id).into(),
+			module,
+			model: model.map(|model| _I(model).into()),
+			inherit_id: None,
+			location: MinLoc {
+				path,
+				range: rope_conv(range, rope),
+			},
+		};
+		out.push((record, metadata));
+	}
+	out
+}
+
+/// The cell at `offset` along with the header of its column, unless it is a header itself.
+fn cell_at<'a>(rows: &'a [Vec<Cell<'a>>], offset: usize) -> Option<(&'a Cell<'a>, &'a str)> {
+	let (header, rows) = rows.split_first()?;
+	for row in rows {
+		if let Some(column) = row.iter().position(|cell| cell.range.contains_end(offset)) {
+			return Some((&row[column], header.get(column)?.value));
+		}
+	}
+	None
+}
+
+/// CSV extensions.
+impl Backend {
+	pub fn update_csv(&self, root: Spur, uri: &Uri, rope: RopeSlice<'_>) -> anyhow::Result<()> {
+		let path = uri.to_file_path().ok_or_else(|| errloc!("uri.to_file_path failed"))?;
+		let current_module = self
+			.index
+			.find_module_of(&path)
+			.ok_or_else(|| errloc!("module_of_path for {} failed", uri.path().as_str()))?;
+		let contents = Cow::from(rope);
+		let path_uri = PathSymbol::strip_root(root, &path);
+		let records = csv_records(&contents, csv_model(&path), current_module, path_uri, rope);
+		self.index.records.append(records);
+		Ok(())
+	}
+	pub fn csv_completions(
+		&self,
+		params: &CompletionParams,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<CompletionResponse>> {
+		let uri = &params.text_document_position.text_document.uri;
+		let path = some!(uri.to_file_path());
+		let current_module = some!(self.index.find_module_of(&path));
+		let completions_limit = self
+			.workspaces
+			.find_workspace_of(&path, |_, ws| ws.completions.limit)
+			.unwrap_or_else(|| self.project_config.completions_limit.load(Relaxed));
+		let ByteOffset(offset) = rope_conv(params.text_document_position.position, rope);
+		let contents = Cow::from(rope);
+		let rows = parse_csv(&contents);
+		let (cell, header) = some!(cell_at(&rows, offset));
+		let needle = &cell.value[..offset - cell.range.start];
+		let range = cell.range.clone().map_unit(ByteOffset);
+
+		let mut items = MaxVec::new(completions_limit);
+		match self.index.csv_column(csv_model(&path), header) {
+			Column::Model => {
+				(self.index).complete_model_xml_id(needle, rope_conv(range, rope), current_module, &mut items);
+			}
+			Column::Record(model) => {
+				let model_filter = model.map(|model| vec![ImStr::from(_R(model))]);
+				self.index.complete_xml_id(
+					needle,
+					range,
+					rope,
+					model_filter.as_deref(),
+					current_module,
+					None,
+					&mut items,
+				)?;
+			}
+			Column::Id | Column::Value => return Ok(None),
+		}
+		Ok(Some(CompletionResponse::List(CompletionList {
+			is_incomplete: !items.has_space(),
+			items: items.into_inner(),
+		})))
+	}
+	pub fn csv_jump_def(&self, params: GotoDefinitionParams, rope: RopeSlice<'_>) -> anyhow::Result<Option<Location>> {
+		let uri = &params.text_document_position_params.text_document.uri;
+		let path = some!(uri.to_file_path());
+		let ByteOffset(offset) = rope_conv(params.text_document_position_params.position, rope);
+		let contents = Cow::from(rope);
+		let rows = parse_csv(&contents);
+		let (cell, header) = some!(cell_at(&rows, offset));
+		match self.index.csv_column(csv_model(&path), header) {
+			Column::Model => {
+				let model = some!(self.index.model_of_xml_id(cell.value));
+				self.index.jump_def_model(_R(model))
+			}
+			Column::Record(_) => self.index.jump_def_xml_id(cell.value, uri),
+			Column::Id | Column::Value => Ok(None),
+		}
+	}
+	pub fn csv_references(
+		&self,
+		params: ReferenceParams,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<Vec<Location>>> {
+		let uri = &params.text_document_position.text_document.uri;
+		let path = some!(uri.to_file_path());
+		let ByteOffset(offset) = rope_conv(params.text_document_position.position, rope);
+		let contents = Cow::from(rope);
+		let rows = parse_csv(&contents);
+		let (cell, header) = some!(cell_at(&rows, offset));
+		let current_module = self.index.find_module_of(&path);
+		match self.index.csv_column(csv_model(&path), header) {
+			Column::Model => {
+				let model = some!(self.index.model_of_xml_id(cell.value));
+				self.model_references(&path, &model)
+			}
+			Column::Id | Column::Record(_) => self.record_references(&path, cell.value, current_module),
+			Column::Value => Ok(None),
+		}
+	}
+	pub fn diagnose_csv(&self, uri: &Uri, rope: RopeSlice<'_>, diagnostics: &mut Vec<Diagnostic>) {
+		diagnostics.clear();
+		let Some(path) = uri.to_file_path() else {
+			return;
+		};
+		let current_module = self.index.find_module_of(&path);
+		let contents = Cow::from(rope);
+		let rows = parse_csv(&contents);
+		let Some((header, rows)) = rows.split_first() else {
+			return;
+		};
+		let columns = (header.iter())
+			.map(|cell| self.index.csv_column(csv_model(&path), cell.value))
+			.collect::<Vec<_>>();
+		for (cell, column) in rows.iter().flat_map(|row| row.iter().zip(&columns)) {
+			let (message, severity) = match column {
+				_ if cell.value.is_empty() => continue,
+				Column::Model => {
+					if self.index.model_of_xml_id(cell.value).is_some() {
+						continue;
+					}
+					(
+						format!("No model with XML ID `{}` found", cell.value),
+						DiagnosticSeverity::ERROR,
+					)
+				}
+				Column::Record(_) => {
+					if self.index.has_xml_id(cell.value, current_module) {
+						continue;
+					}
+					(
+						format!("No XML record with ID `{}` found", cell.value),
+						DiagnosticSeverity::WARNING,
+					)
+				}
+				Column::Id | Column::Value => continue,
+			};
+			diagnostics.push(Diagnostic {
+				range: rope_conv(cell.range.clone().map_unit(ByteOffset), rope),
+				severity: Some(severity),
+				message,
+				..Default::default()
+			});
+		}
+	}
+}
+
+impl Index {
+	fn csv_column(&self, model: Option<&str>, header: &str) -> Column {
+		if header == "id" {
+			return Column::Id;
+		}
+		let Some(field) = header.strip_suffix(":id").or_else(|| header.strip_suffix("/id")) else {
+			return Column::Value;
+		};
+		let comodel = model
+			.and_then(_G)
+			.and_then(|model| self.resolve_domain_path(model.into(), field)?.relation);
+		let comodel = comodel.map(|comodel| _R(comodel)).or_else(|| {
+			let (_, comodel) = COMMON_COLUMNS.iter().find(|(column, _)| *column == field)?;
+			Some(*comodel)
+		});
+		match comodel {
+			Some("ir.model") => Column::Model,
+			Some(comodel) => Column::Record(_G(comodel).map(ModelName::from)),
+			None => Column::Record(None),
+		}
+	}
+	/// Resolves an XML ID of `ir.model` like `base.model_res_partner` to its model.
+	pub fn model_of_xml_id(&self, xml_id: &str) -> Option<ModelName> {
+		let id = xml_id.rsplit_once('.').map_or(xml_id, |(_, id)| id);
+		// `res.partner` and `res_partner` share at least the first segment
+		let prefix = id.strip_prefix("model_")?.split('_').next()?;
+		let by_prefix = self.models.by_prefix.read().ok()?;
+		(by_prefix.iter_prefix(prefix.as_bytes()))
+			.find(|(model, _)| model_xml_id(model) == id)
+			.map(|(_, key)| *key)
+	}
+	/// Completes `model_*` XML IDs, qualified by the module declaring the model unless it is `current_module`.
+	pub fn complete_model_xml_id(
+		&self,
+		needle: &str,
+		range: Range,
+		current_module: ModuleName,
+		items: &mut MaxVec<CompletionItem>,
+	) {
+		let (qualified, id) = match needle.split_once('.') {
+			Some((_, id)) => (true, id),
+			None => (false, needle),
+		};
+		let prefix = (id.strip_prefix("model_"))
+			.and_then(|model| model.split('_').next())
+			.unwrap_or_default();
+		let Ok(by_prefix) = self.models.by_prefix.read() else {
+			return;
+		};
+		let matches = by_prefix.iter_prefix(prefix.as_bytes()).filter_map(|(model, key)| {
+			let xml_id = model_xml_id(model);
+			if !xml_id.starts_with(id) {
+				return None;
+			}
+			let module =
+				(self.models.get(key)).and_then(|entry| self.find_module_of(&entry.base.as_ref()?.0.path.to_path()));
+			let label = match module {
+				Some(module) if qualified || module != current_module => format!("{}.{xml_id}", _R(module)),
+				_ => xml_id,
+			};
+			Some(CompletionItem {
+				text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+					new_text: label.clone(),
+					range,
+				})),
+				label,
+				detail: Some(model.to_string()),
+				kind: Some(CompletionItemKind::CLASS),
+				..Default::default()
+			})
+		});
+		items.extend(matches);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+
+	use crate::index::Index;
+	use crate::prelude::*;
+	use crate::test_utils::index::index_models_with_properties;
+
+	use super::{Column, model_xml_id, parse_csv};
+
+	#[test]
+	fn test_parse_csv() {
+		let contents = "id,name,model_id:id\r\naccess_foo,\"Foo, \"\"bar\"\"\",model_foo\nlast,\"multi\nline\",";
+		let rows = parse_csv(contents)
+			.into_iter()
+			.map(|row| row.into_iter().map(|cell| cell.value).collect::<Vec<_>>())
+			.collect::<Vec<_>>();
+		assert_eq!(
+			rows,
+			[
+				vec!["id", "name", "model_id:id"],
+				vec!["access_foo", "Foo, \"\"bar\"\"", "model_foo"],
+				vec!["last", "multi\nline", ""],
+			]
+		);
+	}
+
+	#[test]
+	fn test_model_xml_ids() {
+		let mut index = Index::default();
+		let py = r#"
+class ResPartner(models.Model):
+    _name = 'res.partner'
+    user_id = fields.Many2one('res.users')
+
+class ResPartnerBank(models.Model):
+    _name = 'res.partner.bank'
+"#;
+		index_models_with_properties(&mut index, Some(py), None, None);
+		assert_eq!(model_xml_id("res.partner.bank"), "model_res_partner_bank");
+		let model = |xml_id: &str| index.model_of_xml_id(xml_id).map(|model| _R(model).to_string());
+		assert_eq!(model("base.model_res_partner").as_deref(), Some("res.partner"));
+		assert_eq!(model("model_res_partner_bank").as_deref(), Some("res.partner.bank"));
+		assert_eq!(model("model_res_partners"), None);
+
+		assert_eq!(index.csv_column(Some("ir.model.access"), "id"), Column::Id);
+		assert_eq!(index.csv_column(Some("ir.model.access"), "model_id:id"), Column::Model);
+		assert_eq!(index.csv_column(Some("ir.model.access"), "perm_read"), Column::Value);
+		let users = _G("res.users").map(Into::into);
+		assert_eq!(
+			index.csv_column(Some("res.partner"), "user_id/id"),
+			Column::Record(users)
+		);
+	}
+}
@@ -0,0 +1,558 @@
+//! Domains, i.e. search criteria in prefix notation such as `['|', ('name', 'ilike', 'foo'), ('user_id', '=', uid)]`.
+//!
+//! Domains are always read from Python syntax trees, whether they were written in Python files
+//! or in XML attributes. Leaves on relational fields can nest subdomains with `any` and `not any`.
+
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::index::Index;
+use crate::model::{FieldKind, ModelName, ResolveMappedError};
+use crate::selection::diagnose_selection_value;
+
+/// Operators of domain leaves, along with their descriptions.
+pub static OPERATORS: &[(&str, &str)] = &[
+	("=", "Equal to the value, or unset if the value is `False`"),
+	("!=", "Not equal to the value, or set if the value is `False`"),
+	(">", "Greater than the value"),
+	(">=", "Greater than or equal to the value"),
+	("<", "Less than the value"),
+	("<=", "Less than or equal to the value"),
+	(
+		"=?",
+		"Equal to the value, or always true if the value is `None` or `False`",
+	),
+	(
+		"=like",
+		"Matches the pattern, where `_` stands for any character and `%` for any string",
+	),
+	("=ilike", "Matches the pattern case-insensitively"),
+	("like", "Contains the value"),
+	("not like", "Does not contain the value"),
+	("ilike", "Contains the value case-insensitively"),
+	("not ilike", "Does not contain the value case-insensitively"),
+	("in", "Equal to any of the values"),
+	("not in", "Equal to none of the values"),
+	("child_of", "Is one of the records, or one of their descendants"),
+	("parent_of", "Is one of the records, or one of their ancestors"),
+	("any", "Has a related record matching the subdomain"),
+	("not any", "Has no related record matching the subdomain"),
+];
+
+/// An element of a domain list.
+pub enum Term<'tree> {
+	/// `'&'` or `'|'`, applied to the next two terms, or `'!'`, applied to the next term.
+	Logical {
+		node: Node<'tree>,
+		operator: char,
+	},
+	Leaf(Leaf<'tree>),
+	/// Anything else, e.g. a variable holding a leaf, or a splatted domain.
+	Opaque(Node<'tree>),
+}
+
+/// A `(path, operator, value)` criterion, possibly still being written.
+pub struct Leaf<'tree> {
+	pub path: Node<'tree>,
+	pub operator: Option<Node<'tree>>,
+	pub value: Option<Node<'tree>>,
+}
+
+/// The field at the end of a domain path.
+pub struct PathField {
+	/// The model declaring the field.
+	pub model: ModelName,
+	pub name: ImStr,
+	/// The field class, e.g. `Many2one`.
+	pub type_: ImStr,
+	/// The comodel of a relational field.
+	pub relation: Option<ModelName>,
+}
+
+/// The part of a domain under the cursor. Ranges exclude the quotes of their strings.
+pub enum DomainCursor<'tree> {
+	/// The segment of a field path under the cursor, which belongs to `model`.
+	Path {
+		model: ModelName,
+		range: ByteRange,
+		/// How many `any` subdomains the leaf is nested in.
+		depth: usize,
+	},
+	Operator {
+		field: Option<PathField>,
+		range: ByteRange,
+	},
+	/// A string value, or a string in a list of values.
+	Value {
+		field: Option<PathField>,
+		node: Node<'tree>,
+		range: ByteRange,
+	},
+}
+
+impl DomainCursor<'_> {
+	pub fn range(&self) -> ByteRange {
+		match self {
+			Self::Path { range, .. } | Self::Operator { range, .. } | Self::Value { range, .. } => range.clone(),
+		}
+	}
+}
+
+/// A problem found in a domain, relative to the contents it was parsed from.
+pub struct DomainDiagnostic {
+	pub range: ByteRange,
+	pub severity: DiagnosticSeverity,
+	pub message: String,
+}
+
+/// Unwraps `lambda self: [...]` into its domain list, if any.
+pub fn domain_list(mut node: Node) -> Option<Node> {
+	if node.kind() == "lambda" {
+		node = node.child_by_field_name("body")?;
+	}
+	(node.kind() == "list").then_some(node)
+}
+
+pub fn parse_domain<'tree>(list: Node<'tree>, contents: &str) -> Vec<Term<'tree>> {
+	let mut terms = vec![];
+	for child in list.named_children(&mut list.walk()) {
+		let term = match child.kind() {
+			"comment" => continue,
+			"string" => match string_value(child, contents) {
+				Some("&") => Term::Logical {
+					node: child,
+					operator: '&',
+				},
+				Some("|") => Term::Logical {
+					node: child,
+					operator: '|',
+				},
+				Some("!") => Term::Logical {
+					node: child,
+					operator: '!',
+				},
+				_ => Term::Opaque(child),
+			},
+			// `('name'` is parsed as a parenthesized expression while it is being written
+			"tuple" | "list" | "parenthesized_expression" => {
+				match child
+					.python_nth_named_child::<0>()
+					.filter(|path| path.kind() == "string")
+				{
+					Some(path) => Term::Leaf(Leaf {
+						path,
+						operator: child.python_nth_named_child::<1>(),
+						value: child.python_nth_named_child::<2>(),
+					}),
+					None => Term::Opaque(child),
+				}
+			}
+			_ => Term::Opaque(child),
+		};
+		terms.push(term);
+	}
+	terms
+}
+
+/// Finds logical operators without enough operands.
+///
+/// Terms left over once all operators are applied are implicitly joined with `'&'`.
+pub fn arity_errors<'tree>(terms: &[Term<'tree>]) -> Vec<(Node<'tree>, String)> {
+	// splatted domains have an unknown number of terms
+	if (terms.iter()).any(|term| matches!(term, Term::Opaque(node) if node.kind() == "list_splat")) {
+		return vec![];
+	}
+	let mut operands = 0usize;
+	let mut errors = vec![];
+	for term in terms.iter().rev() {
+		match term {
+			Term::Logical { node, operator } => {
+				let arity = if *operator == '!' { 1 } else { 2 };
+				if operands < arity {
+					errors.push((
+						*node,
+						format!("`{operator}` expects {arity} operand(s), found {operands}"),
+					));
+					operands = 1;
+				} else {
+					operands -= arity - 1;
+				}
+			}
+			Term::Leaf(_) | Term::Opaque(_) => operands += 1,
+		}
+	}
+	errors.reverse();
+	errors
+}
+
+/// Whether `operator` can be applied to fields of class `type_`, e.g. `Many2one`.
+pub fn operator_applies(operator: &str, type_: &str, relational: bool) -> bool {
+	match operator {
+		"any" | "not any" | "child_of" | "parent_of" => relational,
+		">" | ">=" | "<" | "<=" => !matches!(type_, "Boolean" | "Binary" | "One2many" | "Many2many"),
+		"like" | "not like" | "ilike" | "not ilike" | "=like" | "=ilike" => !matches!(type_, "Boolean" | "Binary"),
+		_ => true,
+	}
+}
+
+/// The contents of a string literal, without its quotes.
+pub fn string_value<'a>(node: Node, contents: &'a str) -> Option<&'a str> {
+	(node.kind() == "string")
+		.then(|| contents.get(node.byte_range().shrink(1)))
+		.flatten()
+}
+
+impl Index {
+	/// Resolves a dotted `path` starting from `model` into its last field.
+	pub fn resolve_domain_path(&self, model: ModelName, path: &str) -> Option<PathField> {
+		let mut model = model.into();
+		let mut needle = path;
+		self.models.resolve_mapped(&mut model, &mut needle, None).ok()?;
+		let model = ModelName::from(model);
+		let builtin = match needle {
+			// `id` is hierarchical on the model itself
+			"id" => Some(("Integer", Some(model))),
+			"display_name" => Some(("Char", None)),
+			"create_date" | "write_date" => Some(("Datetime", None)),
+			"create_uid" | "write_uid" => Some(("Many2one", _G("res.users").map(ModelName::from))),
+			_ => None,
+		};
+		if let Some((type_, relation)) = builtin {
+			return Some(PathField {
+				model,
+				name: needle.into(),
+				type_: type_.into(),
+				relation,
+			});
+		}
+
+		let key = _G(needle)?;
+		let entry = self.models.populate_properties(model, &[])?;
+		let field = entry.fields.as_ref()?.get(&key)?.clone();
+		drop(entry);
+		let relation = match field.kind {
+			FieldKind::Relational(relation) => Some(relation.into()),
+			FieldKind::Related(_) | FieldKind::Reference(_) | FieldKind::ModelReference(_) => self
+				.models
+				.resolve_related_field(key.into(), model.into())
+				.map(Into::into),
+			FieldKind::Value => None,
+		};
+		Some(PathField {
+			model,
+			name: needle.into(),
+			type_: _R(field.type_).into(),
+			relation,
+		})
+	}
+	/// Checks the operators of a domain, and the arity of its logical operators.
+	///
+	/// Paths are only checked inside subdomains unless `top_level_paths` is set,
+	/// since the paths of most domains are already checked as mapped strings.
+	pub fn diagnose_domain(
+		&self,
+		list: Node,
+		model: ModelName,
+		contents: &str,
+		top_level_paths: bool,
+		diagnostics: &mut Vec<DomainDiagnostic>,
+	) {
+		let terms = parse_domain(list, contents);
+		for (node, message) in arity_errors(&terms) {
+			diagnostics.push(DomainDiagnostic {
+				range: node.byte_range().map_unit(ByteOffset),
+				severity: DiagnosticSeverity::ERROR,
+				message,
+			});
+		}
+		for term in terms {
+			let Term::Leaf(leaf) = term else { continue };
+			let Some(path) = string_value(leaf.path, contents).filter(|path| !path.is_empty()) else {
+				continue;
+			};
+			if top_level_paths && !self.diagnose_domain_path(leaf.path, model, contents, diagnostics) {
+				continue;
+			}
+			let field = self.resolve_domain_path(model, path);
+			let Some(operator_node) = leaf.operator else { continue };
+			let Some(operator) = string_value(operator_node, contents) else {
+				continue;
+			};
+			let range = operator_node.byte_range().shrink(1).map_unit(ByteOffset);
+			if !OPERATORS.iter().any(|(known, _)| *known == operator) {
+				diagnostics.push(DomainDiagnostic {
+					range,
+					severity: DiagnosticSeverity::ERROR,
+					message: format!("Unknown domain operator `{operator}`"),
+				});
+				continue;
+			}
+			let Some(field) = field else { continue };
+			if !operator_applies(operator, &field.type_, field.relation.is_some()) {
+				diagnostics.push(DomainDiagnostic {
+					range,
+					severity: DiagnosticSeverity::WARNING,
+					message: format!(
+						"Operator `{operator}` does not apply to {} field `{}`",
+						field.type_, field.name
+					),
+				});
+			} else if matches!(operator, "any" | "not any")
+				&& let Some(comodel) = field.relation
+				&& let Some(subdomain) = leaf.value.and_then(domain_list)
+			{
+				self.diagnose_domain(subdomain, comodel, contents, true, diagnostics);
+			} else if matches!(operator, "=" | "!=" | "in" | "not in")
+				&& let Some(value) = leaf.value
+				&& let Some(selection) = self.selection_of(field.model, &field.name)
+			{
+				let values = match value.kind() {
+					"list" | "tuple" => value.named_children(&mut value.walk()).collect(),
+					_ => vec![value],
+				};
+				for value in values {
+					let Some(message) = string_value(value, contents)
+						.and_then(|key| diagnose_selection_value(&field.name, &selection, key))
+					else {
+						continue;
+					};
+					diagnostics.push(DomainDiagnostic {
+						range: value.byte_range().shrink(1).map_unit(ByteOffset),
+						severity: DiagnosticSeverity::ERROR,
+						message,
+					});
+				}
+			}
+		}
+	}
+	/// Reports unknown fields along a path, and returns whether the path is valid.
+	fn diagnose_domain_path(
+		&self,
+		path: Node,
+		model: ModelName,
+		contents: &str,
+		diagnostics: &mut Vec<DomainDiagnostic>,
+	) -> bool {
+		let mut range = path.byte_range().shrink(1).map_unit(ByteOffset);
+		let mut needle = &contents[range.erase()];
+		let mut model = model.into();
+		if let Err(ResolveMappedError::NonRelational) =
+			self.models.resolve_mapped(&mut model, &mut needle, Some(&mut range))
+		{
+			diagnostics.push(DomainDiagnostic {
+				range,
+				severity: DiagnosticSeverity::ERROR,
+				message: format!("`{needle}` is not a relational field"),
+			});
+			return false;
+		}
+		if needle.is_empty() || !self.models.contains_key(&model) {
+			return true;
+		}
+		if self.resolve_domain_path(model.into(), needle).is_none() {
+			diagnostics.push(DomainDiagnostic {
+				range,
+				severity: DiagnosticSeverity::ERROR,
+				message: format!("Model `{}` has no field `{needle}`", _R(model)),
+			});
+			return false;
+		}
+		true
+	}
+	/// Finds the part of a domain at `offset`, descending into subdomains.
+	pub fn domain_cursor<'tree>(
+		&self,
+		list: Node<'tree>,
+		model: ModelName,
+		contents: &str,
+		offset: usize,
+	) -> Option<DomainCursor<'tree>> {
+		self.domain_cursor_at_depth(list, model, contents, offset, 0)
+	}
+	fn domain_cursor_at_depth<'tree>(
+		&self,
+		list: Node<'tree>,
+		model: ModelName,
+		contents: &str,
+		offset: usize,
+		depth: usize,
+	) -> Option<DomainCursor<'tree>> {
+		let leaf = parse_domain(list, contents).into_iter().find_map(|term| match term {
+			Term::Leaf(leaf) if (leaf.path.parent()).is_some_and(|leaf| leaf.byte_range().contains_end(offset)) => {
+				Some(leaf)
+			}
+			_ => None,
+		})?;
+		let path = string_value(leaf.path, contents)?;
+		let path_range = leaf.path.byte_range().shrink(1);
+		if path_range.contains_end(offset) {
+			// foo.bar|.baz
+			//     ^^^ segment under the cursor
+			let relative = offset - path_range.start;
+			let start = path[..relative].rfind('.').map(|dot| dot + 1).unwrap_or(0);
+			let end = path[relative..]
+				.find('.')
+				.map(|dot| relative + dot)
+				.unwrap_or(path.len());
+			let mut model = model.into();
+			let mut prefix = &path[..start];
+			self.models.resolve_mapped(&mut model, &mut prefix, None).ok()?;
+			return Some(DomainCursor::Path {
+				model: model.into(),
+				range: (path_range.start + start..path_range.start + end).map_unit(ByteOffset),
+				depth,
+			});
+		}
+
+		let operator = leaf.operator?;
+		if operator.byte_range().contains_end(offset) {
+			return (operator.kind() == "string").then(|| DomainCursor::Operator {
+				field: self.resolve_domain_path(model, path),
+				range: operator.byte_range().shrink(1).map_unit(ByteOffset),
+			});
+		}
+
+		let value = leaf.value.filter(|value| value.byte_range().contains_end(offset))?;
+		if matches!(string_value(operator, contents), Some("any" | "not any"))
+			&& let Some(subdomain) = domain_list(value)
+		{
+			let comodel = self.resolve_domain_path(model, path)?.relation?;
+			return self.domain_cursor_at_depth(subdomain, comodel, contents, offset, depth + 1);
+		}
+		let node = match value.kind() {
+			"string" => value,
+			"list" | "tuple" => (value.named_children(&mut value.walk()))
+				.find(|item| item.kind() == "string" && item.byte_range().contains_end(offset))?,
+			_ => return None,
+		};
+		Some(DomainCursor::Value {
+			field: self.resolve_domain_path(model, path),
+			node,
+			range: node.byte_range().shrink(1).map_unit(ByteOffset),
+		})
+	}
+	/// Completes operators and selection values. Field paths are left to the caller.
+	pub fn complete_domain(&self, cursor: &DomainCursor, range: Range, items: &mut MaxVec<CompletionItem>) {
+		match cursor {
+			DomainCursor::Path { .. } => {}
+			DomainCursor::Operator { field, .. } => {
+				let operators = OPERATORS.iter().filter(|(operator, _)| {
+					field
+						.as_ref()
+						.is_none_or(|field| operator_applies(operator, &field.type_, field.relation.is_some()))
+				});
+				items.extend(operators.map(|(operator, description)| CompletionItem {
+					label: operator.to_string(),
+					kind: Some(CompletionItemKind::OPERATOR),
+					detail: Some(description.to_string()),
+					text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+						range,
+						new_text: operator.to_string(),
+					})),
+					..Default::default()
+				}));
+			}
+			DomainCursor::Value { field, .. } => {
+				let Some(field) = field.as_ref() else { return };
+				if let Some(selection) = self.selection_of(field.model, &field.name) {
+					self.complete_selection(&selection, range, items);
+				}
+			}
+		}
+	}
+	/// Describes the field or operator under a [`DomainCursor`].
+	pub fn hover_domain(&self, cursor: &DomainCursor, contents: &str, range: Range) -> anyhow::Result<Option<Hover>> {
+		match cursor {
+			DomainCursor::Path { model, range: path, .. } => {
+				let needle = &contents[path.erase()];
+				self.hover_property_name(needle, _R(*model), Some(range))
+			}
+			DomainCursor::Operator { range: operator, .. } => {
+				let operator = &contents[operator.erase()];
+				let (_, description) = some!(OPERATORS.iter().find(|(known, _)| *known == operator));
+				Ok(Some(Hover {
+					range: Some(range),
+					contents: HoverContents::Markup(MarkupContent {
+						kind: MarkupKind::Markdown,
+						value: format!("`{operator}`: {description}"),
+					}),
+				}))
+			}
+			DomainCursor::Value {
+				field: Some(field),
+				range: value,
+				..
+			} => {
+				let selection = some!(self.selection_of(field.model, &field.name));
+				Ok(self.hover_selection_value(&selection, &contents[value.erase()], range))
+			}
+			DomainCursor::Value { field: None, .. } => Ok(None),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+	use tree_sitter::Parser;
+
+	use super::{Term, arity_errors, domain_list, operator_applies, parse_domain, string_value};
+
+	fn with_domain(domain: &str, check: impl FnOnce(&str, Vec<Term>)) {
+		let mut parser = Parser::new();
+		parser.set_language(&tree_sitter_python::LANGUAGE.into()).unwrap();
+		let ast = parser.parse(domain, None).unwrap();
+		let list = ast.root_node().named_child(0).unwrap().named_child(0).unwrap();
+		let list = domain_list(list).unwrap();
+		check(domain, parse_domain(list, domain));
+	}
+
+	#[test]
+	fn test_parse_domain() {
+		with_domain(
+			"['|', ('partner_id.name', 'ilike', 'foo'), '!', ('active', '=', True), other]",
+			|contents, terms| {
+				let shapes = (terms.iter())
+					.map(|term| match term {
+						Term::Logical { operator, .. } => operator.to_string(),
+						Term::Leaf(leaf) => string_value(leaf.path, contents).unwrap().to_string(),
+						Term::Opaque(node) => contents[node.byte_range()].to_string(),
+					})
+					.collect::<Vec<_>>();
+				assert_eq!(shapes, ["|", "partner_id.name", "!", "active", "other"]);
+			},
+		);
+		with_domain("lambda self: [('user_id', '=', self.env.uid)]", |contents, terms| {
+			let [Term::Leaf(leaf)] = &terms[..] else { panic!() };
+			assert_eq!(string_value(leaf.operator.unwrap(), contents), Some("="));
+			assert_eq!(&contents[leaf.value.unwrap().byte_range()], "self.env.uid");
+		});
+	}
+
+	#[test]
+	fn test_arity_errors() {
+		let messages = |domain: &str| {
+			let mut out = vec![];
+			with_domain(domain, |_, terms| {
+				out = arity_errors(&terms).into_iter().map(|(_, message)| message).collect();
+			});
+			out
+		};
+		assert!(messages("['|', ('a', '=', 1), ('b', '=', 2), ('c', '=', 3)]").is_empty());
+		assert!(messages("['&', '!', ('a', '=', 1), '|', ('b', '=', 2), ('c', '=', 3)]").is_empty());
+		assert_eq!(messages("['|', ('a', '=', 1)]"), ["`|` expects 2 operand(s), found 1"]);
+		assert_eq!(messages("[('a', '=', 1), '!']"), ["`!` expects 1 operand(s), found 0"]);
+		assert!(messages("['|', ('a', '=', 1), *other]").is_empty());
+	}
+
+	#[test]
+	fn test_operator_applies() {
+		assert!(operator_applies("any", "Many2many", true));
+		assert!(!operator_applies("any", "Char", false));
+		assert!(!operator_applies("child_of", "Integer", false));
+		assert!(!operator_applies(">", "Boolean", false));
+		assert!(operator_applies("ilike", "Many2one", true));
+		assert!(operator_applies("in", "Selection", false));
+	}
+}
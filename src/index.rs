@@ -20,17 +20,22 @@ use xmlparser::{Token, Tokenizer};
 use crate::prelude::*;
 
 pub use crate::component::{Component, ComponentName};
+use crate::context::{ContextIndex, index_context_keys};
+use crate::csv::{csv_model, csv_records};
 use crate::model::{Model, ModelIndex, ModelLocation, ModelType};
 use crate::record::{Record, RecordMetadata};
 use crate::template::{NewTemplate, gather_templates};
 pub use crate::template::{Template, TemplateName};
+use crate::xml::ParentArch;
 
+mod cache;
 mod js;
 mod module;
 mod record;
 pub(crate) mod symbol;
 mod template;
 
+pub use cache::set_cache_dir;
 pub use js::JsQuery;
 pub use module::ModuleEntry;
 pub use record::{RecordId, SymbolMap, SymbolSet};
@@ -66,6 +71,7 @@ pub struct Index {
 	pub records: record::RecordIndex,
 	pub templates: template::TemplateIndex,
 	pub models: ModelIndex,
+	pub contexts: ContextIndex,
 	pub components: js::ComponentIndex,
 	#[default(_code = "DashMap::with_shard_amount(4)")]
 	pub widgets: DashMap<ImStr, MinLoc>,
@@ -80,6 +86,9 @@ pub struct Index {
 	pub(crate) transitive_deps_cache: DashMap<ModuleName, HashSet<ModuleName>>,
 	#[default(_code = "Cache::new(16)")]
 	pub(crate) ast_cache: Cache<PathBuf, Arc<AstCacheItem>>,
+	/// Inheriting view -> the arch its specs apply to.
+	#[default(_code = "DashMap::with_shard_amount(4)")]
+	pub(crate) parent_archs: DashMap<RecordId, ParentArch>,
 }
 
 pub struct AstCacheItem {
@@ -89,6 +98,7 @@ pub struct AstCacheItem {
 
 pub type ModuleName = Symbol<ModuleEntry>;
 
+#[derive(Serialize, Deserialize)]
 enum Output {
 	// Nothing,
 	Xml {
@@ -96,9 +106,15 @@ enum Output {
 		metadata: Vec<Option<RecordMetadata>>,
 		templates: Vec<NewTemplate>,
 	},
+	Csv {
+		records: Vec<Record>,
+		metadata: Vec<Option<RecordMetadata>>,
+	},
 	Models {
 		path: PathSymbol,
 		models: Vec<Model>,
+		/// Keys read from the context, see [`index_context_keys`].
+		contexts: Vec<(ImStr, Range)>,
 	},
 	JsItems {
 		components: HashMap<ComponentName, Component>,
@@ -113,9 +129,12 @@ impl Index {
 		self.models.retain(|_, model| !model.deleted);
 
 		for mut model in self.models.iter_mut() {
-			let before = model.ancestors.len();
+			let before = model.ancestors.len() + model.delegates.len();
 			model.ancestors.retain(|key| self.models.contains_key(key));
-			if model.ancestors.len() != before {
+			model
+				.delegates
+				.retain(|delegate| self.models.contains_key(&delegate.model));
+			if model.ancestors.len() + model.delegates.len() != before {
 				model.fields = None;
 				model.methods = None;
 			}
@@ -252,6 +271,17 @@ impl Index {
 
 		self.load_module(module_name).await
 	}
+	/// How many modules the module of `path` transitively depends on, which orders modules by dependency.
+	pub fn module_depth(&self, path: &Path) -> usize {
+		(self.find_module_of(path))
+			.map(|module| self.resolve_transitive_dependencies(module).len())
+			.unwrap_or(0)
+	}
+	/// Indexes the models declared in `path`, see [`ModelIndex::append`].
+	pub fn append_models(&self, path: PathSymbol, replace: bool, models: &[Model]) {
+		(self.models.module_depths).insert(path, self.module_depth(&path.to_path()));
+		self.models.append(path, replace, models);
+	}
 	/// Resolve all transitive dependencies for a module
 	pub fn resolve_transitive_dependencies(&self, module: ModuleName) -> HashSet<ModuleName> {
 		// Check cache first
@@ -568,6 +598,8 @@ impl Index {
 		}
 
 		let mut outputs = tokio::task::JoinSet::new();
+		// module -> (root, module dir, updated cache, whether it should be written)
+		let mut caches = HashMap::new();
 		for (module_key, root) in modules.into_iter().zip(roots.into_iter()) {
 			trace!("{} depends on {}", _R(module_name), _R(module_key));
 			let root_display = root.key().to_string_lossy();
@@ -581,6 +613,7 @@ impl Index {
 
 			info!("{} depends on {}", _R(module_name), _R(module_key));
 			let module_dir = Path::new(&*root_display).join(&module.path);
+			let mut cached = cache::ModuleCache::read(root.key(), module_key);
 			if let Ok(xmls) = globwalk::glob_builder(format!("{}/**/*.xml", module_dir.display()))
 				.file_type(FileType::FILE | FileType::SYMLINK)
 				.follow_links(true)
@@ -588,7 +621,23 @@ impl Index {
 			{
 				for xml in xmls {
 					let Ok(xml) = xml else { continue };
-					outputs.spawn(add_root_xml(root_key, xml.path().to_path_buf(), module_key));
+					let path = xml.path().to_path_buf();
+					let entry = cached.take(&module_dir, &path);
+					let parse = add_root_xml(root_key, path.clone(), module_key);
+					outputs.spawn(cache::load(module_key, path, entry, parse));
+				}
+			}
+			if let Ok(csvs) = globwalk::glob_builder(format!("{}/**/*.csv", module_dir.display()))
+				.file_type(FileType::FILE | FileType::SYMLINK)
+				.follow_links(true)
+				.build()
+			{
+				for csv in csvs {
+					let Ok(csv) = csv else { continue };
+					let path = csv.path().to_path_buf();
+					let entry = cached.take(&module_dir, &path);
+					let parse = add_root_csv(root_key, path.clone(), module_key);
+					outputs.spawn(cache::load(module_key, path, entry, parse));
 				}
 			}
 			if let Ok(pys) = globwalk::glob_builder(format!("{}/**/*.py", module_dir.display()))
@@ -605,7 +654,9 @@ impl Index {
 						}
 					};
 					let path = py.path().to_path_buf();
-					outputs.spawn(add_root_py(root_key, path));
+					let entry = cached.take(&module_dir, &path);
+					let parse = add_root_py(root_key, path.clone());
+					outputs.spawn(cache::load(module_key, path, entry, parse));
 				}
 			}
 			if let Ok(scripts) = globwalk::glob_builder(format!("{}/**/*.js", module_dir.display()))
@@ -616,14 +667,20 @@ impl Index {
 				for js in scripts {
 					let Ok(js) = js else { continue };
 					let path = js.path().to_path_buf();
-					outputs.spawn(js::add_root_js(root_key, path));
+					let entry = cached.take(&module_dir, &path);
+					let parse = js::add_root_js(root_key, path.clone());
+					outputs.spawn(cache::load(module_key, path, entry, parse));
 				}
 			}
+			// leftover entries belong to files that no longer exist
+			let dirty = !cached.is_empty();
+			let root = root.key().clone();
+			caches.insert(module_key, (root, module_dir, cache::ModuleCache::default(), dirty));
 		}
 
 		while let Some(res) = outputs.join_next().await {
-			let outputs = match res {
-				Ok(Ok(out)) => out,
+			let loaded = match res {
+				Ok(Ok(loaded)) => loaded,
 				Ok(Err(err)) => {
 					warn!("task failed: {err}");
 					continue;
@@ -633,7 +690,11 @@ impl Index {
 					continue;
 				}
 			};
-			match outputs {
+			if let Some((_, module_dir, cache, dirty)) = caches.get_mut(&loaded.module) {
+				*dirty |= loaded.fresh;
+				cache.insert(module_dir, &loaded.path, loaded.entry);
+			}
+			match loaded.output {
 				Output::Xml {
 					records,
 					metadata,
@@ -642,8 +703,12 @@ impl Index {
 					self.records.append(records.into_iter().zip(metadata.into_iter()));
 					self.templates.append(templates);
 				}
-				Output::Models { path, models } => {
-					self.models.append(path, false, &models);
+				Output::Csv { records, metadata } => {
+					self.records.append(records.into_iter().zip(metadata));
+				}
+				Output::Models { path, models, contexts } => {
+					self.append_models(path, false, &models);
+					self.contexts.append(path, false, &contexts);
 				}
 				Output::JsItems {
 					components,
@@ -665,6 +730,12 @@ impl Index {
 			}
 		}
 
+		for (module_key, (root, _, cache, dirty)) in caches {
+			if dirty && let Err(err) = cache.write(&root, module_key) {
+				warn!("could not write cache for {}: {err}", _R(module_key));
+			}
+		}
+
 		// After loading all requested modules, check for auto_install modules
 		let mut all_loaded_modules = HashSet::new();
 		for root in self.roots.iter() {
@@ -1079,6 +1150,20 @@ async fn add_root_xml(root: Spur, path: PathBuf, module_name: ModuleName) -> any
 	})
 }
 
+async fn add_root_csv(root: Spur, path: PathBuf, module_name: ModuleName) -> anyhow::Result<Output> {
+	let path_uri = PathSymbol::strip_root(root, &path);
+	let contents = ok!(
+		tokio::fs::read_to_string(&path).await,
+		"Could not read {}",
+		path.display()
+	);
+	let rope = Rope::from_str(&contents);
+	let (records, metadata) = csv_records(&contents, csv_model(&path), module_name, path_uri, rope.slice(..))
+		.into_iter()
+		.unzip();
+	Ok(Output::Csv { records, metadata })
+}
+
 #[rustfmt::skip]
 query! {
 	#[derive(Debug, PartialEq, Eq)]
@@ -1105,7 +1190,8 @@ async fn add_root_py(root: Spur, path: PathBuf) -> anyhow::Result<Output> {
 
 	let path = PathSymbol::strip_root(root, &path);
 	let models = index_models(&contents)?;
-	Ok(Output::Models { path, models })
+	let contexts = index_context_keys(&contents)?;
+	Ok(Output::Models { path, models, contexts })
 }
 
 pub fn index_models(contents: &str) -> anyhow::Result<Vec<Model>> {
@@ -1124,6 +1210,7 @@ pub fn index_models(contents: &str) -> anyhow::Result<Vec<Model>> {
 		byte_range: ByteRange,
 		name: Option<&'a str>,
 		inherits: Vec<&'a str>,
+		delegates: Vec<(&'a str, &'a str)>,
 	}
 	let mut matches = cursor.matches(query, ast.root_node(), contents.as_bytes());
 	while let Some(match_) = matches.next() {
@@ -1140,6 +1227,7 @@ pub fn index_models(contents: &str) -> anyhow::Result<Vec<Model>> {
 			range: model_node.range(),
 			byte_range: model_node.byte_range().map_unit(ByteOffset),
 			inherits: vec![],
+			delegates: delegate_fields(model_node, contents),
 		});
 		match &contents[capture.byte_range()] {
 			"_name" => {
@@ -1192,8 +1280,15 @@ pub fn index_models(contents: &str) -> anyhow::Result<Vec<Model>> {
 						continue;
 					}
 					let key = pair.python_nth_named_child::<0>().expect("key");
-					if key.kind() == "string" {
-						model.inherits.push(&contents[key.byte_range().shrink(1)]);
+					let Some(value) = pair.child_by_field_name("value") else {
+						continue;
+					};
+					if key.kind() == "string" && value.kind() == "string" {
+						let parent = &contents[key.byte_range().shrink(1)];
+						let field = &contents[value.byte_range().shrink(1)];
+						if !model.delegates.contains(&(parent, field)) {
+							model.delegates.push((parent, field));
+						}
 					}
 				}
 			}
@@ -1213,12 +1308,16 @@ pub fn index_models(contents: &str) -> anyhow::Result<Vec<Model>> {
 			}
 		}
 		let inherits = model.inherits.into_iter().map(Into::into).collect::<Vec<_>>();
+		let delegates = (model.delegates.into_iter())
+			.map(|(parent, field)| (parent.into(), field.into()))
+			.collect::<Vec<_>>();
 		let range = span_conv(model.range);
 		let byte_range = model.byte_range;
 		match (inherits.is_empty(), model.name) {
 			(false, None) => Some(Model {
 				range,
 				byte_range,
+				delegates,
 				// if _name is not defined and _inherit = [A, B, C], A is considered the inherit base by default
 				type_: ModelType::Inherit(inherits),
 			}),
@@ -1228,6 +1327,7 @@ pub fn index_models(contents: &str) -> anyhow::Result<Vec<Model>> {
 					Some(Model {
 						range,
 						byte_range,
+						delegates,
 						type_: ModelType::Inherit(inherits),
 					})
 				} else {
@@ -1238,6 +1338,7 @@ pub fn index_models(contents: &str) -> anyhow::Result<Vec<Model>> {
 						},
 						range,
 						byte_range,
+						delegates,
 					})
 				}
 			}
@@ -1246,6 +1347,62 @@ pub fn index_models(contents: &str) -> anyhow::Result<Vec<Model>> {
 	Ok(models.collect())
 }
 
+/// `(comodel, field)` pairs of the Many2one fields declared with `delegate=True` in `class`.
+fn delegate_fields<'a>(class: Node, contents: &'a str) -> Vec<(&'a str, &'a str)> {
+	let Some(body) = class.child_by_field_name("body") else {
+		return vec![];
+	};
+	let mut delegates = vec![];
+	for stmt in body.named_children(&mut body.walk()) {
+		let Some(assignment) = stmt.named_child(0).filter(|_| stmt.kind() == "expression_statement") else {
+			continue;
+		};
+		let (Some(field), Some(call)) = (
+			assignment.child_by_field_name("left"),
+			assignment.child_by_field_name("right"),
+		) else {
+			continue;
+		};
+		let Some(function) = call.child_by_field_name("function").filter(|_| call.kind() == "call") else {
+			continue;
+		};
+		if !matches!(&contents[function.byte_range()], "fields.Many2one" | "Many2one") {
+			continue;
+		}
+		let Some(arguments) = call.child_by_field_name("arguments") else {
+			continue;
+		};
+		let mut comodel = None;
+		let mut delegate = false;
+		for argument in arguments.named_children(&mut arguments.walk()) {
+			match argument.kind() {
+				"string" if comodel.is_none() => comodel = Some(&contents[argument.byte_range().shrink(1)]),
+				"keyword_argument" => {
+					let (Some(key), Some(value)) = (
+						argument.child_by_field_name("name"),
+						argument.child_by_field_name("value"),
+					) else {
+						continue;
+					};
+					match (&contents[key.byte_range()], value.kind()) {
+						("comodel_name", "string") => comodel = Some(&contents[value.byte_range().shrink(1)]),
+						("delegate", "true") => delegate = true,
+						_ => {}
+					}
+				}
+				_ => {}
+			}
+		}
+		if delegate
+			&& let Some(comodel) = comodel
+			&& field.kind() == "identifier"
+		{
+			delegates.push((comodel, &contents[field.byte_range()]));
+		}
+	}
+	delegates
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::index::{_I, Index, Interner, ModelQuery, ModuleEntry};
@@ -1827,6 +1984,7 @@ mod tests {
 			},
 			range: Default::default(),
 			byte_range: Default::default(),
+			delegates: vec![],
 		}];
 		index.models.append(path, false, &models);
 
@@ -1979,6 +2137,54 @@ class TransifexCodeTranslation(models.Model):
 		));
 	}
 
+	#[test]
+	fn test_index_delegates() {
+		let models = index_models(
+			r#"
+class Users(models.Model):
+    _name = "res.users"
+    _inherits = {"res.partner": "partner_id"}
+
+class Product(models.Model):
+    _inherit = "product.product"
+
+    template_id = fields.Many2one("product.template", delegate=True)
+    other_id = fields.Many2one(comodel_name="res.company", delegate=True)
+    plain_id = fields.Many2one("res.currency")
+"#,
+		)
+		.unwrap();
+		let mut models = (models.into_iter())
+			.map(|model| {
+				let name = match model.type_ {
+					super::ModelType::Base { name, ancestors } => {
+						assert!(ancestors.is_empty(), "delegates are not ancestors");
+						name
+					}
+					super::ModelType::Inherit(inherits) => inherits[0].clone(),
+				};
+				let delegates = (model.delegates.iter())
+					.map(|(parent, field)| format!("{parent}:{field}"))
+					.collect::<Vec<_>>();
+				(name.to_string(), delegates)
+			})
+			.collect::<Vec<_>>();
+		models.sort();
+		assert_eq!(
+			models,
+			[
+				(
+					"product.product".to_string(),
+					vec![
+						"product.template:template_id".to_string(),
+						"res.company:other_id".to_string()
+					]
+				),
+				("res.users".to_string(), vec!["res.partner:partner_id".to_string()]),
+			]
+		);
+	}
+
 	#[test]
 	fn test_depends_manifest_parsing() {
 		use crate::test_utils;
@@ -0,0 +1,283 @@
+//! On-disk cache of the outputs of indexing each file, so that only files that changed since
+//! the last startup need to be parsed again.
+//!
+//! Each module is cached in its own file under a directory specific to its root, mapping
+//! paths relative to the module to their outputs along with the modification time and
+//! content hash of the file at the time. Interned symbols are stored as the strings they
+//! stand for, and the entire cache is discarded when written by a different build per [`GITVER`]
+//! or with a different [`FORMAT_VERSION`]. Entries that still fail to deserialize are parsed again.
+//!
+//! The cache directory can be overridden with `ODOO_LSP_CACHE_DIR`, and disabled by setting it to
+//! an empty value. It is always disabled in unit tests, see also [`set_cache_dir`].
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{BuildHasher, DefaultHasher, Hash, Hasher, RandomState};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
+
+use crate::GITVER;
+use crate::prelude::*;
+
+use super::{ModuleName, Output};
+
+/// Must be bumped whenever [`Output`] or anything it contains changes shape, or its parsers change
+/// what they produce. Dirty builds share the same [`GITVER`] and rely on this to invalidate their caches.
+const FORMAT_VERSION: u32 = 1;
+
+static CACHE_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct ModuleCache {
+	version: String,
+	#[serde(default)]
+	format: u32,
+	/// path relative to the module -> entry
+	files: HashMap<String, Entry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct Entry {
+	/// Nanoseconds since the Unix epoch.
+	mtime: u64,
+	hash: u64,
+	output: serde_json::Value,
+}
+
+/// The result of loading a single file, whether from the cache or not.
+pub(super) struct Loaded {
+	pub module: ModuleName,
+	pub path: PathBuf,
+	pub entry: Entry,
+	pub output: Output,
+	/// Whether `entry` differs from what was cached.
+	pub fresh: bool,
+}
+
+impl Default for ModuleCache {
+	fn default() -> Self {
+		Self {
+			version: GITVER.to_string(),
+			format: FORMAT_VERSION,
+			files: HashMap::new(),
+		}
+	}
+}
+
+impl ModuleCache {
+	/// Reads the cache of `module` under `root`, or an empty one if it is missing or outdated.
+	pub fn read(root: &Path, module: ModuleName) -> Self {
+		let Some(path) = cache_path(root, module) else {
+			return Self::default();
+		};
+		let Ok(file) = std::fs::File::open(&path) else {
+			return Self::default();
+		};
+		match serde_json::from_reader::<_, Self>(std::io::BufReader::new(file)) {
+			Ok(cache) if cache.version == GITVER && cache.format == FORMAT_VERSION => cache,
+			Ok(_) => {
+				debug!("discarding outdated cache at {}", path.display());
+				Self::default()
+			}
+			Err(err) => {
+				warn!("could not read cache at {}: {err}", path.display());
+				Self::default()
+			}
+		}
+	}
+	pub fn write(&self, root: &Path, module: ModuleName) -> anyhow::Result<()> {
+		let Some(path) = cache_path(root, module) else {
+			return Ok(());
+		};
+		let dir = ok!(path.parent(), "cache path has no parent");
+		std::fs::create_dir_all(dir)?;
+		// write to a temporary file first so that readers never see a partial cache,
+		// named uniquely as other servers may be writing the same cache
+		let temp = path.with_extension(format!("json.{}.tmp", unique_suffix()));
+		let file = std::fs::File::create(&temp)?;
+		let written = serde_json::to_writer(BufWriter::new(file), self)
+			.map_err(anyhow::Error::from)
+			.and_then(|()| Ok(std::fs::rename(&temp, &path)?));
+		if written.is_err() {
+			_ = std::fs::remove_file(&temp);
+		}
+		written
+	}
+	pub fn take(&mut self, module_dir: &Path, path: &Path) -> Option<Entry> {
+		let key = path.strip_prefix(module_dir).ok()?;
+		self.files.remove(key.to_str()?)
+	}
+	pub fn insert(&mut self, module_dir: &Path, path: &Path, entry: Entry) {
+		if let Ok(key) = path.strip_prefix(module_dir)
+			&& let Some(key) = key.to_str()
+		{
+			self.files.insert(key.to_string(), entry);
+		}
+	}
+	pub fn is_empty(&self) -> bool {
+		self.files.is_empty()
+	}
+}
+
+/// Loads the output of `path` from `cached` if the file has not changed since, otherwise from `parse`.
+pub(super) async fn load(
+	module: ModuleName,
+	path: PathBuf,
+	cached: Option<Entry>,
+	parse: impl Future<Output = anyhow::Result<Output>>,
+) -> anyhow::Result<Loaded> {
+	let metadata = tokio::fs::metadata(&path).await?;
+	let mtime = metadata.modified()?.duration_since(UNIX_EPOCH)?.as_nanos() as u64;
+	let mut hash = None;
+	if let Some(mut entry) = cached {
+		let unchanged = entry.mtime == mtime || {
+			let contents = tokio::fs::read(&path).await?;
+			let content_hash = content_hash(&contents);
+			hash = Some(content_hash);
+			entry.hash == content_hash
+		};
+		if unchanged {
+			match serde_json::from_value(entry.output.clone()) {
+				Ok(output) => {
+					let fresh = entry.mtime != mtime;
+					entry.mtime = mtime;
+					return Ok(Loaded {
+						module,
+						path,
+						entry,
+						output,
+						fresh,
+					});
+				}
+				Err(err) => debug!("invalid cache entry for {}: {err}", path.display()),
+			}
+		}
+	}
+
+	let output = parse.await?;
+	let hash = match hash {
+		Some(hash) => hash,
+		None => content_hash(&tokio::fs::read(&path).await?),
+	};
+	let entry = Entry {
+		mtime,
+		hash,
+		output: serde_json::to_value(&output)?,
+	};
+	Ok(Loaded {
+		module,
+		path,
+		entry,
+		output,
+		fresh: true,
+	})
+}
+
+/// Only needs to be stable across runs of the same build, see [`ModuleCache::read`].
+fn content_hash(contents: &[u8]) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	contents.hash(&mut hasher);
+	hasher.finish()
+}
+
+fn cache_path(root: &Path, module: ModuleName) -> Option<PathBuf> {
+	let mut hasher = DefaultHasher::new();
+	root.hash(&mut hasher);
+	let root = format!("{:016x}", hasher.finish());
+	Some(cache_dir()?.join(root).join(format!("{}.json", _R(module))))
+}
+
+/// Distinguishes files written by concurrent processes.
+fn unique_suffix() -> String {
+	format!("{}-{:016x}", std::process::id(), RandomState::new().hash_one(0u8))
+}
+
+/// Overrides the cache directory for the rest of the process, or disables the cache if `None`.
+///
+/// Has no effect once the cache has been used.
+pub fn set_cache_dir(dir: Option<PathBuf>) {
+	_ = CACHE_DIR.set(dir);
+}
+
+fn cache_dir() -> Option<&'static Path> {
+	CACHE_DIR.get_or_init(default_cache_dir).as_deref()
+}
+
+fn default_cache_dir() -> Option<PathBuf> {
+	if cfg!(test) {
+		return None;
+	}
+	if let Some(dir) = std::env::var_os("ODOO_LSP_CACHE_DIR") {
+		return (!dir.is_empty()).then(|| PathBuf::from(dir));
+	}
+	#[cfg(windows)]
+	let base = std::env::var_os("LOCALAPPDATA").map(PathBuf::from);
+	#[cfg(not(windows))]
+	let base = std::env::var_os("XDG_CACHE_HOME")
+		.map(PathBuf::from)
+		.or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")));
+	Some(base?.join("odoo-lsp"))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::PathBuf;
+
+	use pretty_assertions::assert_eq;
+
+	use crate::index::{_I, Output, PathSymbol};
+	use crate::model::{Model, ModelType};
+	use crate::utils::ByteOffset;
+
+	use super::{ModuleCache, load, unique_suffix};
+
+	#[tokio::test]
+	async fn test_load_from_cache() {
+		let dir = std::env::temp_dir().join(format!("odoo_lsp_test_cache_{}", unique_suffix()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("models.py");
+		std::fs::write(&path, "class Foo(models.Model):\n    _name = 'foo'\n").unwrap();
+		let module = _I("foo").into();
+
+		let parse = async {
+			Ok(Output::Models {
+				path: PathSymbol::strip_root(_I(dir.to_string_lossy()), &path),
+				models: vec![Model {
+					type_: ModelType::Base {
+						name: "foo".into(),
+						ancestors: vec![],
+					},
+					range: Default::default(),
+					byte_range: ByteOffset(0)..ByteOffset(43),
+					delegates: vec![],
+				}],
+				contexts: vec![],
+			})
+		};
+		let loaded = load(module, path.clone(), None, parse).await.unwrap();
+		assert!(loaded.fresh);
+
+		let mut cache = ModuleCache::default();
+		cache.insert(&dir, &path, loaded.entry);
+		let cache = serde_json::to_string(&cache).unwrap();
+		let mut cache = serde_json::from_str::<ModuleCache>(&cache).unwrap();
+		let entry = cache.take(&dir, &path);
+		assert!(entry.is_some());
+		assert!(cache.is_empty());
+
+		let parse = async { Err(anyhow::anyhow!("unchanged files should not be parsed")) };
+		let loaded = load(module, PathBuf::from(&path), entry, parse).await.unwrap();
+		assert!(!loaded.fresh);
+		let Output::Models {
+			path: symbol, models, ..
+		} = loaded.output
+		else {
+			panic!("expected models");
+		};
+		assert_eq!(symbol.to_path(), path);
+		assert_eq!(models[0].type_.splay(), (Some("foo"), &[][..]));
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
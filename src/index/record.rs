@@ -1,5 +1,6 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::RwLock;
-use std::{collections::HashSet, fmt::Debug, hash::Hash, marker::PhantomData};
+use std::{fmt::Debug, hash::Hash, marker::PhantomData};
 
 use dashmap::{DashMap, mapref::one::Ref};
 use derive_more::{Deref, DerefMut};
@@ -8,6 +9,7 @@ use smart_default::SmartDefault;
 
 use crate::prelude::*;
 
+use crate::csv::model_xml_id;
 use crate::record::RecordMetadata;
 use crate::{ImStr, format_loc};
 use crate::{model::ModelName, record::Record};
@@ -25,6 +27,9 @@ pub struct RecordIndex {
 	by_inherit_id: DashMap<RecordId, HashSet<RecordId>>,
 	#[default(_code = "DashMap::with_shard_amount(4)")]
 	views_by_model: DashMap<ModelName, HashSet<RecordId>>,
+	/// unqualified `model_*` XML ID -> access rules
+	#[default(_code = "DashMap::with_shard_amount(4)")]
+	access_by_model: DashMap<ImStr, HashSet<RecordId>>,
 	/// unqualified XML ID -> RecordID
 	pub by_prefix: RwLock<RecordPrefixTrie>,
 }
@@ -69,6 +74,9 @@ impl RecordIndex {
 					.or_insert_with(Default::default)
 					.insert(qualified_id);
 			}
+			Some(RecordMetadata::Access(model_id)) => {
+				self.access_by_model.entry(model_id).or_default().insert(qualified_id);
+			}
 			Some(RecordMetadata::View(_)) | None => {}
 		}
 		self.inner.insert(qualified_id, record);
@@ -80,6 +88,59 @@ impl RecordIndex {
 			self.insert(id.into(), record, meta, Some(&mut prefix));
 		}
 	}
+	/// Moves the record `old` to the unqualified ID `new_id` within the same module,
+	/// keeping all secondary indices (including records inheriting from it) in sync.
+	///
+	/// Returns the new qualified ID, or None if `old` is not indexed.
+	pub fn rename(&self, old: RecordId, new_id: &str) -> Option<RecordId> {
+		let (_, mut record) = self.inner.remove(&old)?;
+		let new = RecordId::from(_I(format!("{}.{new_id}", _R(record.module))));
+		if let Some(model) = &record.model
+			&& let Some(mut ids) = self.by_model.get_mut(model)
+		{
+			ids.remove(&old);
+			ids.insert(new);
+		}
+		if let Some(inherit_id) = &record.inherit_id
+			&& let Some(mut ids) = self.by_inherit_id.get_mut(inherit_id)
+		{
+			ids.remove(&old);
+			ids.insert(new);
+		}
+		for mut ids in self.views_by_model.iter_mut() {
+			if ids.remove(&old) {
+				ids.insert(new);
+			}
+		}
+		for mut ids in self.access_by_model.iter_mut() {
+			if ids.remove(&old) {
+				ids.insert(new);
+			}
+		}
+		if let Some((_, children)) = self.by_inherit_id.remove(&old) {
+			for child in &children {
+				if let Some(mut child) = self.inner.get_mut(child) {
+					child.inherit_id = Some(new);
+				}
+			}
+			self.by_inherit_id.insert(new, children);
+		}
+		if let Ok(mut by_prefix) = self.by_prefix.write() {
+			if let Some(ids) = by_prefix.get_mut(record.id.as_bytes()) {
+				ids.remove(&old);
+				if ids.is_empty() {
+					by_prefix.remove(record.id.as_bytes());
+				}
+			}
+			by_prefix
+				.entry(ImStr::from(new_id))
+				.or_insert_with(Default::default)
+				.insert(new);
+		}
+		record.id = ImStr::from(new_id);
+		self.inner.insert(new, record);
+		Some(new)
+	}
 	pub fn by_model(&self, model: &ModelName) -> impl Iterator<Item = Ref<'_, RecordId, Record>> {
 		self.by_model
 			.get(model)
@@ -103,6 +164,11 @@ impl RecordIndex {
 			.get(view_model)
 			.is_some_and(|ids| ids.contains(record))
 	}
+	/// Whether any `ir.model.access` rule targets `model`.
+	pub fn has_access_rules(&self, model: &str) -> bool {
+		let model_id = ImStr::from(model_xml_id(model));
+		self.access_by_model.get(&model_id).is_some_and(|ids| !ids.is_empty())
+	}
 	fn resolve_references<K>(
 		&self,
 		ids: Ref<K, HashSet<Symbol<Record>>>,
@@ -141,6 +207,28 @@ impl<K, T> Default for SymbolMap<K, T> {
 	}
 }
 
+/// Serialized as a map keyed by the resolved symbols, see [`Symbol`].
+impl<K, T: Serialize> Serialize for SymbolMap<K, T> {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_map(
+			self.0
+				.iter()
+				.map(|(key, value)| (_R(Spur::try_from_usize(key).unwrap()), value)),
+		)
+	}
+}
+
+impl<'de, K, T: Deserialize<'de>> Deserialize<'de> for SymbolMap<K, T> {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let entries = HashMap::<String, T>::deserialize(deserializer)?;
+		let mut out = Self::default();
+		for (key, value) in entries {
+			out.0.insert(_I(key).into_usize(), value);
+		}
+		Ok(out)
+	}
+}
+
 impl<K> Default for SymbolSet<K> {
 	#[inline]
 	fn default() -> Self {
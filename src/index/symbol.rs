@@ -6,6 +6,7 @@ use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
 use lasso::Spur;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::Interner;
 
@@ -79,6 +80,36 @@ impl<T> Debug for Symbol<T> {
 	}
 }
 
+/// Symbols are serialized as the strings they stand for,
+/// since a [Spur] is only meaningful to the interner of the current process.
+impl<T> Serialize for Symbol<T> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(interner().resolve(&self.inner))
+	}
+}
+
+impl<'de, T> Deserialize<'de> for Symbol<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let value = String::deserialize(deserializer)?;
+		Ok(_I(value).into())
+	}
+}
+
+/// Serialized as a `(root, subpath)` pair of strings.
+impl Serialize for PathSymbol {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let interner = interner();
+		(interner.resolve(&self.0), interner.resolve(&self.1)).serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for PathSymbol {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let (root, path) = <(String, String)>::deserialize(deserializer)?;
+		Ok(PathSymbol(_I(root), _I(path)))
+	}
+}
+
 // TODO: Remove in favor of strict-type symbols
 impl<T> From<Spur> for Symbol<T> {
 	#[inline]
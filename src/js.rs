@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::sync::atomic::Ordering::Relaxed;
 
 use tower_lsp_server::ls_types::*;
@@ -9,7 +10,10 @@ use crate::prelude::*;
 use crate::backend::Backend;
 use crate::backend::Text;
 use crate::index::{_G, JsQuery};
-use crate::model::PropertyKind;
+use crate::model::{ModelName, PropertyKind};
+use crate::rename::PropertyTarget;
+use crate::semantic_tokens::{Token, TokenKind};
+use crate::symbols::{document_symbol, union_range};
 use crate::utils::{ByteOffset, MaxVec, RangeExt, span_conv};
 use tracing::instrument;
 use ts_macros::query;
@@ -360,4 +364,221 @@ impl Backend {
 
 		Ok(None)
 	}
+	pub(crate) fn js_property_target(
+		&self,
+		uri: &Uri,
+		position: Position,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<PropertyTarget>> {
+		let file_path = some!(uri.to_file_path());
+		let ast = self
+			.ast_map
+			.get(file_path.to_str().unwrap())
+			.ok_or_else(|| errloc!("Did not build AST for {}", uri.path().as_str()))?;
+		let ByteOffset(offset) = rope_conv(position, rope);
+		let contents = Cow::from(rope);
+
+		let query = OrmCallQuery::query();
+		let mut cursor = QueryCursor::new();
+		let mut matches = cursor.matches(query, ast.root_node(), contents.as_bytes());
+		while let Some(match_) = matches.next() {
+			let model_node = some!(match_.nodes_for_capture_index(OrmCallQuery::ModelArg as _).next());
+			let method_node = some!(match_.nodes_for_capture_index(OrmCallQuery::MethodArg as _).next());
+			let range = method_node.byte_range().shrink(1);
+			if !range.contains_end(offset) {
+				continue;
+			}
+			let model = some!(_G(&contents[model_node.byte_range().shrink(1)]));
+			return Ok(Some(PropertyTarget {
+				model: model.into(),
+				prop: ImStr::from(&contents[range.clone()]),
+				range: range.map_unit(ByteOffset),
+			}));
+		}
+
+		Ok(None)
+	}
+	/// Ranges of `this.orm.call` method arguments targeting `prop` on any of `models`.
+	pub(crate) fn js_property_edits(
+		&self,
+		rope: RopeSlice<'_>,
+		models: &HashSet<ModelName>,
+		prop: &str,
+	) -> Vec<ByteRange> {
+		let contents = Cow::from(rope);
+		if !contents.contains(prop) {
+			return vec![];
+		}
+		let mut parser = Parser::new();
+		parser
+			.set_language(&tree_sitter_javascript::LANGUAGE.into())
+			.expect("bug: failed to init js parser");
+		let Some(ast) = parser.parse(contents.as_bytes(), None) else {
+			return vec![];
+		};
+
+		let mut out = vec![];
+		let query = OrmCallQuery::query();
+		let mut cursor = QueryCursor::new();
+		let mut matches = cursor.matches(query, ast.root_node(), contents.as_bytes());
+		while let Some(match_) = matches.next() {
+			let model_node = match_.nodes_for_capture_index(OrmCallQuery::ModelArg as _).next();
+			let method_node = match_.nodes_for_capture_index(OrmCallQuery::MethodArg as _).next();
+			let (Some(model_node), Some(method_node)) = (model_node, method_node) else {
+				continue;
+			};
+			let range = method_node.byte_range().shrink(1);
+			if &contents[range.clone()] != prop {
+				continue;
+			}
+			let model = _G(&contents[model_node.byte_range().shrink(1)]);
+			if model.is_some_and(|model| models.contains(&ModelName::from(model))) {
+				out.push(range.map_unit(ByteOffset));
+			}
+		}
+		out
+	}
+	/// Lists Owl components with their props, followed by registry additions.
+	pub(crate) fn js_document_symbols(
+		&self,
+		uri: &Uri,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<Vec<DocumentSymbol>>> {
+		let file_path = some!(uri.to_file_path());
+		let ast = self
+			.ast_map
+			.get(file_path.to_str().unwrap())
+			.ok_or_else(|| errloc!("Did not build AST for {}", uri.path().as_str()))?;
+		let contents = Cow::from(rope);
+
+		// A component may be declared across several statements, e.g. `Foo.props = {..}`.
+		let mut components = Vec::<(&str, Range, Range, Vec<DocumentSymbol>)>::new();
+		let mut registry_items = vec![];
+		let query = JsQuery::query();
+		let mut cursor = QueryCursor::new();
+		let mut matches = cursor.matches(query, ast.root_node(), contents.as_bytes());
+		while let Some(match_) = matches.next() {
+			if let Some(item) = match_.nodes_for_capture_index(JsQuery::RegistryItem as _).next() {
+				let registry = match_.nodes_for_capture_index(JsQuery::Registry as _).next();
+				let call = item.parent().and_then(|args| args.parent()).unwrap_or(item);
+				// registry.category("fields") -> fields
+				let category = registry.map(|registry| {
+					let category = (registry.child_by_field_name("arguments"))
+						.and_then(|args| args.named_child(0))
+						.filter(|category| category.kind() == "string");
+					match category {
+						Some(category) => &contents[category.byte_range().shrink(1)],
+						None => &contents[registry.byte_range()],
+					}
+				});
+				registry_items.push(document_symbol(
+					&contents[item.byte_range().shrink(1)],
+					category.map(|category| format!("registry: {category}")),
+					SymbolKind::KEY,
+					span_conv(call.range()),
+					span_conv(item.range()),
+					vec![],
+				));
+				continue;
+			}
+
+			let Some(name) = match_.nodes_for_capture_index(JsQuery::Name as _).next() else {
+				continue;
+			};
+			// either the class declaration or the statement assigning to one of its members
+			let mut declaration = name.parent().unwrap_or(name);
+			while declaration.kind() != "class_declaration"
+				&& declaration.kind() != "expression_statement"
+				&& let Some(parent) = declaration.parent()
+			{
+				declaration = parent;
+			}
+			let props = match_.nodes_for_capture_index(JsQuery::Prop as _).map(|prop| {
+				let prop_name = match prop.kind() {
+					"string" => &contents[prop.byte_range().shrink(1)],
+					_ => &contents[prop.byte_range()],
+				};
+				document_symbol(
+					prop_name,
+					None,
+					SymbolKind::PROPERTY,
+					span_conv(prop.parent().unwrap_or(prop).range()),
+					span_conv(prop.range()),
+					vec![],
+				)
+			});
+			let name_str = &contents[name.byte_range()];
+			let range = span_conv(declaration.range());
+			match components.iter_mut().find(|(component, ..)| *component == name_str) {
+				Some((_, component_range, _, component_props)) => {
+					*component_range = union_range(*component_range, range);
+					component_props.extend(props);
+				}
+				None => components.push((name_str, range, span_conv(name.range()), props.collect())),
+			}
+		}
+
+		let mut symbols = components
+			.into_iter()
+			.map(|(name, range, selection_range, props)| {
+				document_symbol(name, None, SymbolKind::CLASS, range, selection_range, props)
+			})
+			.collect::<Vec<_>>();
+		symbols.append(&mut registry_items);
+		Ok(Some(symbols))
+	}
+	/// Model and method names passed to `this.orm.call`.
+	pub(crate) fn js_semantic_tokens(&self, uri: &Uri, rope: RopeSlice<'_>) -> anyhow::Result<Vec<Token>> {
+		let file_path = ok!(uri.to_file_path(), "Not a file: {}", uri.as_str());
+		let ast = self
+			.ast_map
+			.get(file_path.to_str().unwrap())
+			.ok_or_else(|| errloc!("Did not build AST for {}", uri.path().as_str()))?;
+		let contents = Cow::from(rope);
+
+		let mut tokens = vec![];
+		let query = OrmCallQuery::query();
+		let mut cursor = QueryCursor::new();
+		let mut matches = cursor.matches(query, ast.root_node(), contents.as_bytes());
+		while let Some(match_) = matches.next() {
+			let mut model_arg_node = None;
+			let mut method_arg_node = None;
+
+			for capture in match_.captures {
+				match OrmCallQuery::from(capture.index) {
+					Some(OrmCallQuery::ModelArg) => {
+						model_arg_node = Some(capture.node);
+					}
+					Some(OrmCallQuery::MethodArg) => {
+						method_arg_node = Some(capture.node);
+					}
+					_ => {}
+				}
+			}
+
+			let Some(model_node) = model_arg_node else {
+				continue;
+			};
+			let range = model_node.byte_range().shrink(1);
+			let model = _G(&contents[range.clone()])
+				.map(ModelName::from)
+				.filter(|model| self.index.models.contains_key(model));
+			tokens.push(Token {
+				range: range.map_unit(ByteOffset),
+				kind: TokenKind::Model,
+				resolved: model.is_some(),
+			});
+			if let Some(method_node) = method_arg_node {
+				let range = method_node.byte_range().shrink(1);
+				let resolved =
+					model.is_some_and(|model| self.index.has_property(model, &contents[range.clone()], false));
+				tokens.push(Token {
+					range: range.map_unit(ByteOffset),
+					kind: TokenKind::Method,
+					resolved,
+				});
+			}
+		}
+		Ok(tokens)
+	}
 }
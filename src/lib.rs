@@ -10,11 +10,23 @@ pub use str::ImStr;
 
 pub mod analyze;
 pub mod backend;
+pub mod call_hierarchy;
+pub mod check;
+pub mod code_lens;
 pub mod component;
+pub mod context;
+pub mod csv;
+pub mod domain;
+pub mod manifest;
 pub mod model;
 pub mod record;
+pub mod rename;
+pub mod selection;
+pub mod semantic_tokens;
 pub mod server;
+pub mod symbols;
 pub mod template;
+pub mod type_hierarchy;
 
 mod js;
 pub mod python;
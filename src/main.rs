@@ -130,6 +130,7 @@ fn main() {
 				Ok(Interner::report_usage())
 			})
 			.custom_method("odoo-lsp/inspect-type", Backend::debug_inspect_type)
+			.custom_method("odoo-lsp/view-arch", Backend::view_arch)
 			.finish();
 
 		let service = tower::ServiceBuilder::new()
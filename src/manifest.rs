@@ -0,0 +1,411 @@
+//! Paths listed by the `data`, `demo` and `assets` entries of `__manifest__.py`.
+//!
+//! Data files are relative to the module directory, while assets are globs relative to the addons
+//! directory, i.e. starting with the name of a module. Besides plain globs, a bundle may list directives:
+//!
+//! ```python
+//! 'assets': {
+//!     'web.assets_backend': [
+//!         'my_module/static/src/**/*.js',
+//!         ('include', 'web._assets_helpers'),
+//!         ('remove', 'web/static/src/legacy/**/*'),
+//!         ('after', 'web/static/src/core/l10n/**/*', 'my_module/static/src/l10n.js'),
+//!     ],
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use fomat_macros::fomat;
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::domain::string_value;
+use crate::index::{Index, ModuleName};
+
+/// Manifest keys whose values are lists of data files.
+const DATA_KEYS: [&str; 2] = ["data", "demo"];
+
+/// Bundle files listed in hovers before the rest are elided.
+const HOVER_FILES: usize = 20;
+
+/// How an asset is added to or removed from a bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Directive {
+	Append,
+	Prepend,
+	Before,
+	After,
+	Replace,
+	Remove,
+	Include,
+}
+
+/// An entry of an `assets` bundle.
+pub struct Asset<'tree> {
+	pub directive: Directive,
+	/// The glob positioning `before`, `after` and `replace` directives.
+	pub target: Option<Node<'tree>>,
+	/// A glob, or the name of a bundle for `include` directives.
+	pub path: Node<'tree>,
+}
+
+pub struct Bundle<'tree> {
+	pub name: Node<'tree>,
+	pub assets: Vec<Asset<'tree>>,
+}
+
+/// The path-bearing entries of a manifest.
+pub struct Manifest<'tree> {
+	pub dictionary: Node<'tree>,
+	/// The first `data` key, if any.
+	pub data_key: Option<Node<'tree>>,
+	/// Strings listed under `data` and `demo`.
+	pub data: Vec<Node<'tree>>,
+	pub bundles: Vec<Bundle<'tree>>,
+}
+
+/// What a string under the cursor in a manifest refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestPath {
+	/// A file relative to the module directory.
+	Data,
+	/// A glob relative to the addons directory.
+	Asset,
+	/// The name of a bundle.
+	Bundle,
+}
+
+impl Directive {
+	fn from_str(directive: &str) -> Option<Self> {
+		Some(match directive {
+			"append" => Self::Append,
+			"prepend" => Self::Prepend,
+			"before" => Self::Before,
+			"after" => Self::After,
+			"replace" => Self::Replace,
+			"remove" => Self::Remove,
+			"include" => Self::Include,
+			_ => return None,
+		})
+	}
+}
+
+impl<'tree> Manifest<'tree> {
+	/// Parses the dictionary of a manifest rooted at `root`.
+	pub fn parse(root: Node<'tree>, contents: &str) -> Option<Self> {
+		let dictionary = (root.named_children(&mut root.walk()))
+			.find(|stmt| stmt.kind() == "expression_statement")?
+			.named_child(0)
+			.filter(|dictionary| dictionary.kind() == "dictionary")?;
+		let mut manifest = Manifest {
+			dictionary,
+			data_key: None,
+			data: vec![],
+			bundles: vec![],
+		};
+		for pair in dictionary.named_children(&mut dictionary.walk()) {
+			let (Some(key), Some(value)) = (pair.child_by_field_name("key"), pair.child_by_field_name("value")) else {
+				continue;
+			};
+			match string_value(key, contents) {
+				Some(name) if DATA_KEYS.contains(&name) && value.kind() == "list" => {
+					if name == "data" {
+						manifest.data_key.get_or_insert(key);
+					}
+					let strings = value.named_children(&mut value.walk());
+					manifest.data.extend(strings.filter(|string| string.kind() == "string"));
+				}
+				Some("assets") if value.kind() == "dictionary" => {
+					for bundle in value.named_children(&mut value.walk()) {
+						let (Some(name), Some(assets)) =
+							(bundle.child_by_field_name("key"), bundle.child_by_field_name("value"))
+						else {
+							continue;
+						};
+						if name.kind() != "string" || assets.kind() != "list" {
+							continue;
+						}
+						let assets = (assets.named_children(&mut assets.walk()))
+							.filter_map(|asset| parse_asset(asset, contents))
+							.collect();
+						manifest.bundles.push(Bundle { name, assets });
+					}
+				}
+				_ => {}
+			}
+		}
+		Some(manifest)
+	}
+	/// The string under `offset`, along with what it refers to.
+	pub fn path_at(&self, offset: usize) -> Option<(Node<'tree>, ManifestPath)> {
+		if let Some(data) = self.data.iter().find(|data| data.byte_range().contains_end(offset)) {
+			return Some((*data, ManifestPath::Data));
+		}
+		for bundle in &self.bundles {
+			if bundle.name.byte_range().contains_end(offset) {
+				return Some((bundle.name, ManifestPath::Bundle));
+			}
+			for asset in &bundle.assets {
+				if asset.path.byte_range().contains_end(offset) {
+					let kind = match asset.directive {
+						Directive::Include => ManifestPath::Bundle,
+						_ => ManifestPath::Asset,
+					};
+					return Some((asset.path, kind));
+				}
+				if let Some(target) = asset.target
+					&& target.byte_range().contains_end(offset)
+				{
+					return Some((target, ManifestPath::Asset));
+				}
+			}
+		}
+		None
+	}
+}
+
+fn parse_asset<'tree>(asset: Node<'tree>, contents: &str) -> Option<Asset<'tree>> {
+	match asset.kind() {
+		"string" => Some(Asset {
+			directive: Directive::Append,
+			target: None,
+			path: asset,
+		}),
+		"tuple" => {
+			let mut items = asset.named_children(&mut asset.walk());
+			let directive = Directive::from_str(string_value(items.next()?, contents)?)?;
+			let mut target = None;
+			if matches!(directive, Directive::Before | Directive::After | Directive::Replace) {
+				target = Some(items.next().filter(|target| target.kind() == "string")?);
+			}
+			let path = items.next().filter(|path| path.kind() == "string")?;
+			Some(Asset {
+				directive,
+				target,
+				path,
+			})
+		}
+		_ => None,
+	}
+}
+
+/// Whether `path` has any glob metacharacters.
+pub fn is_glob(path: &str) -> bool {
+	path.contains(['*', '?', '['])
+}
+
+/// Files under `dir` matched by `pattern`, sorted.
+pub fn expand_glob(dir: &Path, pattern: &str) -> Vec<PathBuf> {
+	if !is_glob(pattern) {
+		let path = dir.join(pattern);
+		return if path.is_file() { vec![path] } else { vec![] };
+	}
+	let Ok(walker) = globwalk::GlobWalkerBuilder::new(dir, pattern)
+		.file_type(globwalk::FileType::FILE)
+		.build()
+	else {
+		return vec![];
+	};
+	let mut files = walker.flatten().map(|entry| entry.into_path()).collect::<Vec<_>>();
+	files.sort();
+	files
+}
+
+impl Index {
+	/// The absolute path to the directory of `module`.
+	pub fn module_dir(&self, module: ModuleName) -> Option<PathBuf> {
+		for root in self.roots.iter() {
+			if let Some(entry) = root.get(&module) {
+				return Some(root.key().join(entry.path.as_str()));
+			}
+		}
+		None
+	}
+	/// Splits an asset glob into the directory of its module and the remaining pattern.
+	pub fn resolve_asset<'a>(&self, asset: &'a str) -> Option<(PathBuf, &'a str)> {
+		let (module, rest) = asset.trim_start_matches('/').split_once('/')?;
+		let module = _G(module)?;
+		Some((self.module_dir(module.into())?, rest))
+	}
+	/// Files matched by an asset glob, e.g. `web/static/src/**/*.js`.
+	pub fn asset_files(&self, asset: &str) -> Vec<PathBuf> {
+		match self.resolve_asset(asset) {
+			Some((dir, pattern)) => expand_glob(&dir, pattern),
+			None => vec![],
+		}
+	}
+	/// Files contributed to `bundle` by the manifests of all modules, with directives applied.
+	///
+	/// Modules are visited in no particular order, so the result is not the load order of the bundle.
+	pub fn bundle_files(&self, bundle: &str) -> Vec<PathBuf> {
+		let mut files = vec![];
+		self.expand_bundle(bundle, &mut files, &mut vec![]);
+		files
+	}
+	fn expand_bundle(&self, bundle: &str, files: &mut Vec<PathBuf>, seen: &mut Vec<String>) {
+		if seen.iter().any(|seen| seen == bundle) {
+			return;
+		}
+		seen.push(bundle.to_string());
+		let manifests = (self.roots.iter())
+			.flat_map(|root| {
+				(root.values())
+					.map(|entry| root.key().join(entry.path.as_str()).join("__manifest__.py"))
+					.collect::<Vec<_>>()
+			})
+			.collect::<Vec<_>>();
+		let mut parser = Parser::new();
+		if parser.set_language(&tree_sitter_python::LANGUAGE.into()).is_err() {
+			return;
+		}
+		for manifest in manifests {
+			let Ok(contents) = test_utils::fs::read_to_string(&manifest) else {
+				continue;
+			};
+			let Some(ast) = parser.parse(contents.as_bytes(), None) else {
+				continue;
+			};
+			let Some(manifest) = Manifest::parse(ast.root_node(), &contents) else {
+				continue;
+			};
+			for entry in &manifest.bundles {
+				if string_value(entry.name, &contents) == Some(bundle) {
+					self.expand_assets(&entry.assets, &contents, files, seen);
+				}
+			}
+		}
+	}
+	/// Applies the `assets` of a single bundle entry onto `files`.
+	pub fn expand_assets(&self, assets: &[Asset], contents: &str, files: &mut Vec<PathBuf>, seen: &mut Vec<String>) {
+		for asset in assets {
+			let Some(path) = string_value(asset.path, contents) else {
+				continue;
+			};
+			if asset.directive == Directive::Include {
+				self.expand_bundle(path, files, seen);
+				continue;
+			}
+			let matched = self.asset_files(path);
+			let target = (asset.target)
+				.and_then(|target| string_value(target, contents))
+				.map(|target| self.asset_files(target))
+				.unwrap_or_default();
+			let position = |files: &[PathBuf], last: bool| {
+				let mut positions = files.iter().enumerate().filter(|(_, file)| target.contains(file));
+				let position = if last { positions.last() } else { positions.next() };
+				position.map(|(idx, _)| idx)
+			};
+			let at = match asset.directive {
+				Directive::Append => files.len(),
+				Directive::Prepend => 0,
+				Directive::Before | Directive::Replace => position(files, false).unwrap_or(files.len()),
+				Directive::After => position(files, true).map(|idx| idx + 1).unwrap_or(files.len()),
+				Directive::Remove => {
+					files.retain(|file| !matched.contains(file));
+					continue;
+				}
+				Directive::Include => unreachable!(),
+			};
+			let matched = (matched.into_iter())
+				.filter(|file| !files.contains(file))
+				.collect::<Vec<_>>();
+			files.splice(at..at, matched);
+			if asset.directive == Directive::Replace {
+				files.retain(|file| !target.contains(file));
+			}
+		}
+	}
+	/// Describes the files matched by an asset glob or a whole bundle.
+	pub fn hover_asset_files(&self, title: &str, files: &[PathBuf], range: Range) -> Option<Hover> {
+		let listed = files.iter().take(HOVER_FILES).map(|file| {
+			let module = self.find_module_of(file);
+			let relative = module.and_then(|module| {
+				Some(
+					file.strip_prefix(self.module_dir(module)?.parent()?)
+						.ok()?
+						.to_path_buf(),
+				)
+			});
+			relative.unwrap_or_else(|| file.clone())
+		});
+		let value = fomat! {
+			"`" (title) "`: " (files.len()) " file" if files.len() != 1 { "s" } "\n"
+			for file in listed { "\n- `" (file.display()) "`" }
+			if files.len() > HOVER_FILES { "\n- ..." }
+		};
+		Some(Hover {
+			contents: HoverContents::Markup(MarkupContent {
+				kind: MarkupKind::Markdown,
+				value,
+			}),
+			range: Some(range),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use tree_sitter::Parser;
+
+	use super::{Directive, Manifest, ManifestPath};
+	use crate::domain::string_value;
+
+	#[test]
+	fn test_parse_manifest_paths() {
+		let mut parser = Parser::new();
+		parser.set_language(&tree_sitter_python::LANGUAGE.into()).unwrap();
+		let contents = r#"{
+    "name": "foo",
+    "data": ["security/ir.model.access.csv", "views/foo.xml"],
+    "demo": ["demo/foo.xml"],
+    "assets": {
+        "web.assets_backend": [
+            "foo/static/src/**/*.js",
+            ("include", "web._assets_helpers"),
+            ("after", "web/static/src/a.js", "foo/static/src/b.js"),
+            ("unknown", "foo/static/src/c.js"),
+        ],
+    },
+}"#;
+		let ast = parser.parse(contents, None).unwrap();
+		let manifest = Manifest::parse(ast.root_node(), contents).unwrap();
+		let data = (manifest.data.iter())
+			.map(|data| string_value(*data, contents).unwrap())
+			.collect::<Vec<_>>();
+		assert_eq!(data, ["security/ir.model.access.csv", "views/foo.xml", "demo/foo.xml"]);
+		assert_eq!(string_value(manifest.data_key.unwrap(), contents), Some("data"));
+
+		let [bundle] = &manifest.bundles[..] else { panic!() };
+		assert_eq!(string_value(bundle.name, contents), Some("web.assets_backend"));
+		let assets = (bundle.assets.iter())
+			.map(|asset| {
+				let target = asset.target.and_then(|target| string_value(target, contents));
+				(asset.directive, target, string_value(asset.path, contents).unwrap())
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(
+			assets,
+			[
+				(Directive::Append, None, "foo/static/src/**/*.js"),
+				(Directive::Include, None, "web._assets_helpers"),
+				(Directive::After, Some("web/static/src/a.js"), "foo/static/src/b.js"),
+			]
+		);
+
+		let offset = contents.find("views/foo").unwrap();
+		assert_eq!(manifest.path_at(offset).map(|(_, kind)| kind), Some(ManifestPath::Data));
+		let offset = contents.find("web._assets").unwrap();
+		assert_eq!(
+			manifest.path_at(offset).map(|(_, kind)| kind),
+			Some(ManifestPath::Bundle)
+		);
+		let offset = contents.find("web/static/src/a").unwrap();
+		assert_eq!(
+			manifest.path_at(offset).map(|(_, kind)| kind),
+			Some(ManifestPath::Asset)
+		);
+		assert!(manifest.path_at(contents.find("name").unwrap()).is_none());
+	}
+}
@@ -1,8 +1,8 @@
 //! Stores the state of Odoo [models][ModelEntry].
 
 use std::borrow::Cow;
-use std::collections::HashMap;
 use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::ops::Deref;
 use std::sync::Arc;
@@ -25,14 +25,16 @@ use crate::prelude::*;
 use crate::analyze::FunctionParam;
 use crate::{ImStr, errloc, format_loc, test_utils};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Model {
 	pub type_: ModelType,
 	pub range: Range,
 	pub byte_range: ByteRange,
+	/// `(parent model, link field)` pairs declared by `_inherits` or `delegate=True` fields.
+	pub delegates: Vec<(ImStr, ImStr)>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ModelType {
 	Base { name: ImStr, ancestors: Vec<ImStr> },
 	Inherit(Vec<ImStr>),
@@ -61,6 +63,15 @@ pub struct ModelIndex {
 	#[default(_code = "DashMap::with_shard_amount(4)")]
 	inner: DashMap<ModelName, ModelEntry>,
 	pub by_prefix: RwLock<Trie<ImStr, ModelName>>,
+	/// Python file -> how many modules its module transitively depends on,
+	/// so that the declarations of a model are merged in module dependency order.
+	#[default(_code = "DashMap::with_shard_amount(4)")]
+	pub(crate) module_depths: DashMap<PathSymbol, usize>,
+	/// Models standing for any of several models, see [`ModelIndex::union_model`].
+	///
+	/// Kept apart from the models themselves, so that they are never listed as such.
+	#[default(_code = "DashMap::with_shard_amount(4)")]
+	unions: DashMap<ModelName, ModelEntry>,
 }
 
 pub type ModelName = Symbol<ModelEntry>;
@@ -70,6 +81,7 @@ pub struct ModelEntry {
 	pub base: Option<ModelLocation>,
 	pub descendants: Vec<ModelLocation>,
 	pub ancestors: Vec<ModelName>,
+	pub delegates: Vec<Delegate>,
 	pub fields: Option<HashMap<Symbol<Field>, Arc<Field>>>,
 	pub methods: Option<HashMap<Symbol<Method>, Arc<Method>>>,
 	pub properties_by_prefix: qp_trie::Trie<&'static [u8], PropertyKind>,
@@ -77,12 +89,39 @@ pub struct ModelEntry {
 	pub deleted: bool,
 }
 
+/// A parent model whose fields are readable through a Many2one, i.e. delegation inheritance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delegate {
+	pub model: ModelName,
+	/// The Many2one field linking to the parent record.
+	pub field: ImStr,
+}
+
+/// Keys of a selection field, as declared by its `selection` and `selection_add` across modules.
+#[derive(Clone, Debug, Default)]
+pub struct Selection {
+	pub options: Vec<SelectionOption>,
+	/// Files declaring keys through a method or any other expression, in which case `options` is incomplete.
+	pub computed_in: Vec<PathSymbol>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SelectionOption {
+	pub value: ImStr,
+	pub label: Option<ImStr>,
+	pub location: MinLoc,
+}
+
 #[derive(Clone, Debug)]
 pub enum FieldKind {
 	Value,
 	/// x2many, many2one model
 	Relational(Spur),
 	Related(ImStr),
+	/// `fields.Reference`, with the models allowed by its static selection
+	Reference(Box<[ImStr]>),
+	/// `fields.Many2oneReference`, with the name of the field holding the model
+	ModelReference(ImStr),
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -103,6 +142,10 @@ pub struct Field {
 	pub type_: Spur,
 	pub location: TrackedMinLoc,
 	pub help: Option<ImStr>,
+	/// Set if this field is provided by a parent model through delegation.
+	pub delegate: Option<Delegate>,
+	/// Set for selection fields.
+	pub selection: Option<Selection>,
 }
 
 #[derive(Debug, SmartDefault)]
@@ -150,10 +193,14 @@ impl Field {
 			type_,
 			location,
 			help,
+			delegate,
+			selection,
 		} = other;
 		debug!("TODO Field inheritance location {location:?}");
 		match &mut self_.kind {
-			FieldKind::Value | FieldKind::Related(_) => self_.kind = kind.clone(),
+			FieldKind::Value | FieldKind::Related(_) | FieldKind::Reference(_) | FieldKind::ModelReference(_) => {
+				self_.kind = kind.clone()
+			}
 			FieldKind::Relational(_) => {}
 		}
 		self_.type_ = *type_;
@@ -161,6 +208,25 @@ impl Field {
 		if let Some(help) = help {
 			self_.help = Some(help.clone());
 		}
+		self_.delegate.clone_from(delegate);
+		if let Some(selection) = selection {
+			let self_selection = self_.selection.get_or_insert_default();
+			for option in &selection.options {
+				let known = (self_selection.options.iter()).any(|known| {
+					known.value == option.value
+						&& known.location.path == option.location.path
+						&& known.location.range == option.location.range
+				});
+				if !known {
+					self_selection.options.push(option.clone());
+				}
+			}
+			for path in &selection.computed_in {
+				if !self_selection.computed_in.contains(path) {
+					self_selection.computed_in.push(*path);
+				}
+			}
+		}
 		self_
 	}
 }
@@ -385,6 +451,7 @@ impl ModelIndex {
 						entry
 							.ancestors
 							.extend(ancestors.iter().map(|sym| ModelName::from(_I(sym))));
+						entry.add_delegates(&item.delegates);
 					} else if let Some(base) = entry.base.as_ref() {
 						warn!(
 							"Conflicting bases for {}:\nfirst={base}\n  new={}",
@@ -421,6 +488,7 @@ impl ModelIndex {
 						entry
 							.ancestors
 							.extend(ancestors.iter().map(|sym| ModelName::from(_I(sym))));
+						entry.add_delegates(&item.delegates);
 					}
 				}
 			}
@@ -429,6 +497,7 @@ impl ModelIndex {
 	/// Recursively traverses this model's definitions and populates all of its properties, including fields and methods.
 	///
 	/// `locations_filter` can be set to an empty slice to search all definitions, or a list of specific paths to search.
+	/// Unions built by [`ModelIndex::union_model`] are also returned as they are.
 	///
 	/// Deadlocks if an entry in [`ModelIndex`] is being held with the key `model`.
 	pub fn populate_properties<'model>(
@@ -439,7 +508,7 @@ impl ModelIndex {
 		let mut entry = match self.try_get_mut(&model) {
 			TryResult::Present(entry) => entry,
 			TryResult::Absent => {
-				return None;
+				return self.unions.get_mut(&model);
 			}
 			TryResult::Locked => {
 				cold_path();
@@ -450,7 +519,11 @@ impl ModelIndex {
 			return Some(entry);
 		}
 		let t0 = std::time::Instant::now();
-		let locations = entry.base.iter().chain(&entry.descendants).cloned().collect::<Vec<_>>();
+		let mut locations = entry.base.iter().chain(&entry.descendants).cloned().collect::<Vec<_>>();
+		// a module depends on strictly fewer modules than its dependents, which extend it
+		locations.sort_by_key(|ModelLocation(location, _)| {
+			(self.module_depths.get(&location.path)).map_or(0, |depth| *depth)
+		});
 
 		let query = ModelProperties::query();
 		let iter = locations
@@ -483,10 +556,12 @@ impl ModelIndex {
 					let mut kwarg = None::<Kwargs>;
 					let mut help = None;
 					let mut related = None;
+					let mut model_field = None;
 					enum Kwargs {
 						ComodelName,
 						Help,
 						Related,
+						ModelField,
 					}
 					let mut method_name = None;
 					let mut method_body = None;
@@ -497,7 +572,6 @@ impl ModelIndex {
 							}
 							Some(ModelProperties::Type) => {
 								type_ = Some(capture.node.byte_range());
-								// TODO: fields.Reference
 								is_relational = matches!(
 									&contents[capture.node.byte_range()],
 									"One2many" | "Many2one" | "Many2many"
@@ -512,6 +586,7 @@ impl ModelIndex {
 								"comodel_name" if is_relational => kwarg = Some(Kwargs::ComodelName),
 								"help" => kwarg = Some(Kwargs::Help),
 								"related" => kwarg = Some(Kwargs::Related),
+								"model_field" => kwarg = Some(Kwargs::ModelField),
 								_ => kwarg = None,
 							},
 							Some(ModelProperties::Value) => match kwarg {
@@ -530,6 +605,11 @@ impl ModelIndex {
 										related = Some(capture.node.byte_range().shrink(1));
 									}
 								}
+								Some(Kwargs::ModelField) => {
+									if capture.node.kind() == "string" {
+										model_field = Some(capture.node.byte_range().shrink(1));
+									}
+								}
 								None => {}
 							},
 							Some(ModelProperties::Method) => {
@@ -545,10 +625,13 @@ impl ModelIndex {
 						&& let Some(type_) = type_
 					{
 						let range = span_conv(field.range());
+						let arguments = (field.parent())
+							.and_then(|assignment| assignment.child_by_field_name("right"))
+							.and_then(|call| call.child_by_field_name("arguments"));
 						let field_str = &contents[field.byte_range()];
 						let field = _I(field_str);
 						let type_ = &contents[type_];
-						let location = MinLoc {
+						let location: TrackedMinLoc = MinLoc {
 							path: location.path,
 							range,
 						}
@@ -560,12 +643,30 @@ impl ModelIndex {
 							FieldKind::Relational(relation)
 						} else if let Some(related) = related {
 							FieldKind::Related(contents[related].into())
+						} else if type_ == "Reference" {
+							let models = arguments.map(|arguments| reference_selection(arguments, &contents));
+							let models = models.into_iter().flatten();
+							FieldKind::Reference(
+								models
+									.map(|model| contents[model.byte_range().shrink(1)].into())
+									.collect(),
+							)
+						} else if type_ == "Many2oneReference"
+							&& let Some(model_field) = model_field
+						{
+							FieldKind::ModelReference(contents[model_field].into())
 						} else {
 							if is_relational {
 								debug!("is_relational but no relation found: field={field_str} type={type_}");
 							}
 							FieldKind::Value
 						};
+						let selection = match arguments {
+							Some(arguments) if type_ == "Selection" => {
+								Some(parse_selection(arguments, &contents, location.path))
+							}
+							_ => None,
+						};
 						let type_ = _I(type_);
 						fields.push((
 							field,
@@ -574,6 +675,8 @@ impl ModelIndex {
 								type_,
 								location,
 								help,
+								delegate: None,
+								selection,
 							},
 						))
 					}
@@ -603,6 +706,7 @@ impl ModelIndex {
 			});
 
 		let ancestors = entry.ancestors.to_vec();
+		let delegates = entry.delegates.clone();
 		let mut out_fields = entry.fields.take().unwrap_or_default();
 		let mut out_methods = entry.methods.take().unwrap_or_default();
 		let mut properties_set = core::mem::take(&mut entry.properties_by_prefix);
@@ -614,13 +718,20 @@ impl ModelIndex {
 				.map(|filter| filter.to_path())
 				.collect::<Vec<_>>();
 
+			let stale = |path: &PathSymbol| (locations_filter.iter()).any(|filter| path.to_path().starts_with(filter));
 			for (_, field) in out_fields.iter_mut() {
-				if locations_filter
-					.iter()
-					.any(|filter| field.location.path.to_path().starts_with(filter))
-				{
+				if stale(&field.location.path) {
 					Arc::make_mut(field).location.active = false;
 				}
+				// keys are added again by the declarations being updated
+				let has_stale_keys = field.selection.as_ref().is_some_and(|selection| {
+					(selection.options.iter()).any(|option| stale(&option.location.path))
+						|| selection.computed_in.iter().any(&stale)
+				});
+				if has_stale_keys && let Some(selection) = Arc::make_mut(field).selection.as_mut() {
+					selection.options.retain(|option| !stale(&option.location.path));
+					selection.computed_in.retain(|path| !stale(path));
+				}
 			}
 
 			for (_, method) in out_methods.iter_mut() {
@@ -706,6 +817,34 @@ impl ModelIndex {
 			properties_set.insert(_R(key).as_bytes(), PropertyKind::Method);
 		}
 
+		// delegated fields are only visible if not declared on the model itself, and methods are never delegated
+		for delegate in delegates {
+			if delegate.model == model {
+				continue;
+			}
+			let parent_fields = match self.populate_properties(delegate.model, locations_filter) {
+				Some(entry) => (entry.fields.iter().flatten())
+					.map(|(name, field)| (*name, field.clone()))
+					.collect::<Vec<_>>(),
+				None => continue,
+			};
+			for (name, mut field) in parent_fields {
+				let Entry::Vacant(empty) = out_fields.entry(name) else {
+					continue;
+				};
+				let related = matches!(field.kind, FieldKind::Related(_))
+					.then(|| self.resolve_related_field(name, delegate.model.into()))
+					.flatten();
+				let field_ = Arc::make_mut(&mut field);
+				if let Some(relation) = related {
+					field_.kind = FieldKind::Relational(relation);
+				}
+				field_.delegate = Some(delegate.clone());
+				empty.insert(field);
+				properties_set.insert(_R(name).as_bytes(), PropertyKind::Field);
+			}
+		}
+
 		if !locations_filter.is_empty() {
 			// updating done, let's delete dead locations
 			out_fields.retain(|_, field| field.location.active);
@@ -729,6 +868,51 @@ impl ModelIndex {
 		entry.properties_by_prefix = properties_set;
 		Some(entry)
 	}
+	/// Every declaration of `field` on `model`, its ancestors and its delegates.
+	///
+	/// Unlike [`Field::location`] which only tracks one declaration, this rescans each definition of these models.
+	pub fn field_declarations(&self, model: ModelName, field: &str) -> Vec<MinLoc> {
+		let mut models = vec![model];
+		let mut seen = HashSet::new();
+		let mut locations = vec![];
+		while let Some(model) = models.pop() {
+			if !seen.insert(model) {
+				continue;
+			}
+			let Some(entry) = self.get(&model) else { continue };
+			locations.extend(entry.base.iter().chain(&entry.descendants).cloned());
+			models.extend(entry.ancestors.iter().copied());
+			models.extend(entry.delegates.iter().map(|delegate| delegate.model));
+		}
+
+		let mut parser = Parser::new();
+		parser
+			.set_language(&tree_sitter_python::LANGUAGE.into())
+			.expect(format_loc!("Failed to set language"));
+		let mut out = vec![];
+		for ModelLocation(location, byte_range) in locations {
+			let Ok(contents) = test_utils::fs::read_to_string(location.path.to_path()) else {
+				continue;
+			};
+			let Some(ast) = parser.parse(&contents, None) else {
+				continue;
+			};
+			let mut cursor = QueryCursor::new();
+			cursor.set_byte_range(byte_range.erase());
+			let mut matches = cursor.matches(ModelProperties::query(), ast.root_node(), contents.as_bytes());
+			while let Some(match_) = matches.next() {
+				let declaration = (match_.nodes_for_capture_index(ModelProperties::Field as _))
+					.find(|node| &contents[node.byte_range()] == field);
+				if let Some(declaration) = declaration {
+					out.push(MinLoc {
+						path: location.path,
+						range: span_conv(declaration.range()),
+					});
+				}
+			}
+		}
+		out
+	}
 	/// Splits a mapped access expression, e.g. `foo.bar.baz`, and traverses until the expression is exhausted.
 	///
 	/// For completing a `Model.write({'foo.bar.baz': ..})`:
@@ -761,7 +945,7 @@ impl ModelIndex {
 				match field.as_ref().map(|f| &f.kind) {
 					Some(FieldKind::Relational(rel)) => resolved = Some(*rel),
 					None | Some(FieldKind::Value) => return Err(ResolveMappedError::NonRelational),
-					Some(FieldKind::Related(..)) => {
+					Some(FieldKind::Related(..) | FieldKind::Reference(..) | FieldKind::ModelReference(..)) => {
 						drop(model_entry);
 						resolved = self.resolve_related_field(_G(lhs).unwrap().into(), *model);
 					}
@@ -799,7 +983,20 @@ impl ModelIndex {
 		// It's a no-op if the fields are already populated.
 		// If a stack overflow occurs, check populate_properties.
 		let entry = self.populate_properties(model.into(), &[])?;
-		let field_entry = entry.fields.as_ref()?.get(&field)?;
+		let fields = entry.fields.as_ref()?;
+		let field_entry = fields.get(&field)?;
+		let candidates = match &field_entry.kind {
+			FieldKind::Reference(models) => Some(models.clone()),
+			// `model_field` holds the model name, a selection lists the models it may hold
+			FieldKind::ModelReference(model_field) => (_G(model_field).and_then(|key| fields.get(&key)))
+				.and_then(|model_field| model_field.selection.as_ref())
+				.map(|selection| (selection.options.iter()).map(|option| option.value.clone()).collect()),
+			_ => None,
+		};
+		if let Some(candidates) = candidates {
+			drop(entry);
+			return self.union_model(&candidates).map(Into::into);
+		}
 		let mut kind = field_entry.kind.clone();
 		let mut field_model = model;
 		if let FieldKind::Related(related) = &field_entry.kind {
@@ -831,9 +1028,147 @@ impl ModelIndex {
 		match kind {
 			FieldKind::Relational(rel) => Some(rel),
 			FieldKind::Value => None,
-			FieldKind::Related(_) => None,
+			FieldKind::Related(_) | FieldKind::Reference(_) | FieldKind::ModelReference(_) => None,
+		}
+	}
+	/// Returns a model standing for any record of `models`, i.e. one that only has the fields and methods common to all of them.
+	///
+	/// The union is cached apart from other models under the name of its candidates joined with ` | `,
+	/// and is rebuilt on every call to follow changes to its candidates. It is not rebuilt if it is currently held.
+	pub fn union_model(&self, models: &[ImStr]) -> Option<ModelName> {
+		let mut candidates = vec![];
+		for model in models {
+			let Some(model) = _G(model).map(ModelName::from) else {
+				continue;
+			};
+			if self.contains_key(&model) && !candidates.contains(&model) {
+				candidates.push(model);
+			}
+		}
+		match candidates.as_slice() {
+			[] => return None,
+			[model] => return Some(*model),
+			_ => {}
+		}
+
+		let mut fields: Option<HashMap<Symbol<Field>, Arc<Field>>> = None;
+		let mut methods: Option<HashMap<Symbol<Method>, Arc<Method>>> = None;
+		for model in &candidates {
+			let entry = self.populate_properties(*model, &[])?;
+			let empty = HashMap::new();
+			let model_fields = entry.fields.as_ref().unwrap_or(&empty);
+			match fields.as_mut() {
+				Some(fields) => fields.retain(|name, _| model_fields.contains_key(name)),
+				None => fields = Some(model_fields.clone()),
+			}
+			let empty = HashMap::new();
+			let model_methods = entry.methods.as_ref().unwrap_or(&empty);
+			match methods.as_mut() {
+				Some(methods) => methods.retain(|name, _| model_methods.contains_key(name)),
+				None => methods = Some(model_methods.clone()),
+			}
+		}
+		let fields = fields.unwrap_or_default();
+		let methods = methods.unwrap_or_default();
+		let mut properties_by_prefix = Trie::new();
+		for name in fields.keys() {
+			properties_by_prefix.insert(_R(*name).as_bytes(), PropertyKind::Field);
+		}
+		for name in methods.keys() {
+			properties_by_prefix.insert(_R(*name).as_bytes(), PropertyKind::Method);
+		}
+
+		let name = (candidates.iter())
+			.map(|model| _R(*model))
+			.collect::<Vec<_>>()
+			.join(" | ");
+		let key = ModelName::from(_I(name));
+		let entry = ModelEntry {
+			fields: Some(fields),
+			methods: Some(methods),
+			properties_by_prefix,
+			..Default::default()
+		};
+		match self.unions.try_get_mut(&key) {
+			TryResult::Present(mut union) => *union = entry,
+			TryResult::Absent => {
+				self.unions.insert(key, entry);
+			}
+			TryResult::Locked => {}
+		}
+		Some(key)
+	}
+}
+
+/// The `selection` or `selection_add` argument of a field declaration given its `arguments`,
+/// which may also be passed as the first positional argument.
+fn selection_argument<'tree>(arguments: Node<'tree>, contents: &str) -> Option<Node<'tree>> {
+	let mut positional = true;
+	for argument in arguments.named_children(&mut arguments.walk()) {
+		match argument.kind() {
+			"comment" => {}
+			"keyword_argument" => {
+				let name = argument.child_by_field_name("name");
+				if name.is_some_and(|name| matches!(&contents[name.byte_range()], "selection" | "selection_add")) {
+					return argument.child_by_field_name("value");
+				}
+				positional = false;
+			}
+			_ if positional => return Some(argument),
+			_ => {}
 		}
 	}
+	None
+}
+
+/// The model names allowed by the static selection of a `fields.Reference`,
+/// given its `arguments`, e.g. `'res.partner'` in `fields.Reference([('res.partner', "Partner")])`.
+pub fn reference_selection<'tree>(arguments: Node<'tree>, contents: &str) -> Vec<Node<'tree>> {
+	let Some(selection) = selection_argument(arguments, contents).filter(|selection| selection.kind() == "list") else {
+		return vec![];
+	};
+	(selection.named_children(&mut selection.walk()))
+		.filter(|option| option.kind() == "tuple")
+		.filter_map(|option| option.named_child(0))
+		.filter(|model| model.kind() == "string")
+		.collect()
+}
+
+/// Reads the keys of a `fields.Selection` declared in `path`, given its `arguments`.
+fn parse_selection(arguments: Node, contents: &str, path: PathSymbol) -> Selection {
+	let mut selection = Selection::default();
+	match selection_argument(arguments, contents) {
+		None => {}
+		Some(list) if list.kind() == "list" => {
+			for option in list.named_children(&mut list.walk()) {
+				let Some(value) =
+					(option.named_child(0)).filter(|value| option.kind() == "tuple" && value.kind() == "string")
+				else {
+					continue;
+				};
+				// labels are usually translated, i.e. `_("Draft")`
+				let label = (option.named_child(1))
+					.map(|label| match label.kind() {
+						"call" => (label.child_by_field_name("arguments"))
+							.and_then(|arguments| arguments.named_child(0))
+							.unwrap_or(label),
+						_ => label,
+					})
+					.filter(|label| label.kind() == "string")
+					.map(|label| ImStr::from(&contents[label.byte_range().shrink(1)]));
+				selection.options.push(SelectionOption {
+					value: contents[value.byte_range().shrink(1)].into(),
+					label,
+					location: MinLoc {
+						path,
+						range: span_conv(value.range()),
+					},
+				});
+			}
+		}
+		Some(_) => selection.computed_in.push(path),
+	}
+	selection
 }
 
 #[rustfmt::skip]
@@ -847,6 +1182,17 @@ query! {
 }
 
 impl ModelEntry {
+	fn add_delegates(&mut self, delegates: &[(ImStr, ImStr)]) {
+		for (model, field) in delegates {
+			let delegate = Delegate {
+				model: _I(model).into(),
+				field: field.clone(),
+			};
+			if !self.delegates.contains(&delegate) {
+				self.delegates.push(delegate);
+			}
+		}
+	}
 	pub fn resolve_details(&mut self) -> anyhow::Result<()> {
 		let Some(ModelLocation(loc, byte_range)) = &self.base else {
 			return Ok(());
@@ -921,9 +1267,15 @@ mod tests {
 	use std::collections::HashSet;
 	use tree_sitter::{Parser, QueryCursor, StreamingIterator, StreamingIteratorMut};
 
+	use std::path::Path;
+
+	use super::ModelIndex;
 	use crate::{
-		index::{_I, _R, ModelQuery},
-		test_utils::cases::foo::{FOO_PY, prepare_foo_index},
+		index::{_I, _R, ModelQuery, PathSymbol, index_models},
+		test_utils::{
+			self,
+			cases::foo::{FOO_PY, prepare_foo_index},
+		},
 		utils::acc_vec,
 	};
 
@@ -1004,4 +1356,53 @@ mod tests {
 		assert!(bar_fields.contains("baz"));
 		assert!(bar_fields.contains("bar"));
 	}
+
+	#[test]
+	fn test_reference_union() {
+		const PY: &str = r#"
+class Partner(models.Model):
+	_name = 'ref.partner'
+	name = fields.Char()
+	email = fields.Char()
+
+class Company(models.Model):
+	_name = 'ref.company'
+	name = fields.Char()
+	vat = fields.Char()
+
+class Message(models.Model):
+	_name = 'ref.message'
+	res_ref = fields.Reference([('ref.partner', "Partner"), ('ref.company', "Company")])
+	model_ref = fields.Reference(selection='_selection_models')
+	res_model = fields.Selection([('ref.partner', "Partner")])
+	res_id = fields.Many2oneReference(model_field='res_model')
+	any_model = fields.Char()
+	any_id = fields.Many2oneReference(model_field='any_model')
+"#;
+		if let Ok(mut fs) = test_utils::fs::TEST_FS.write() {
+			fs.insert("/addons/ref.py".into(), PY.as_bytes());
+		}
+		let index = ModelIndex::default();
+		let models = index_models(PY).unwrap();
+		index.append(
+			PathSymbol::strip_root(_I("/addons"), Path::new("/addons/ref.py")),
+			false,
+			&models,
+		);
+
+		let message = _I("ref.message");
+		let union = index.resolve_related_field(_I("res_ref").into(), message).unwrap();
+		assert_eq!(_R(union), "ref.partner | ref.company");
+		let union = index.populate_properties(union.into(), &[]).unwrap();
+		let fields = (union.fields.as_ref().unwrap().keys())
+			.map(|field| _R(*field))
+			.collect::<Vec<_>>();
+		assert_eq!(fields, ["name"]);
+		drop(union);
+
+		assert_eq!(index.resolve_related_field(_I("model_ref").into(), message), None);
+		let partner = index.resolve_related_field(_I("res_id").into(), message);
+		assert_eq!(partner.map(_R), Some("ref.partner"));
+		assert_eq!(index.resolve_related_field(_I("any_id").into(), message), None);
+	}
 }
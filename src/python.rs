@@ -12,6 +12,7 @@ use ts_macros::query;
 use crate::prelude::*;
 
 use crate::analyze::{Type, type_cache};
+use crate::context::index_context_keys;
 use crate::index::{_G, _I, _R, PathSymbol, index_models};
 use crate::model::{ModelName, ModelType};
 use crate::xml::determine_csv_xmlid_subgroup;
@@ -19,12 +20,28 @@ use crate::{backend::Backend, backend::Text};
 
 use std::collections::HashMap;
 
+mod call_hierarchy;
+mod code_lens;
 mod completions;
+mod compute;
+mod context;
+mod csv;
 mod diagnostics;
+mod domain;
+mod inlay_hints;
+mod manifest;
+mod reference;
+mod rename;
+mod selection;
+mod semantic_tokens;
+mod symbols;
+mod type_hierarchy;
 
 #[cfg(test)]
 mod tests;
 
+pub(crate) use call_hierarchy::PythonFunction;
+
 #[rustfmt::skip]
 query! {
 	PyCompletions(Request, XmlId, Mapped, MappedTarget, Depends, ReadFn, Model, Prop, ForXmlId, Scope, FieldDescriptor, FieldType, HasGroups);
@@ -400,7 +417,8 @@ impl Backend {
 		};
 		let models = index_models(&text)?;
 		let path = PathSymbol::strip_root(root, path);
-		self.index.models.append(path, true, &models);
+		self.index.append_models(path, true, &models);
+		self.index.contexts.append(path, true, &index_context_keys(&text)?);
 		for model in models {
 			match model.type_ {
 				ModelType::Base { name, ancestors } => {
@@ -582,6 +600,13 @@ impl Backend {
 		let contents = Cow::from(rope);
 		let root = some!(top_level_stmt(ast.root_node(), offset));
 
+		if let Some(location) = self.python_reference_jump_def(root, &contents, offset)? {
+			return Ok(Some(location));
+		}
+		if let Some(location) = self.python_context_jump_def(root, &contents, offset)? {
+			return Ok(Some(location));
+		}
+
 		// Parse imports from the current file
 		let imports = self.parse_imports(&contents).unwrap_or_default();
 		debug!("Parsed imports: {:?}", imports);
@@ -874,6 +899,9 @@ impl Backend {
 		let root = some!(top_level_stmt(ast.root_node(), offset));
 		let query = PyCompletions::query();
 		let contents = Cow::from(rope);
+		if let Some(references) = self.python_context_references(root, &contents, offset) {
+			return Ok(Some(references));
+		}
 		let mut cursor = tree_sitter::QueryCursor::new();
 		let path = some!(params.text_document_position.text_document.uri.to_file_path());
 		let current_module = self.index.find_module_of(&path);
@@ -982,6 +1010,17 @@ impl Backend {
 
 		let contents = Cow::from(rope);
 		let root = some!(top_level_stmt(ast.root_node(), offset));
+		if file_path.ends_with("__manifest__.py")
+			&& let Some(hover) = self.manifest_hover(ast.root_node(), &contents, offset, rope)?
+		{
+			return Ok(Some(hover));
+		}
+		if let Some(hover) = self.python_domain_hover(root, &contents, offset, rope)? {
+			return Ok(Some(hover));
+		}
+		if let Some(hover) = self.python_selection_hover(root, &contents, offset, rope)? {
+			return Ok(Some(hover));
+		}
 		let query = PyCompletions::query();
 		let mut cursor = tree_sitter::QueryCursor::new();
 		let mut this_model = ThisModel::default();
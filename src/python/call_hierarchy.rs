@@ -0,0 +1,255 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use tower_lsp_server::ls_types::Position;
+
+use crate::prelude::*;
+
+use crate::analyze::{Scope, Type, determine_scope, type_cache};
+use crate::backend::Backend;
+use crate::index::{Index, index_models};
+use crate::model::{Model, ModelName};
+
+use super::rename::{declaring_class, model_of_class};
+
+/// A Python function, as a caller of model methods.
+#[derive(Debug, Clone)]
+pub(crate) struct PythonFunction {
+	pub name: ImStr,
+	/// Set if the function is a method of a model class.
+	pub model: Option<ModelName>,
+	pub range: ByteRange,
+	pub name_range: ByteRange,
+}
+
+/// A reference to a model method from within a Python function.
+#[derive(Debug)]
+pub(crate) struct PythonCall {
+	pub caller: PythonFunction,
+	/// Range of the method name at the call site.
+	pub range: ByteRange,
+	/// Whether the method is accessed on `super()`.
+	pub via_super: bool,
+}
+
+/// A model method called from a Python function.
+#[derive(Debug)]
+pub(crate) struct PythonCallee {
+	pub model: ModelName,
+	pub method: ImStr,
+	/// Range of the method name at the call site.
+	pub range: ByteRange,
+	pub via_super: bool,
+}
+
+/// Python extensions for [`crate::call_hierarchy`].
+impl Backend {
+	/// References to `method` on any of `models` within the functions of a Python file.
+	///
+	/// References found outside of functions, e.g. in field declarations, are skipped.
+	pub(crate) fn python_method_calls(
+		&self,
+		path: &Path,
+		rope: RopeSlice<'_>,
+		models: &HashSet<ModelName>,
+		method: &str,
+	) -> Vec<PythonCall> {
+		let ranges = self.python_property_edits(path, rope, models, method);
+		if ranges.is_empty() {
+			return vec![];
+		}
+		let contents = Cow::from(rope);
+		let Some(ast) = self.python_ast(path, &contents) else {
+			return vec![];
+		};
+		let root = ast.root_node();
+		let class_models = index_models(&contents).unwrap_or_default();
+
+		let mut out = vec![];
+		for range in ranges {
+			let Some(node) = root.descendant_for_byte_range(range.start.0, range.end.0) else {
+				continue;
+			};
+			// declarations are not calls
+			if node.kind() == "identifier" && declaring_class(node).is_some() {
+				continue;
+			}
+			let Some(function) = enclosing_function(node) else {
+				continue;
+			};
+			let via_super = node
+				.parent()
+				.filter(|parent| parent.kind() == "attribute" && parent.child_by_field_name("attribute") == Some(node))
+				.and_then(|attribute| attribute.child_by_field_name("object"))
+				.is_some_and(|object| is_super_call(object, &contents));
+			out.push(PythonCall {
+				caller: python_function(function, &class_models, &contents),
+				range,
+				via_super,
+			});
+		}
+		out
+	}
+	/// Model methods called within the function starting at `offset`, resolved with the same
+	/// scope analysis as [`Index::model_of_range`].
+	pub(crate) fn python_method_callees(&self, path: &Path, rope: RopeSlice<'_>, offset: usize) -> Vec<PythonCallee> {
+		let contents = Cow::from(rope);
+		let Some(ast) = self.python_ast(path, &contents) else {
+			return vec![];
+		};
+		let root = ast.root_node();
+		let Some((self_type, fn_scope, self_param)) = determine_scope(root, &contents, offset) else {
+			return vec![];
+		};
+		let mut scope = Scope::default();
+		let self_type = match self_type {
+			Some(type_) => &contents[type_.byte_range().shrink(1)],
+			None => "",
+		};
+		scope.super_ = Some(self_param.into());
+		scope.insert(self_param.to_string(), Type::Model(self_type.into()));
+		let scope_end = fn_scope.end_byte();
+
+		let mut out = vec![];
+		Index::walk_scope(fn_scope, Some(scope), |scope, node| {
+			let entered = (self.index).build_scope(scope, node, scope_end, &contents)?;
+			if node.kind() != "call" {
+				return ControlFlow::Continue(entered);
+			}
+			let Some(function) = node
+				.child_by_field_name("function")
+				.filter(|function| function.kind() == "attribute")
+			else {
+				return ControlFlow::Continue(entered);
+			};
+			let (Some(object), Some(attribute)) = (
+				function.child_by_field_name("object"),
+				function.child_by_field_name("attribute"),
+			) else {
+				return ControlFlow::Continue(entered);
+			};
+			let Some(lhs_t) = (self.index).type_of(object, scope, &contents) else {
+				return ControlFlow::Continue(entered);
+			};
+			let lhs_t = type_cache().resolve(lhs_t);
+			let method = &contents[attribute.byte_range()];
+			if let Some(model) = (self.index).try_resolve_model(lhs_t, scope)
+				&& self.index.has_property(model, method, false)
+			{
+				out.push(PythonCallee {
+					model,
+					method: ImStr::from(method),
+					range: attribute.byte_range().map_unit(ByteOffset),
+					via_super: is_super_call(object, &contents),
+				});
+			}
+			ControlFlow::Continue(entered)
+		});
+		out
+	}
+	/// The range of the name of the function whose definition starts at `start`, e.g. a method location.
+	pub(crate) fn python_function_name_range(&self, path: &Path, start: Position) -> Option<Range> {
+		let rope = self.rope_of(path)?;
+		let rope = rope.slice(..);
+		let contents = Cow::from(rope);
+		let ast = self.python_ast(path, &contents)?;
+		let ByteOffset(offset) = rope_conv(start, rope);
+		let node = ast.root_node().descendant_for_byte_range(offset, offset)?;
+		// `def` or `async` if not the definition itself
+		let function =
+			std::iter::successors(Some(node), Node::parent).find(|node| node.kind() == "function_definition")?;
+		let name = function.child_by_field_name("name")?;
+		Some(span_conv(name.range()))
+	}
+}
+
+/// The innermost `function_definition` containing `node`.
+fn enclosing_function(node: Node) -> Option<Node> {
+	let mut current = node.parent();
+	while let Some(node) = current {
+		if node.kind() == "function_definition" {
+			return Some(node);
+		}
+		current = node.parent();
+	}
+	None
+}
+
+fn python_function(function: Node, class_models: &[Model], contents: &str) -> PythonFunction {
+	let name = function.child_by_field_name("name");
+	let model = name
+		.and_then(declaring_class)
+		.and_then(|class| model_of_class(class_models, class))
+		.and_then(_G)
+		.map(ModelName::from);
+	let range = function.byte_range().map_unit(ByteOffset);
+	PythonFunction {
+		name: ImStr::from(name.map(|name| &contents[name.byte_range()]).unwrap_or_default()),
+		model,
+		name_range: name
+			.map(|name| name.byte_range().map_unit(ByteOffset))
+			.unwrap_or(range.clone()),
+		range,
+	}
+}
+
+/// Whether `node` is a call to `super()`, with or without arguments.
+fn is_super_call(node: Node, contents: &str) -> bool {
+	node.kind() == "call"
+		&& node
+			.child_by_field_name("function")
+			.is_some_and(|function| &contents[function.byte_range()] == "super")
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+	use tree_sitter::Parser;
+
+	use super::{enclosing_function, is_super_call, python_function};
+	use crate::index::index_models;
+	use crate::prelude::*;
+
+	#[test]
+	fn test_python_function() {
+		let contents = r#"
+class Foo(models.Model):
+	_inherit = 'foo'
+
+	def action_confirm(self):
+		res = super().action_confirm()
+		return res
+
+def helper(records):
+	records.action_confirm()
+"#;
+		let mut parser = Parser::new();
+		parser.set_language(&tree_sitter_python::LANGUAGE.into()).unwrap();
+		let ast = parser.parse(contents, None).unwrap();
+		let root = ast.root_node();
+		let models = index_models(contents).unwrap();
+		_I("foo");
+
+		let call = |needle: &str| {
+			let start = contents.find(needle).unwrap() + needle.len() - "action_confirm".len();
+			root.descendant_for_byte_range(start, start + "action_confirm".len())
+				.unwrap()
+		};
+
+		let in_method = call("super().action_confirm");
+		let function = python_function(enclosing_function(in_method).unwrap(), &models, contents);
+		assert_eq!(function.name.as_str(), "action_confirm");
+		assert_eq!(function.model.map(_R), Some("foo"));
+		let object = in_method.parent().unwrap().child_by_field_name("object").unwrap();
+		assert!(is_super_call(object, contents));
+
+		let in_helper = call("records.action_confirm");
+		let function = python_function(enclosing_function(in_helper).unwrap(), &models, contents);
+		assert_eq!(function.name.as_str(), "helper");
+		assert_eq!(function.model, None);
+		let object = in_helper.parent().unwrap().child_by_field_name("object").unwrap();
+		assert!(!is_super_call(object, contents));
+	}
+}
@@ -0,0 +1,123 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::code_lens::LensTarget;
+use crate::index::index_models;
+
+use super::rename::model_of_class;
+
+/// Python extensions for [`crate::code_lens`].
+impl Backend {
+	/// Model classes of a Python file along with their fields and methods, keyed by the range of their names.
+	pub(crate) fn python_lens_targets(&self, path: &Path, rope: RopeSlice<'_>) -> Vec<(ByteRange, LensTarget)> {
+		let contents = Cow::from(rope);
+		let Some(ast) = self.python_ast(path, &contents) else {
+			return vec![];
+		};
+		let root = ast.root_node();
+		let models = index_models(&contents).unwrap_or_default();
+
+		let mut out = vec![];
+		for class in root.named_children(&mut root.walk()) {
+			if class.kind() != "class_definition" {
+				continue;
+			}
+			let Some(model) = model_of_class(&models, class).and_then(_G) else {
+				continue;
+			};
+			let model = model.into();
+			if let Some(name) = class.child_by_field_name("name") {
+				out.push((name.byte_range().map_unit(ByteOffset), LensTarget::Model(model)));
+			}
+			let Some(body) = class.child_by_field_name("body") else {
+				continue;
+			};
+			for member in body.named_children(&mut body.walk()) {
+				if let Some(field) = declared_field(member, &contents) {
+					let name = ImStr::from(&contents[field.byte_range()]);
+					out.push((field.byte_range().map_unit(ByteOffset), LensTarget::Field(model, name)));
+				} else if let Some(method) = declared_method(member) {
+					let name = ImStr::from(&contents[method.byte_range()]);
+					out.push((
+						method.byte_range().map_unit(ByteOffset),
+						LensTarget::Method(model, name),
+					));
+				}
+			}
+		}
+		out
+	}
+}
+
+/// The name of a field declared by `member`, e.g. `foo` in `foo = fields.Char()`.
+fn declared_field<'node>(member: Node<'node>, contents: &str) -> Option<Node<'node>> {
+	let assignment = (member.named_child(0)).filter(|_| member.kind() == "expression_statement")?;
+	let left = (assignment.child_by_field_name("left"))
+		.filter(|left| assignment.kind() == "assignment" && left.kind() == "identifier")?;
+	let function = (assignment.child_by_field_name("right"))
+		.filter(|right| right.kind() == "call")?
+		.child_by_field_name("function")?;
+	let object = function
+		.child_by_field_name("object")
+		.filter(|_| function.kind() == "attribute")?;
+	(&contents[object.byte_range()] == "fields").then_some(left)
+}
+
+/// The name of a method declared by `member`, with or without decorators.
+fn declared_method(member: Node) -> Option<Node> {
+	let function = match member.kind() {
+		"function_definition" => member,
+		"decorated_definition" => member
+			.child_by_field_name("definition")
+			.filter(|definition| definition.kind() == "function_definition")?,
+		_ => return None,
+	};
+	function.child_by_field_name("name")
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+	use tree_sitter::Parser;
+
+	use super::{declared_field, declared_method};
+
+	#[test]
+	fn test_declared_members() {
+		let mut parser = Parser::new();
+		parser.set_language(&tree_sitter_python::LANGUAGE.into()).unwrap();
+		let contents = r#"
+class Foo(models.Model):
+	_name = 'foo'
+
+	bar = fields.Char()
+	baz = 1
+
+	@api.depends('bar')
+	def _compute_baz(self):
+		pass
+
+	def action_confirm(self):
+		pass
+"#;
+		let ast = parser.parse(contents, None).unwrap();
+		let class = ast.root_node().named_child(0).unwrap();
+		let body = class.child_by_field_name("body").unwrap();
+		let members = body.named_children(&mut body.walk()).collect::<Vec<_>>();
+
+		let fields = (members.iter())
+			.filter_map(|member| declared_field(*member, contents))
+			.map(|field| &contents[field.byte_range()])
+			.collect::<Vec<_>>();
+		assert_eq!(fields, ["bar"]);
+
+		let methods = (members.iter())
+			.filter_map(|member| declared_method(*member))
+			.map(|method| &contents[method.byte_range()])
+			.collect::<Vec<_>>();
+		assert_eq!(methods, ["_compute_baz", "action_confirm"]);
+	}
+}
@@ -42,6 +42,20 @@ impl Backend {
 		let mut this_model = ThisModel::default();
 
 		let root = some!(top_level_stmt(ast.root_node(), offset));
+		if path.ends_with("__manifest__.py") {
+			return self.manifest_completions(&path, ast.root_node(), &contents, offset, rope, completions_limit);
+		}
+		if let Some(completions) = self.python_domain_completions(root, &contents, offset, rope, completions_limit)? {
+			return Ok(Some(completions));
+		}
+		if let Some(completions) =
+			self.python_selection_completions(root, &contents, offset, rope, completions_limit)?
+		{
+			return Ok(Some(completions));
+		}
+		if let Some(completions) = self.python_context_completions(root, &contents, offset, rope, completions_limit)? {
+			return Ok(Some(completions));
+		}
 		let mut matches = cursor.matches(query, root, contents.as_bytes());
 		while let Some(match_) = matches.next() {
 			let mut model_filter = None;
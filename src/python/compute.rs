@@ -0,0 +1,446 @@
+use std::collections::{HashMap, HashSet};
+
+use tower_lsp_server::ls_types::{Diagnostic, DiagnosticSeverity};
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::domain::string_value;
+use crate::index::index_models;
+use crate::model::ModelName;
+
+use super::rename::model_of_class;
+
+/// A field declared with `compute=` or `related=`.
+struct ComputedField<'tree> {
+	name: Node<'tree>,
+	/// The field type, e.g. `Char` in `fields.Char()`.
+	type_: Node<'tree>,
+	compute: Option<Node<'tree>>,
+	related: Option<Node<'tree>>,
+	store: bool,
+}
+
+/// A method along with the paths of its `@api.depends`, if decorated.
+struct ComputeMethod<'tree> {
+	body: Node<'tree>,
+	depends: Option<Depends<'tree>>,
+}
+
+struct Depends<'tree> {
+	paths: Vec<Node<'tree>>,
+	/// Whether every argument is a string literal, e.g. not `*FIELDS`.
+	complete: bool,
+}
+
+impl Backend {
+	/// Checks how the computed and related fields of the models declared under `root` are written.
+	pub(super) fn diagnose_computes(
+		&self,
+		root: Node,
+		contents: &str,
+		rope: RopeSlice<'_>,
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
+		let models = index_models(contents).unwrap_or_default();
+		let classes = match root.kind() {
+			"class_definition" => vec![root],
+			_ => root.named_children(&mut root.walk()).collect(),
+		};
+		let mut push = |node: Node, severity, message| {
+			let range = match node.kind() {
+				"string" => node.byte_range().shrink(1),
+				_ => node.byte_range(),
+			};
+			diagnostics.push(Diagnostic {
+				range: rope_conv(range.map_unit(ByteOffset), rope),
+				severity: Some(severity),
+				message,
+				..Default::default()
+			})
+		};
+		for class in classes {
+			let Some(model) = model_of_class(&models, class).and_then(_G) else {
+				continue;
+			};
+			let model = ModelName::from(model);
+			let Some(body) = class.child_by_field_name("body") else {
+				continue;
+			};
+			let mut fields = vec![];
+			let mut methods = HashMap::new();
+			for member in body.named_children(&mut body.walk()) {
+				if let Some(field) = computed_field(member, contents) {
+					fields.push(field);
+				} else if let Some((name, method)) = compute_method(member, contents) {
+					methods.insert(name, method);
+				}
+			}
+
+			for field in &fields {
+				let Some(related) = field.related else { continue };
+				let Some(related_type) =
+					string_value(related, contents).and_then(|path| self.related_type(model, path))
+				else {
+					continue;
+				};
+				let type_ = &contents[field.type_.byte_range()];
+				if related_type != type_ {
+					let path = &contents[related.byte_range().shrink(1)];
+					let message = format!("Related field `{path}` is a `{related_type}` field, not `{type_}`");
+					push(related, DiagnosticSeverity::ERROR, message);
+				}
+			}
+
+			let mut by_compute = HashMap::<_, Vec<_>>::new();
+			for field in &fields {
+				let Some(compute) = field.compute else { continue };
+				let method = match compute.kind() {
+					"string" => &contents[compute.byte_range().shrink(1)],
+					_ => &contents[compute.byte_range()],
+				};
+				by_compute.entry(method).or_default().push(field);
+			}
+			for (name, group) in by_compute {
+				// computes defined elsewhere are checked for existence only
+				let Some(method) = methods.get(name) else { continue };
+				let computed = (group.iter())
+					.map(|field| &contents[field.name.byte_range()])
+					.collect::<Vec<_>>();
+
+				match &method.depends {
+					None => {
+						for field in group.iter().filter(|field| field.store) {
+							let field_name = &contents[field.name.byte_range()];
+							let message =
+								format!("Stored field `{field_name}` is computed by `{name}` without `@api.depends`");
+							push(field.compute.unwrap(), DiagnosticSeverity::WARNING, message);
+						}
+					}
+					Some(depends) => {
+						for path in &depends.paths {
+							let path_name = &contents[path.byte_range().shrink(1)];
+							if computed.contains(&path_name) {
+								let message = format!("Field `{path_name}` cannot depend on itself");
+								push(*path, DiagnosticSeverity::ERROR, message);
+							}
+						}
+					}
+				}
+
+				if let Some(assigned) = assigned_fields(method.body, contents) {
+					for field in &group {
+						let field_name = &contents[field.name.byte_range()];
+						if !assigned.contains(field_name) {
+							let message = format!("`{name}` never assigns `{field_name}`");
+							push(field.compute.unwrap(), DiagnosticSeverity::WARNING, message);
+						}
+					}
+				}
+
+				// a method computing several fields is where dependencies are most easily forgotten
+				let Some(depends) = method
+					.depends
+					.as_ref()
+					.filter(|depends| depends.complete && group.len() > 1)
+				else {
+					continue;
+				};
+				let roots = (depends.paths.iter())
+					.map(|path| {
+						let path = &contents[path.byte_range().shrink(1)];
+						path.split_once('.').map(|(root, _)| root).unwrap_or(path)
+					})
+					.collect::<HashSet<_>>();
+				let mut reported = HashSet::new();
+				for read in field_reads(method.body, contents) {
+					let read_name = &contents[read.byte_range()];
+					if read_name == "id"
+						|| computed.contains(&read_name)
+						|| roots.contains(read_name)
+						|| !self.has_field(model, read_name)
+						|| !reported.insert(read_name)
+					{
+						continue;
+					}
+					let message = format!(
+						"`{name}` computes several fields and reads `{read_name}`, which is missing from its `@api.depends`"
+					);
+					push(read, DiagnosticSeverity::WARNING, message);
+				}
+			}
+		}
+	}
+	/// The type of the field at the end of a `related` chain starting from `model`.
+	fn related_type(&self, model: ModelName, path: &str) -> Option<&'static str> {
+		let mut model: Spur = model.into();
+		let mut needle = path;
+		self.index.models.resolve_mapped(&mut model, &mut needle, None).ok()?;
+		let entry = self.index.models.populate_properties(model.into(), &[])?;
+		let field = entry.fields.as_ref()?.get(&_G(needle)?)?;
+		Some(_R(field.type_))
+	}
+	fn has_field(&self, model: ModelName, field: &str) -> bool {
+		let Some(field) = _G(field) else {
+			return false;
+		};
+		let Some(entry) = self.index.models.populate_properties(model, &[]) else {
+			return false;
+		};
+		entry.fields.as_ref().is_some_and(|fields| fields.contains_key(&field))
+	}
+}
+
+/// Parses `name = fields.Type(..., compute=..., related=..., store=...)`.
+fn computed_field<'tree>(member: Node<'tree>, contents: &str) -> Option<ComputedField<'tree>> {
+	let assignment = (member.named_child(0)).filter(|_| member.kind() == "expression_statement")?;
+	let name = (assignment.child_by_field_name("left"))
+		.filter(|left| assignment.kind() == "assignment" && left.kind() == "identifier")?;
+	let call = (assignment.child_by_field_name("right")).filter(|right| right.kind() == "call")?;
+	let function = call
+		.child_by_field_name("function")
+		.filter(|function| function.kind() == "attribute")?;
+	let object = function.child_by_field_name("object")?;
+	if &contents[object.byte_range()] != "fields" {
+		return None;
+	}
+	let mut field = ComputedField {
+		name,
+		type_: function.child_by_field_name("attribute")?,
+		compute: None,
+		related: None,
+		store: false,
+	};
+	let arguments = call.child_by_field_name("arguments")?;
+	for argument in arguments.named_children(&mut arguments.walk()) {
+		let (Some(key), Some(value)) = (
+			argument.child_by_field_name("name"),
+			argument.child_by_field_name("value"),
+		) else {
+			continue;
+		};
+		match (&contents[key.byte_range()], value.kind()) {
+			("compute", "string" | "identifier") => field.compute = Some(value),
+			("related", "string") => field.related = Some(value),
+			("store", "true") => field.store = true,
+			_ => {}
+		}
+	}
+	(field.compute.is_some() || field.related.is_some()).then_some(field)
+}
+
+/// Parses a method along with its `@api.depends` decorator.
+fn compute_method<'tree>(member: Node<'tree>, contents: &'tree str) -> Option<(&'tree str, ComputeMethod<'tree>)> {
+	let (function, decorated) = match member.kind() {
+		"function_definition" => (member, None),
+		"decorated_definition" => (member.child_by_field_name("definition")?, Some(member)),
+		_ => return None,
+	};
+	let name = &contents[function.child_by_field_name("name")?.byte_range()];
+	let mut depends = None;
+	for decorator in decorated
+		.iter()
+		.flat_map(|decorated| decorated.named_children(&mut decorated.walk()))
+	{
+		let Some(call) = decorator.named_child(0).filter(|call| call.kind() == "call") else {
+			continue;
+		};
+		let Some(callee) = call.child_by_field_name("function") else {
+			continue;
+		};
+		if !matches!(&contents[callee.byte_range()], "api.depends" | "depends") {
+			continue;
+		}
+		let arguments = call.child_by_field_name("arguments")?;
+		let arguments = arguments.named_children(&mut arguments.walk()).collect::<Vec<_>>();
+		depends = Some(Depends {
+			complete: arguments.iter().all(|argument| argument.kind() == "string"),
+			paths: arguments
+				.into_iter()
+				.filter(|argument| argument.kind() == "string")
+				.collect(),
+		});
+	}
+	let method = ComputeMethod {
+		body: function.child_by_field_name("body")?,
+		depends,
+	};
+	Some((name, method))
+}
+
+/// Fields assigned on records in `body`, e.g. `rec.foo = ...`, `rec['foo'] = ...` or `rec.update({'foo': ...})`.
+///
+/// Returns [None] if some assignments cannot be known statically, e.g. when calling `super()` or another compute.
+fn assigned_fields<'a>(body: Node, contents: &'a str) -> Option<HashSet<&'a str>> {
+	let mut assigned = HashSet::new();
+	for node in PreTravel::new(body) {
+		match node.kind() {
+			"assignment" | "augmented_assignment" => {
+				let left = node.child_by_field_name("left")?;
+				let targets = match left.kind() {
+					"pattern_list" | "tuple_pattern" => left.named_children(&mut left.walk()).collect(),
+					_ => vec![left],
+				};
+				for target in targets {
+					match target.kind() {
+						"attribute" => {
+							let attribute = target.child_by_field_name("attribute")?;
+							assigned.insert(&contents[attribute.byte_range()]);
+						}
+						"subscript" => {
+							let index = target.child_by_field_name("subscript")?;
+							assigned.insert(string_value(index, contents)?);
+						}
+						_ => {}
+					}
+				}
+			}
+			"call" => {
+				let function = node.child_by_field_name("function")?;
+				let callee = match function.kind() {
+					"attribute" => &contents[function.child_by_field_name("attribute")?.byte_range()],
+					_ => &contents[function.byte_range()],
+				};
+				match callee {
+					"super" | "setattr" => return None,
+					_ if callee.starts_with("_compute") => return None,
+					"update" | "write" => {
+						let arguments = node.child_by_field_name("arguments")?;
+						let values = arguments
+							.named_child(0)
+							.filter(|values| values.kind() == "dictionary")?;
+						for pair in values.named_children(&mut values.walk()) {
+							let key = pair.child_by_field_name("key")?;
+							assigned.insert(string_value(key, contents)?);
+						}
+					}
+					_ => {}
+				}
+			}
+			_ => {}
+		}
+	}
+	Some(assigned)
+}
+
+/// Field names read on `self` or on records iterated from `self` in `body`, e.g. `bar` in `rec.bar`.
+fn field_reads<'tree>(body: Node<'tree>, contents: &str) -> Vec<Node<'tree>> {
+	let mut records = vec!["self"];
+	for node in PreTravel::new(body) {
+		if node.kind() == "for_statement"
+			&& let Some(left) = node.child_by_field_name("left")
+			&& let Some(right) = node.child_by_field_name("right")
+			&& left.kind() == "identifier"
+			&& contents[right.byte_range()].starts_with("self")
+		{
+			records.push(&contents[left.byte_range()]);
+		}
+	}
+	let mut reads = vec![];
+	for node in PreTravel::new(body) {
+		if node.kind() != "attribute" {
+			continue;
+		}
+		let (Some(object), Some(attribute)) = (
+			node.child_by_field_name("object"),
+			node.child_by_field_name("attribute"),
+		) else {
+			continue;
+		};
+		if object.kind() != "identifier" || !records.contains(&&contents[object.byte_range()]) {
+			continue;
+		}
+		let Some(parent) = node.parent() else { continue };
+		let written = matches!(parent.kind(), "assignment" | "augmented_assignment")
+			&& parent.child_by_field_name("left") == Some(node);
+		let called = parent.kind() == "call" && parent.child_by_field_name("function") == Some(node);
+		if !written && !called {
+			reads.push(attribute);
+		}
+	}
+	reads
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+	use tree_sitter::Parser;
+
+	use super::{assigned_fields, compute_method, computed_field, field_reads};
+
+	#[test]
+	fn test_compute_members() {
+		let mut parser = Parser::new();
+		parser.set_language(&tree_sitter_python::LANGUAGE.into()).unwrap();
+		let contents = r#"
+class Foo(models.Model):
+	_name = 'foo'
+
+	total = fields.Float(compute='_compute_amounts', store=True)
+	tax = fields.Float(compute='_compute_amounts')
+	partner_name = fields.Char(related='partner_id.name')
+	plain = fields.Char()
+
+	@api.depends('line_ids.amount')
+	def _compute_amounts(self):
+		for order in self:
+			order.total = sum(order.line_ids.mapped('amount')) + order.discount
+			order.update({'unrelated': False})
+
+	def _compute_delegated(self):
+		super()._compute_delegated()
+"#;
+		let ast = parser.parse(contents, None).unwrap();
+		let class = ast.root_node().named_child(0).unwrap();
+		let body = class.child_by_field_name("body").unwrap();
+		let members = body.named_children(&mut body.walk()).collect::<Vec<_>>();
+
+		let fields = (members.iter())
+			.filter_map(|member| computed_field(*member, contents))
+			.map(|field| {
+				let name = &contents[field.name.byte_range()];
+				let compute = field.compute.map(|compute| &contents[compute.byte_range()]);
+				let related = field.related.map(|related| &contents[related.byte_range()]);
+				(name, &contents[field.type_.byte_range()], compute, related, field.store)
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(
+			fields,
+			[
+				("total", "Float", Some("'_compute_amounts'"), None, true),
+				("tax", "Float", Some("'_compute_amounts'"), None, false),
+				("partner_name", "Char", None, Some("'partner_id.name'"), false),
+			]
+		);
+
+		let methods = (members.iter())
+			.filter_map(|member| compute_method(*member, contents))
+			.collect::<Vec<_>>();
+		let [(name, amounts), (_, delegated)] = &methods[..] else {
+			panic!("expected two methods");
+		};
+		assert_eq!(*name, "_compute_amounts");
+		let depends = amounts.depends.as_ref().unwrap();
+		assert!(depends.complete);
+		assert_eq!(
+			(depends.paths.iter())
+				.map(|path| &contents[path.byte_range()])
+				.collect::<Vec<_>>(),
+			["'line_ids.amount'"]
+		);
+		assert!(delegated.depends.is_none());
+
+		let mut assigned = assigned_fields(amounts.body, contents)
+			.unwrap()
+			.into_iter()
+			.collect::<Vec<_>>();
+		assigned.sort();
+		assert_eq!(assigned, ["total", "unrelated"]);
+		assert_eq!(assigned_fields(delegated.body, contents), None);
+
+		let reads = (field_reads(amounts.body, contents).into_iter())
+			.map(|read| &contents[read.byte_range()])
+			.collect::<Vec<_>>();
+		assert_eq!(reads, ["line_ids", "discount"]);
+	}
+}
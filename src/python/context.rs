@@ -0,0 +1,157 @@
+use std::ops::Range;
+use std::path::Path;
+
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::context::ContextReads;
+use crate::model::ModelName;
+
+#[rustfmt::skip]
+query! {
+	PyContexts(Target, Arguments);
+
+((call
+  (attribute
+    (_) @TARGET (identifier) @_with)
+  (argument_list) @ARGUMENTS)
+  (#eq? @_with "with_context"))
+}
+
+/// A context key under the cursor.
+struct PyContextKey<'tree> {
+	/// Range of the name of the key, excluding quotes.
+	range: Range<usize>,
+	/// The receiver of `with_context`, from which the implicit keys are derived.
+	target: Option<Node<'tree>>,
+}
+
+/// Python extensions for [`crate::context`].
+impl Backend {
+	/// Finds the key at `offset`, either passed to `with_context` or read from the context.
+	fn python_context_key_at<'tree>(
+		&self,
+		root: Node<'tree>,
+		contents: &str,
+		offset: usize,
+	) -> Option<PyContextKey<'tree>> {
+		let mut cursor = QueryCursor::new();
+		let mut matches = cursor.matches(PyContexts::query(), root, contents.as_bytes());
+		while let Some(match_) = matches.next() {
+			let target = match_.nodes_for_capture_index(PyContexts::Target as _).next();
+			for arguments in match_.nodes_for_capture_index(PyContexts::Arguments as _) {
+				let range = (with_context_keys(arguments, contents).into_iter())
+					.map(key_range)
+					.find(|range| range.contains_end(offset));
+				if let Some(range) = range {
+					return Some(PyContextKey { range, target });
+				}
+			}
+		}
+
+		let mut cursor = QueryCursor::new();
+		let mut matches = cursor.matches(ContextReads::query(), root, contents.as_bytes());
+		while let Some(match_) = matches.next() {
+			let range = (match_.nodes_for_capture_index(ContextReads::Key as _))
+				.map(key_range)
+				.find(|range| range.contains_end(offset));
+			if let Some(range) = range {
+				return Some(PyContextKey { range, target: None });
+			}
+		}
+		None
+	}
+	/// The model of the receiver of `with_context`, if any.
+	fn python_context_model(&self, root: Node, key: &PyContextKey, contents: &str) -> Option<ModelName> {
+		let range = key.target?.byte_range().map_unit(ByteOffset);
+		self.index.model_of_range(root, range, contents)
+	}
+	pub(super) fn python_context_completions(
+		&self,
+		root: Node,
+		contents: &str,
+		offset: usize,
+		rope: RopeSlice<'_>,
+		completions_limit: usize,
+	) -> anyhow::Result<Option<CompletionResponse>> {
+		let key = some!(self.python_context_key_at(root, contents, offset));
+		let model = self.python_context_model(root, &key, contents);
+		let needle = &contents[key.range.start..offset];
+		let read = |path: &Path| self.rope_of_unlocked(path);
+		let mut items = MaxVec::new(completions_limit);
+		let range = rope_conv(key.range.map_unit(ByteOffset), rope);
+		self.index.complete_context_key(needle, model, range, &read, &mut items);
+		Ok(Some(CompletionResponse::List(CompletionList {
+			is_incomplete: !items.has_space(),
+			items: items.into_inner(),
+		})))
+	}
+	pub(super) fn python_context_jump_def(
+		&self,
+		root: Node,
+		contents: &str,
+		offset: usize,
+	) -> anyhow::Result<Option<Location>> {
+		let key = some!(self.python_context_key_at(root, contents, offset));
+		let model = self.python_context_model(root, &key, contents);
+		let read = |path: &Path| self.rope_of_unlocked(path);
+		self.index.jump_def_context_key(&contents[key.range], model, &read)
+	}
+	pub(super) fn python_context_references(&self, root: Node, contents: &str, offset: usize) -> Option<Vec<Location>> {
+		let key = self.python_context_key_at(root, contents, offset)?;
+		self.index.context_key_references(&contents[key.range])
+	}
+}
+
+/// The range of a key, excluding quotes if it is a string.
+fn key_range(key: Node) -> Range<usize> {
+	match key.kind() {
+		"string" => key.byte_range().shrink(1),
+		_ => key.byte_range(),
+	}
+}
+
+/// The keys passed to `with_context`, either as keyword arguments or in a dictionary:
+///
+/// ```python
+/// self.with_context(default_user_id=uid)
+/// self.with_context({'default_user_id': uid})
+/// self.with_context(dict(self.env.context, default_user_id=uid))
+/// ```
+///
+/// Identifiers are included as they are likely keyword arguments yet to be completed.
+fn with_context_keys<'tree>(arguments: Node<'tree>, contents: &str) -> Vec<Node<'tree>> {
+	let mut keys = vec![];
+	for argument in arguments.named_children(&mut arguments.walk()) {
+		match argument.kind() {
+			"keyword_argument" => keys.extend(argument.child_by_field_name("name")),
+			"identifier" => keys.push(argument),
+			"dictionary" | "set" => {
+				for item in argument.named_children(&mut argument.walk()) {
+					match item.kind() {
+						"pair" => keys.extend(item.child_by_field_name("key").filter(|key| key.kind() == "string")),
+						// `{'key'}` while the value is yet to be typed
+						"string" => keys.push(item),
+						_ => {}
+					}
+				}
+			}
+			"call"
+				if (argument.child_by_field_name("function"))
+					.is_some_and(|function| &contents[function.byte_range()] == "dict") =>
+			{
+				if let Some(arguments) = argument.child_by_field_name("arguments") {
+					for argument in arguments.named_children(&mut arguments.walk()) {
+						if argument.kind() == "keyword_argument" {
+							keys.extend(argument.child_by_field_name("name"));
+						}
+					}
+				}
+			}
+			_ => {}
+		}
+	}
+	keys
+}
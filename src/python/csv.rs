@@ -0,0 +1,136 @@
+use std::path::Path;
+
+use tower_lsp_server::ls_types::{Diagnostic, DiagnosticSeverity};
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+
+/// Python extensions for [`crate::csv`].
+impl Backend {
+	/// Reports models declared under `root` without any rule in `ir.model.access.csv`.
+	///
+	/// Only modules which declare access rules of their own are checked.
+	pub(super) fn diagnose_access_rules(
+		&self,
+		path: &str,
+		root: Node,
+		contents: &str,
+		rope: RopeSlice<'_>,
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
+		// test models are only registered while testing
+		if path.contains("/tests/") {
+			return;
+		}
+		let Some(module) = self.index.find_module_of(Path::new(path)) else {
+			return;
+		};
+		let Some(access) = _G("ir.model.access") else {
+			return;
+		};
+		if !(self.index.records.by_model(&access.into())).any(|rule| rule.module == module) {
+			return;
+		}
+		let classes = match root.kind() {
+			"class_definition" => vec![root],
+			_ => root.named_children(&mut root.walk()).collect(),
+		};
+		for class in classes {
+			let Some(name) = declared_model(class, contents) else {
+				continue;
+			};
+			let range = name.byte_range().shrink(1);
+			let model = &contents[range.clone()];
+			if model.is_empty() || self.index.records.has_access_rules(model) {
+				continue;
+			}
+			diagnostics.push(Diagnostic {
+				range: rope_conv(range.map_unit(ByteOffset), rope),
+				message: format!("Model `{model}` has no access rules"),
+				severity: Some(DiagnosticSeverity::WARNING),
+				..Default::default()
+			});
+		}
+	}
+}
+
+/// The `_name` of a new non-abstract model declared by `class`, as a string literal.
+fn declared_model<'node>(class: Node<'node>, contents: &str) -> Option<Node<'node>> {
+	if class.kind() != "class_definition" {
+		return None;
+	}
+	let superclasses = class.child_by_field_name("superclasses")?;
+	let concrete = (superclasses.named_children(&mut superclasses.walk())).any(|base| {
+		matches!(
+			&contents[base.byte_range()],
+			"models.Model" | "models.TransientModel" | "Model" | "TransientModel"
+		)
+	});
+	if !concrete {
+		return None;
+	}
+	let body = class.child_by_field_name("body")?;
+	let mut name = None;
+	let mut inherits = vec![];
+	for statement in body.named_children(&mut body.walk()) {
+		let Some(assignment) = (statement.named_child(0))
+			.filter(|assignment| statement.kind() == "expression_statement" && assignment.kind() == "assignment")
+		else {
+			continue;
+		};
+		let (Some(left), Some(right)) = (
+			assignment.child_by_field_name("left"),
+			assignment.child_by_field_name("right"),
+		) else {
+			continue;
+		};
+		match &contents[left.byte_range()] {
+			"_name" if right.kind() == "string" => name = Some(right),
+			"_inherit" if right.kind() == "string" => inherits.push(&contents[right.byte_range().shrink(1)]),
+			"_inherit" if right.kind() == "list" => inherits.extend(
+				(right.named_children(&mut right.walk()))
+					.filter(|inherit| inherit.kind() == "string")
+					.map(|inherit| &contents[inherit.byte_range().shrink(1)]),
+			),
+			_ => {}
+		}
+	}
+	// `_name` repeated alongside `_inherit` only extends the model
+	name.filter(|name| !inherits.contains(&&contents[name.byte_range().shrink(1)]))
+}
+
+#[cfg(test)]
+mod tests {
+	use tree_sitter::Parser;
+
+	use super::declared_model;
+
+	#[test]
+	fn test_declared_model() {
+		let mut parser = Parser::new();
+		parser.set_language(&tree_sitter_python::LANGUAGE.into()).unwrap();
+		let contents = r#"
+class Foo(models.Model):
+    _name = 'foo'
+    _inherit = ['mail.thread']
+
+class Bar(models.AbstractModel):
+    _name = 'bar'
+
+class Partner(models.Model):
+    _name = 'res.partner'
+    _inherit = 'res.partner'
+
+class Wizard(models.TransientModel):
+    _name = 'foo.wizard'
+"#;
+		let ast = parser.parse(contents, None).unwrap();
+		let root = ast.root_node();
+		let models = (root.named_children(&mut root.walk()))
+			.filter_map(|class| declared_model(class, contents))
+			.map(|name| &contents[name.byte_range()])
+			.collect::<Vec<_>>();
+		assert_eq!(models, ["'foo'", "'foo.wizard'"]);
+	}
+}
@@ -2,7 +2,7 @@ use std::{borrow::Cow, cmp::Ordering, ops::ControlFlow};
 
 use tower_lsp_server::ls_types::{Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location};
 use tracing::{debug, warn};
-use tree_sitter::{Node, QueryCursor, QueryMatch};
+use tree_sitter::{Node, QueryCursor, QueryMatch, Tree};
 
 use crate::analyze::type_cache;
 use crate::index::{_R, Index};
@@ -29,6 +29,17 @@ impl Backend {
 			warn!("Did not build AST for {path}");
 			return;
 		};
+		self.diagnose_python_tree(path, &ast, rope, damage_zone, diagnostics);
+	}
+	/// Like [`Backend::diagnose_python`], but for a tree that is not kept in `ast_map`.
+	pub(crate) fn diagnose_python_tree(
+		&self,
+		path: &str,
+		ast: &Tree,
+		rope: RopeSlice<'_>,
+		damage_zone: Option<ByteRange>,
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
 		let contents = Cow::from(rope);
 		let query = PyCompletions::query();
 		let mut root = ast.root_node();
@@ -60,6 +71,7 @@ impl Backend {
 		// Diagnose manifest dependencies if this is a __manifest__.py file
 		if path.ends_with("__manifest__.py") {
 			self.diagnose_manifest_dependencies(diagnostics, &contents, ast.root_node());
+			self.diagnose_manifest_paths(path, ast.root_node(), &contents, rope, diagnostics);
 		}
 		let top_level_ranges = root
 			.named_children(&mut root.walk())
@@ -292,6 +304,13 @@ impl Backend {
 				}
 			}
 		}
+		self.diagnose_python_domains(root, &contents, rope, diagnostics);
+		self.diagnose_python_selections(root, &contents, rope, diagnostics);
+		self.diagnose_computes(root, &contents, rope, diagnostics);
+		self.diagnose_reference_models(root, &contents, rope, diagnostics);
+		self.diagnose_access_rules(path, root, &contents, rope, diagnostics);
+		// each pass reports in its own order
+		diagnostics.sort_by_key(|diag| diag.range.start);
 	}
 	fn diagnose_python_scope(
 		&self,
@@ -502,10 +521,7 @@ impl Backend {
 			return;
 		}
 		let mut has_property = false;
-		if self.index.models.contains_key(&model) {
-			let Some(entry) = self.index.models.populate_properties(model.into(), &[]) else {
-				return;
-			};
+		if let Some(entry) = self.index.models.populate_properties(model.into(), &[]) {
 			static MAPPED_BUILTINS: phf::Set<&str> = phf::phf_set!(
 				"id",
 				"display_name",
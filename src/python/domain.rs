@@ -0,0 +1,191 @@
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::domain::{DomainCursor, domain_list, string_value};
+use crate::model::{ModelName, PropertyKind};
+
+#[rustfmt::skip]
+query! {
+	PyDomains(DomainTarget, Domain, FieldArgs);
+
+((call
+  (attribute
+    (_) @DOMAIN_TARGET (identifier) @_search)
+  (argument_list . (list) @DOMAIN))
+  (#match? @_search "^(search(_(read|count|fetch))?|_?read_group|filtered_domain|_where_calc)$"))
+
+((call
+  (attribute
+    (_) @DOMAIN_TARGET (identifier) @_search)
+  (argument_list
+    (keyword_argument (identifier) @_domain (list) @DOMAIN)))
+  (#eq? @_domain "domain")
+  (#match? @_search "^(search(_(read|count|fetch))?|_?read_group|filtered_domain|_where_calc)$"))
+
+((call
+  (attribute
+    (identifier) @_fields (identifier) @_type)
+  (argument_list
+    (keyword_argument (identifier) @_domain [(list) (lambda)] @DOMAIN)) @FIELD_ARGS)
+  (#eq? @_fields "fields")
+  (#match? @_type "^(Many2one|One2many|Many2many)$")
+  (#eq? @_domain "domain"))
+}
+
+/// Python extensions for [`crate::domain`].
+impl Backend {
+	/// Domains passed to search methods and relational field declarations under `root`, along with their models.
+	fn python_domains<'tree>(&self, root: Node<'tree>, contents: &str) -> Vec<(Node<'tree>, ModelName)> {
+		let mut cursor = QueryCursor::new();
+		let mut matches = cursor.matches(PyDomains::query(), root, contents.as_bytes());
+		let mut out = vec![];
+		while let Some(match_) = matches.next() {
+			let mut domain = None;
+			let mut model = None;
+			for capture in match_.captures {
+				match PyDomains::from(capture.index) {
+					Some(PyDomains::Domain) => domain = domain_list(capture.node),
+					Some(PyDomains::DomainTarget) => {
+						let range = capture.node.byte_range().map_unit(ByteOffset);
+						model = self.index.model_of_range(root, range, contents);
+					}
+					Some(PyDomains::FieldArgs) => {
+						model = field_comodel(capture.node, contents).and_then(_G).map(ModelName::from);
+					}
+					None => {}
+				}
+			}
+			if let (Some(domain), Some(model)) = (domain, model) {
+				out.push((domain, model));
+			}
+		}
+		out
+	}
+	pub(super) fn diagnose_python_domains(
+		&self,
+		root: Node,
+		contents: &str,
+		rope: RopeSlice<'_>,
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
+		let mut found = vec![];
+		for (domain, model) in self.python_domains(root, contents) {
+			self.index.diagnose_domain(domain, model, contents, false, &mut found);
+		}
+		diagnostics.extend(found.into_iter().map(|diagnostic| Diagnostic {
+			range: rope_conv(diagnostic.range, rope),
+			severity: Some(diagnostic.severity),
+			message: diagnostic.message,
+			..Default::default()
+		}));
+	}
+	/// Completes operators, selection values and field paths inside subdomains.
+	///
+	/// Other field paths are completed as mapped strings.
+	pub(super) fn python_domain_completions(
+		&self,
+		root: Node,
+		contents: &str,
+		offset: usize,
+		rope: RopeSlice<'_>,
+		completions_limit: usize,
+	) -> anyhow::Result<Option<CompletionResponse>> {
+		let Some(cursor) = self.python_domain_cursor(root, contents, offset) else {
+			return Ok(None);
+		};
+		let mut items = MaxVec::new(completions_limit);
+		match &cursor {
+			DomainCursor::Path { depth: 0, .. } => return Ok(None),
+			DomainCursor::Path { model, range, .. } => {
+				let needle = &contents[range.start.0..offset];
+				self.index.complete_property_name(
+					needle,
+					range.clone(),
+					_R(*model).into(),
+					rope,
+					Some(PropertyKind::Field),
+					None,
+					true,
+					false,
+					&mut items,
+				)?;
+			}
+			DomainCursor::Operator { .. } | DomainCursor::Value { .. } => {
+				self.index
+					.complete_domain(&cursor, rope_conv(cursor.range(), rope), &mut items);
+			}
+		}
+		Ok(Some(CompletionResponse::List(CompletionList {
+			is_incomplete: !items.has_space(),
+			items: items.into_inner(),
+		})))
+	}
+	pub(super) fn python_domain_hover(
+		&self,
+		root: Node,
+		contents: &str,
+		offset: usize,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<Hover>> {
+		let cursor = some!(self.python_domain_cursor(root, contents, offset));
+		self.index
+			.hover_domain(&cursor, contents, rope_conv(cursor.range(), rope))
+	}
+	fn python_domain_cursor<'tree>(
+		&self,
+		root: Node<'tree>,
+		contents: &str,
+		offset: usize,
+	) -> Option<DomainCursor<'tree>> {
+		let (domain, model) = (self.python_domains(root, contents).into_iter())
+			.find(|(domain, _)| domain.byte_range().contains_end(offset))?;
+		self.index.domain_cursor(domain, model, contents, offset)
+	}
+}
+
+/// The comodel of a relational field declaration, given its arguments.
+fn field_comodel<'a>(arguments: Node, contents: &'a str) -> Option<&'a str> {
+	for argument in arguments.named_children(&mut arguments.walk()) {
+		match argument.kind() {
+			"string" => return string_value(argument, contents),
+			"keyword_argument"
+				if (argument.child_by_field_name("name"))
+					.is_some_and(|name| &contents[name.byte_range()] == "comodel_name") =>
+			{
+				return string_value(argument.child_by_field_name("value")?, contents);
+			}
+			_ => {}
+		}
+	}
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use tree_sitter::Parser;
+
+	use super::field_comodel;
+
+	#[test]
+	fn test_field_comodel() {
+		let mut parser = Parser::new();
+		parser.set_language(&tree_sitter_python::LANGUAGE.into()).unwrap();
+		let mut comodel = |contents: &str| {
+			let ast = parser.parse(contents, None).unwrap();
+			let call = ast.root_node().named_child(0).unwrap().named_child(0).unwrap();
+			let arguments = call.child_by_field_name("arguments").unwrap();
+			field_comodel(arguments, contents).map(String::from)
+		};
+		assert_eq!(
+			comodel("fields.Many2one('res.partner', domain=[])").as_deref(),
+			Some("res.partner")
+		);
+		assert_eq!(
+			comodel("fields.Many2many(comodel_name='res.users', domain=[])").as_deref(),
+			Some("res.users")
+		);
+		assert_eq!(comodel("fields.One2many(domain=[])"), None);
+	}
+}
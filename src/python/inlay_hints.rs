@@ -0,0 +1,173 @@
+use std::borrow::Cow;
+
+use tower_lsp_server::ls_types::{InlayHint, InlayHintKind, InlayHintLabel, InlayHintParams};
+
+use crate::prelude::*;
+
+use crate::analyze::{ListElement, Type, TypeId, type_cache};
+use crate::backend::Backend;
+use crate::index::Index;
+
+use super::top_level_stmt;
+
+/// Python extensions.
+impl Backend {
+	pub fn python_inlay_hints(&self, params: InlayHintParams) -> anyhow::Result<Option<Vec<InlayHint>>> {
+		let uri = &params.text_document.uri;
+		let rope = {
+			let document = some!(self.document_map.get(uri.path().as_str()));
+			document.rope.clone()
+		};
+		let rope = rope.slice(..);
+		let file_path = some!(uri.to_file_path());
+		let file_path_str = file_path.to_str().unwrap();
+		let ast = self
+			.ast_map
+			.get(file_path_str)
+			.ok_or_else(|| errloc!("Did not build AST for {}", file_path_str))?;
+		let contents = Cow::from(rope);
+		let range: ByteRange = rope_conv(params.range, rope);
+
+		let hints = self
+			.index
+			.python_inlay_hints(ast.root_node(), range, &contents)
+			.into_iter()
+			.map(|(offset, label)| InlayHint {
+				position: rope_conv(offset, rope),
+				label: InlayHintLabel::String(label),
+				kind: Some(InlayHintKind::TYPE),
+				text_edits: None,
+				tooltip: None,
+				padding_left: None,
+				padding_right: None,
+				data: None,
+			})
+			.collect();
+		Ok(Some(hints))
+	}
+}
+
+impl Index {
+	/// Type annotations for variables bound within `range`, by assignments and `for` loops,
+	/// as `(end of the variable, label)`.
+	pub(crate) fn python_inlay_hints(&self, root: Node, range: ByteRange, contents: &str) -> Vec<(ByteOffset, String)> {
+		// bindings are grouped by top-level statement so that each scope is only walked once
+		let mut groups = Vec::<(Node, Vec<Node>)>::new();
+		for node in PreTravel::new(root) {
+			if node.end_byte() < range.start.0 || node.start_byte() > range.end.0 {
+				continue;
+			}
+			for binding in bindings(node) {
+				if &contents[binding.byte_range()] == "_" {
+					continue;
+				}
+				match groups.last_mut() {
+					Some((stmt, group)) if stmt.byte_range().contains(&binding.start_byte()) => group.push(binding),
+					_ => {
+						let Some(stmt) = top_level_stmt(root, binding.start_byte()) else {
+							continue;
+						};
+						groups.push((stmt, vec![binding]));
+					}
+				}
+			}
+		}
+
+		let mut hints = vec![];
+		for (stmt, bindings) in groups {
+			let ranges = (bindings.iter())
+				.map(|binding| binding.byte_range().map_unit(ByteOffset))
+				.collect::<Vec<_>>();
+			let types = self.type_of_ranges(stmt, &ranges, contents);
+			for (binding, type_) in bindings.into_iter().zip(types) {
+				if let Some(label) = type_.and_then(|type_| self.inlay_type_display(type_)) {
+					hints.push((ByteOffset(binding.end_byte()), format!(": {label}")));
+				}
+			}
+		}
+		hints
+	}
+	/// Like [`Index::type_display`] but on a single line, and only for types that carry information
+	/// beyond what is obvious from the source.
+	fn inlay_type_display(&self, type_: TypeId) -> Option<String> {
+		match type_cache().resolve(type_) {
+			Type::Model(model) if model.is_empty() => return None,
+			Type::DictBag(properties) if properties.is_empty() => return None,
+			Type::List(ListElement::Vacant) => return None,
+			Type::Model(_)
+			| Type::Record(_)
+			| Type::Dict(..)
+			| Type::DictBag(_)
+			| Type::List(_)
+			| Type::Tuple(_)
+			| Type::Iterable(_) => {}
+			_ => return None,
+		}
+		let display = self.type_display(type_)?;
+		Some(display.lines().map(str::trim).collect::<Vec<_>>().join(" "))
+	}
+}
+
+/// Identifiers newly bound by `node`, if any.
+fn bindings(node: Node<'_>) -> Vec<Node<'_>> {
+	let target = match node.kind() {
+		// annotated assignments already have their types spelled out
+		"assignment" if node.child_by_field_name("type").is_none() => node.child_by_field_name("left"),
+		"named_expression" => node.child_by_field_name("name"),
+		"for_statement" => node.child_by_field_name("left"),
+		_ => None,
+	};
+	let Some(target) = target else {
+		return vec![];
+	};
+	match target.kind() {
+		"identifier" => vec![target],
+		"pattern_list" | "tuple_pattern" => target
+			.named_children(&mut target.walk())
+			.filter(|child| child.kind() == "identifier")
+			.collect(),
+		_ => vec![],
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+	use tree_sitter::Parser;
+
+	use crate::index::Index;
+	use crate::utils::ByteOffset;
+
+	#[test]
+	fn test_python_inlay_hints() {
+		let mut parser = Parser::new();
+		parser.set_language(&tree_sitter_python::LANGUAGE.into()).unwrap();
+		let contents = r#"
+class Foo(models.Model):
+	_name = 'foo'
+	def bar(self):
+		partners = self.env['res.partner']
+		for rec in self:
+			pass
+		_ = partners
+	def baz(self):
+		users = self.env['res.users']
+"#;
+		let ast = parser.parse(contents, None).unwrap();
+		let index = Index::default();
+		let hints = index.python_inlay_hints(ast.root_node(), ByteOffset(0)..ByteOffset(contents.len()), contents);
+		let hints = hints
+			.iter()
+			.map(|(offset, label)| (&contents[..offset.0], label.as_str()))
+			.map(|(before, label)| (before.rsplit([' ', '\t']).next().unwrap(), label))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			hints,
+			[
+				("partners", r#": Model["res.partner"]"#),
+				("rec", r#": Model["foo"]"#),
+				("users", r#": Model["res.users"]"#),
+			]
+		);
+	}
+}
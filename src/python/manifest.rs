@@ -0,0 +1,264 @@
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering::Relaxed;
+
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::domain::string_value;
+use crate::manifest::{Directive, Manifest, ManifestPath, expand_glob, is_glob};
+
+/// Python extensions for [`crate::manifest`].
+impl Backend {
+	/// Reports missing data files and assets, and data files under the module which the manifest does not list.
+	pub(super) fn diagnose_manifest_paths(
+		&self,
+		path: &str,
+		root: Node,
+		contents: &str,
+		rope: RopeSlice<'_>,
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
+		let Some(module_dir) = Path::new(path).parent() else {
+			return;
+		};
+		let Some(manifest) = Manifest::parse(root, contents) else {
+			return;
+		};
+		let generation = self.saved_generation.load(Relaxed);
+		if let Some(cached) = self.manifest_diagnostics.get(path)
+			&& cached.0 == generation
+			&& cached.1 == contents
+		{
+			diagnostics.extend(cached.2.iter().cloned());
+			return;
+		}
+		let start = diagnostics.len();
+		let mut push = |node: Node, severity, message| {
+			diagnostics.push(Diagnostic {
+				range: rope_conv(node.byte_range().shrink(1).map_unit(ByteOffset), rope),
+				severity: Some(severity),
+				message,
+				..Default::default()
+			})
+		};
+		let mut listed = vec![];
+		for data in &manifest.data {
+			let Some(value) = string_value(*data, contents).filter(|value| !value.is_empty()) else {
+				continue;
+			};
+			let file = module_dir.join(value);
+			if !file.is_file() {
+				push(
+					*data,
+					DiagnosticSeverity::ERROR,
+					format!("File `{value}` does not exist"),
+				);
+			}
+			listed.push(file);
+		}
+		for bundle in &manifest.bundles {
+			for asset in &bundle.assets {
+				let globs = asset
+					.target
+					.into_iter()
+					.chain(Some(asset.path).filter(|_| asset.directive != Directive::Include));
+				for glob in globs {
+					let Some(value) = string_value(glob, contents) else {
+						continue;
+					};
+					// unknown modules are reported on `depends`
+					let Some((dir, pattern)) = self.index.resolve_asset(value) else {
+						continue;
+					};
+					if !expand_glob(&dir, pattern).is_empty() {
+						continue;
+					}
+					let message = match is_glob(value) {
+						true => format!("`{value}` matches no files"),
+						false => format!("File `{value}` does not exist"),
+					};
+					push(glob, DiagnosticSeverity::WARNING, message);
+				}
+			}
+		}
+
+		let Ok(walker) =
+			globwalk::GlobWalkerBuilder::from_patterns(module_dir, &["**/*.{xml,csv}", "!static/**", "!tests/**"])
+				.file_type(globwalk::FileType::FILE)
+				.build()
+		else {
+			return;
+		};
+		let mut unlisted = (walker.flatten())
+			.map(|entry| entry.into_path())
+			.filter(|file| !listed.contains(file))
+			.collect::<Vec<_>>();
+		unlisted.sort();
+		let range = match manifest.data_key {
+			Some(key) => key.byte_range().shrink(1),
+			None => manifest.dictionary.start_byte()..manifest.dictionary.start_byte() + 1,
+		};
+		let range = rope_conv(range.map_unit(ByteOffset), rope);
+		for file in unlisted {
+			let relative = file.strip_prefix(module_dir).unwrap_or(&file);
+			diagnostics.push(Diagnostic {
+				range,
+				severity: Some(DiagnosticSeverity::WARNING),
+				message: format!("`{}` is not listed in the manifest", relative.display()),
+				..Default::default()
+			});
+		}
+		let found = diagnostics[start..].to_vec();
+		(self.manifest_diagnostics).insert(path.to_string(), (generation, contents.to_string(), found));
+	}
+	/// Completes data files relative to the module directory, and assets relative to the addons directory.
+	pub(super) fn manifest_completions(
+		&self,
+		path: &Path,
+		root: Node,
+		contents: &str,
+		offset: usize,
+		rope: RopeSlice<'_>,
+		completions_limit: usize,
+	) -> anyhow::Result<Option<CompletionResponse>> {
+		let manifest = some!(Manifest::parse(root, contents));
+		let (node, kind) = some!(manifest.path_at(offset));
+		let range = node.byte_range().shrink(1);
+		if !range.contains_end(offset) {
+			return Ok(None);
+		}
+		let typed = &contents[range.start..offset];
+		let (parent, segment_start) = match typed.rfind('/') {
+			Some(slash) => (&typed[..slash], range.start + slash + 1),
+			None => ("", range.start),
+		};
+		let replace = rope_conv((segment_start..offset).map_unit(ByteOffset), rope);
+		let mut items = MaxVec::new(completions_limit);
+		let dir = match kind {
+			ManifestPath::Bundle => return Ok(None),
+			ManifestPath::Data => some!(path.parent()).join(parent),
+			ManifestPath::Asset if !typed.contains('/') => {
+				let mut modules = self.index.get_all_available_modules().into_iter().collect::<Vec<_>>();
+				modules.sort_by_key(|module| _R(*module));
+				items.extend(
+					modules
+						.into_iter()
+						.map(|module| path_completion(_R(module), true, replace)),
+				);
+				return Ok(Some(CompletionResponse::List(CompletionList {
+					is_incomplete: !items.has_space(),
+					items: items.into_inner(),
+				})));
+			}
+			ManifestPath::Asset => {
+				let (module_dir, rest) = some!(self.index.resolve_asset(typed));
+				let rest = rest.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+				module_dir.join(rest)
+			}
+		};
+		let mut entries = (std::fs::read_dir(&dir).into_iter().flatten().flatten())
+			.filter_map(|entry| {
+				let name = entry.file_name().to_string_lossy().into_owned();
+				let is_dir = entry.file_type().ok()?.is_dir();
+				if name.starts_with('.') || name == "__pycache__" {
+					return None;
+				}
+				let data_file = name.ends_with(".xml") || name.ends_with(".csv");
+				(is_dir || kind == ManifestPath::Asset || data_file).then_some((name, is_dir))
+			})
+			.collect::<Vec<_>>();
+		entries.sort();
+		items.extend((entries.into_iter()).map(|(name, is_dir)| path_completion(&name, is_dir, replace)));
+		Ok(Some(CompletionResponse::List(CompletionList {
+			is_incomplete: !items.has_space(),
+			items: items.into_inner(),
+		})))
+	}
+	/// Lists the files matched by an asset glob, or contributed to a bundle by all manifests.
+	pub(super) fn manifest_hover(
+		&self,
+		root: Node,
+		contents: &str,
+		offset: usize,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<Hover>> {
+		let manifest = some!(Manifest::parse(root, contents));
+		let (node, kind) = some!(manifest.path_at(offset));
+		let value = some!(string_value(node, contents));
+		let range = rope_conv(node.byte_range().shrink(1).map_unit(ByteOffset), rope);
+		let files = match kind {
+			ManifestPath::Data => return Ok(None),
+			ManifestPath::Asset => self.index.asset_files(value),
+			ManifestPath::Bundle => self.index.bundle_files(value),
+		};
+		Ok(self.index.hover_asset_files(value, &files, range))
+	}
+	/// Links data files and assets which are not globs to their files.
+	pub fn manifest_document_links(&self, uri: &Uri) -> anyhow::Result<Option<Vec<DocumentLink>>> {
+		let path = some!(uri.to_file_path());
+		let module_dir = some!(path.parent());
+		let rope = {
+			let document = some!(self.document_map.get(uri.path().as_str()));
+			document.rope.clone()
+		};
+		let rope = rope.slice(..);
+		let contents = Cow::from(rope);
+		let ast = some!(self.python_ast(&path, &contents));
+		let manifest = some!(Manifest::parse(ast.root_node(), &contents));
+
+		let mut targets: Vec<(Node, PathBuf)> = vec![];
+		for data in &manifest.data {
+			if let Some(value) = string_value(*data, &contents) {
+				targets.push((*data, module_dir.join(value)));
+			}
+		}
+		for bundle in &manifest.bundles {
+			for asset in &bundle.assets {
+				let globs = (asset.target.into_iter())
+					.chain(Some(asset.path).filter(|_| asset.directive != Directive::Include));
+				for glob in globs {
+					let Some(value) = string_value(glob, &contents).filter(|value| !is_glob(value)) else {
+						continue;
+					};
+					if let Some((dir, rest)) = self.index.resolve_asset(value) {
+						targets.push((glob, dir.join(rest)));
+					}
+				}
+			}
+		}
+		let links = (targets.into_iter())
+			.filter(|(_, file)| file.is_file())
+			.filter_map(|(node, file)| {
+				Some(DocumentLink {
+					range: rope_conv(node.byte_range().shrink(1).map_unit(ByteOffset), rope),
+					target: Some(Uri::from_file_path(file)?),
+					tooltip: None,
+					data: None,
+				})
+			})
+			.collect();
+		Ok(Some(links))
+	}
+}
+
+fn path_completion(name: &str, is_dir: bool, range: Range) -> CompletionItem {
+	let label = match is_dir {
+		true => format!("{name}/"),
+		false => name.to_string(),
+	};
+	CompletionItem {
+		text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+			range,
+			new_text: label.clone(),
+		})),
+		label,
+		kind: Some(match is_dir {
+			true => CompletionItemKind::FOLDER,
+			false => CompletionItemKind::FILE,
+		}),
+		..Default::default()
+	}
+}
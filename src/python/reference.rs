@@ -0,0 +1,67 @@
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::model::reference_selection;
+
+#[rustfmt::skip]
+query! {
+	PyReferences(Arguments);
+
+((call
+  (attribute
+    (identifier) @_fields (identifier) @_type)
+  (argument_list) @ARGUMENTS)
+  (#eq? @_fields "fields")
+  (#eq? @_type "Reference"))
+}
+
+/// Python extensions for [`FieldKind::Reference`](crate::model::FieldKind::Reference).
+impl Backend {
+	/// The model names allowed by `fields.Reference` declarations under `root`.
+	fn python_reference_models<'tree>(&self, root: Node<'tree>, contents: &str) -> Vec<Node<'tree>> {
+		let mut cursor = QueryCursor::new();
+		let mut matches = cursor.matches(PyReferences::query(), root, contents.as_bytes());
+		let mut out = vec![];
+		while let Some(match_) = matches.next() {
+			for arguments in match_.nodes_for_capture_index(PyReferences::Arguments as _) {
+				out.extend(reference_selection(arguments, contents));
+			}
+		}
+		out
+	}
+	pub(super) fn diagnose_reference_models(
+		&self,
+		root: Node,
+		contents: &str,
+		rope: RopeSlice<'_>,
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
+		for model in self.python_reference_models(root, contents) {
+			let range = model.byte_range().shrink(1);
+			let model = &contents[range.clone()];
+			let has_model = _G(model).is_some_and(|model| self.index.models.contains_key(&model));
+			if !has_model {
+				diagnostics.push(Diagnostic {
+					range: rope_conv(range.map_unit(ByteOffset), rope),
+					message: format!("`{model}` is not a valid model name"),
+					severity: Some(DiagnosticSeverity::ERROR),
+					..Default::default()
+				})
+			}
+		}
+	}
+	pub(super) fn python_reference_jump_def(
+		&self,
+		root: Node,
+		contents: &str,
+		offset: usize,
+	) -> anyhow::Result<Option<Location>> {
+		let model = some!(
+			(self.python_reference_models(root, contents).into_iter())
+				.find(|model| model.byte_range().shrink(1).contains_end(offset))
+		);
+		self.index.jump_def_model(&contents[model.byte_range().shrink(1)])
+	}
+}
@@ -0,0 +1,642 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::str::FromStr;
+
+use tower_lsp_server::ls_types::Position;
+use tree_sitter::{QueryMatch, Tree};
+
+use crate::prelude::*;
+
+use crate::analyze::{Scope, Type, determine_scope, type_cache};
+use crate::backend::Backend;
+use crate::index::{Index, ModuleName, index_models};
+use crate::model::{Model, ModelName, ModelType};
+use crate::rename::{PropertyTarget, RecordTarget, csv_xml_ids};
+use crate::xml::determine_csv_xmlid_subgroup;
+
+use super::{FieldDescriptors, Mapped, PyCompletions, ThisModel, extract_comodel_name, top_level_stmt};
+
+/// Python extensions for [`crate::rename`].
+impl Backend {
+	pub(crate) fn python_property_target(
+		&self,
+		uri: &Uri,
+		position: Position,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<PropertyTarget>> {
+		let file_path = some!(uri.to_file_path());
+		let file_path_str = file_path.to_str().unwrap();
+		let ast = self
+			.ast_map
+			.get(file_path_str)
+			.ok_or_else(|| errloc!("Did not build AST for {}", file_path_str))?;
+		let ByteOffset(offset) = rope_conv(position, rope);
+		let contents = Cow::from(rope);
+		let root = some!(top_level_stmt(ast.root_node(), offset));
+
+		// Declarations: `foo = fields.Char()` and `def foo(self): ...`
+		if let Some(identifier) = root.descendant_for_byte_range(offset, offset)
+			&& identifier.kind() == "identifier"
+			&& let Some(class) = declaring_class(identifier)
+		{
+			let models = index_models(&contents)?;
+			let model = some!(model_of_class(&models, class));
+			return Ok(Some(PropertyTarget {
+				model: _I(model).into(),
+				prop: ImStr::from(&contents[identifier.byte_range()]),
+				range: identifier.byte_range().map_unit(ByteOffset),
+			}));
+		}
+
+		let query = PyCompletions::query();
+		let mut cursor = QueryCursor::new();
+		let mut this_model = ThisModel::default();
+		let mut matches = cursor.matches(query, ast.root_node(), contents.as_bytes());
+		while let Some(match_) = matches.next() {
+			for capture in match_.captures {
+				let range = capture.node.byte_range();
+				match PyCompletions::from(capture.index) {
+					Some(PyCompletions::Model) => {
+						if range.end < offset
+							&& match_
+								.nodes_for_capture_index(PyCompletions::FieldType as _)
+								.next()
+								.is_none()
+						{
+							this_model.tag_model(capture.node, match_, root.byte_range(), &contents);
+						}
+					}
+					Some(PyCompletions::Mapped) if range.contains_end(offset) => {
+						let mapped = some!(self.gather_mapped(
+							root,
+							match_,
+							Some(offset),
+							range,
+							this_model.inner,
+							&contents,
+							false,
+							None,
+						));
+						return Ok(self.mapped_target(mapped));
+					}
+					Some(PyCompletions::FieldDescriptor) => {
+						use FieldDescriptors as FD;
+						let Some(desc_value) = capture.node.python_next_named_sibling() else {
+							continue;
+						};
+						if !desc_value.byte_range().contains_end(offset) {
+							continue;
+						}
+
+						let comodel = extract_comodel_name(match_.captures, &contents)
+							.map(|comodel| &contents[comodel.byte_range().shrink(1)]);
+						let (model, value, single_field) = match FD::from_str(&contents[range]) {
+							Ok(FD::Compute | FD::Search | FD::Inverse) => (this_model.inner, desc_value, true),
+							Ok(FD::Related) => (this_model.inner, desc_value, false),
+							Ok(FD::InverseName) => (comodel, desc_value, true),
+							Ok(FD::Domain) => {
+								let leaf = domain_leaves(desc_value)
+									.into_iter()
+									.find(|leaf| leaf.byte_range().contains_end(offset));
+								(comodel, some!(leaf), false)
+							}
+							Ok(FD::ComodelName | FD::Groups) | Err(_) => return Ok(None),
+						};
+						if value.kind() != "string" {
+							return Ok(None);
+						}
+						let mapped = some!(self.gather_mapped(
+							root,
+							match_,
+							Some(offset),
+							value.byte_range(),
+							model,
+							&contents,
+							false,
+							Some(single_field),
+						));
+						return Ok(self.mapped_target(mapped));
+					}
+					Some(PyCompletions::Request)
+					| Some(PyCompletions::XmlId)
+					| Some(PyCompletions::ForXmlId)
+					| Some(PyCompletions::HasGroups)
+					| Some(PyCompletions::Mapped)
+					| Some(PyCompletions::MappedTarget)
+					| Some(PyCompletions::Depends)
+					| Some(PyCompletions::Prop)
+					| Some(PyCompletions::ReadFn)
+					| Some(PyCompletions::Scope)
+					| Some(PyCompletions::FieldType)
+					| None => {}
+				}
+			}
+		}
+
+		let (model, prop, range) = some!(self.attribute_at_offset(offset, root, &contents));
+		if prop.is_empty() {
+			return Ok(None);
+		}
+		Ok(Some(PropertyTarget {
+			model: _I(model).into(),
+			prop: ImStr::from(prop),
+			range: range.map_unit(ByteOffset),
+		}))
+	}
+	fn mapped_target(&self, mapped: Mapped<'_>) -> Option<PropertyTarget> {
+		let Mapped {
+			mut needle,
+			model,
+			single_field,
+			mut range,
+		} = mapped;
+		let mut model = _G(model)?;
+		if !single_field {
+			(self.index.models)
+				.resolve_mapped(&mut model, &mut needle, Some(&mut range))
+				.ok()?;
+		}
+		if needle.is_empty() {
+			return None;
+		}
+		Some(PropertyTarget {
+			model: model.into(),
+			prop: ImStr::from(needle),
+			range,
+		})
+	}
+	/// Ranges in a Python file that refer to `prop` on any of `models`.
+	pub(crate) fn python_property_edits(
+		&self,
+		path: &Path,
+		rope: RopeSlice<'_>,
+		models: &HashSet<ModelName>,
+		prop: &str,
+	) -> Vec<ByteRange> {
+		let contents = Cow::from(rope);
+		if !contents.contains(prop) {
+			return vec![];
+		}
+		let Some(ast) = self.python_ast(path, &contents) else {
+			return vec![];
+		};
+		let root = ast.root_node();
+		let class_models = index_models(&contents).unwrap_or_default();
+
+		let mut out = vec![];
+		for node in PreTravel::new(root) {
+			if node.kind() != "identifier" || &contents[node.byte_range()] != prop {
+				continue;
+			}
+			let Some(class) = declaring_class(node) else {
+				continue;
+			};
+			let model = model_of_class(&class_models, class).and_then(_G);
+			if model.is_some_and(|model| models.contains(&ModelName::from(model))) {
+				out.push(node.byte_range().map_unit(ByteOffset));
+			}
+		}
+
+		for class in root.named_children(&mut root.walk()) {
+			let Some(body) = class
+				.child_by_field_name("body")
+				.filter(|_| class.kind() == "class_definition")
+			else {
+				continue;
+			};
+			for member in body.named_children(&mut body.walk()) {
+				let function = match member.kind() {
+					"function_definition" => member,
+					"decorated_definition" => match member.child_by_field_name("definition") {
+						Some(definition) if definition.kind() == "function_definition" => definition,
+						_ => continue,
+					},
+					_ => continue,
+				};
+				if contents[function.byte_range()].contains(prop) {
+					self.python_attribute_edits(root, function, &contents, models, prop, &mut out);
+				}
+			}
+		}
+
+		let query = PyCompletions::query();
+		let mut cursor = QueryCursor::new();
+		let mut this_model = ThisModel::default();
+		let mut matches = cursor.matches(query, root, contents.as_bytes());
+		while let Some(match_) = matches.next() {
+			for capture in match_.captures {
+				match PyCompletions::from(capture.index) {
+					Some(PyCompletions::Model) => {
+						if capture.node.parent().is_some_and(|parent| parent.kind() == "subscript") {
+							continue;
+						}
+						let Some(top_level) = top_level_stmt(root, capture.node.end_byte()) else {
+							continue;
+						};
+						this_model.tag_model(capture.node, match_, top_level.byte_range(), &contents);
+					}
+					Some(PyCompletions::Mapped) if capture.node.kind() == "string" => {
+						let Some(mapped) = self.gather_mapped(
+							root,
+							match_,
+							None,
+							capture.node.byte_range(),
+							this_model.inner,
+							&contents,
+							false,
+							None,
+						) else {
+							continue;
+						};
+						let start = mapped.range.start.0;
+						out.extend((self.index).mapped_property_ranges(
+							mapped.model,
+							mapped.needle,
+							start,
+							models,
+							prop,
+						));
+					}
+					Some(PyCompletions::FieldDescriptor) => {
+						self.field_descriptor_edits(
+							capture.node,
+							match_,
+							this_model.inner,
+							&contents,
+							models,
+							prop,
+							&mut out,
+						);
+					}
+					Some(PyCompletions::Request)
+					| Some(PyCompletions::XmlId)
+					| Some(PyCompletions::ForXmlId)
+					| Some(PyCompletions::HasGroups)
+					| Some(PyCompletions::Mapped)
+					| Some(PyCompletions::MappedTarget)
+					| Some(PyCompletions::Depends)
+					| Some(PyCompletions::Prop)
+					| Some(PyCompletions::ReadFn)
+					| Some(PyCompletions::Scope)
+					| Some(PyCompletions::FieldType)
+					| None => {}
+				}
+			}
+		}
+
+		out
+	}
+	pub(crate) fn python_record_target(
+		&self,
+		uri: &Uri,
+		position: Position,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<RecordTarget>> {
+		use FieldDescriptors as FD;
+		let file_path = some!(uri.to_file_path());
+		let file_path_str = file_path.to_str().unwrap();
+		let ast = self
+			.ast_map
+			.get(file_path_str)
+			.ok_or_else(|| errloc!("Did not build AST for {}", file_path_str))?;
+		let ByteOffset(offset) = rope_conv(position, rope);
+		let contents = Cow::from(rope);
+		let current_module = self.index.find_module_of(&file_path);
+
+		let query = PyCompletions::query();
+		let mut cursor = QueryCursor::new();
+		let mut matches = cursor.matches(query, ast.root_node(), contents.as_bytes());
+		while let Some(match_) = matches.next() {
+			for capture in match_.captures {
+				let range = capture.node.byte_range();
+				let (range, groups) = match PyCompletions::from(capture.index) {
+					Some(PyCompletions::XmlId) => {
+						let groups = match_
+							.nodes_for_capture_index(PyCompletions::HasGroups as _)
+							.next()
+							.is_some();
+						(range, groups)
+					}
+					Some(PyCompletions::FieldDescriptor) => {
+						let Ok(FD::Groups) = FD::from_str(&contents[range]) else {
+							continue;
+						};
+						let Some(desc_value) = capture.node.python_next_named_sibling() else {
+							continue;
+						};
+						if desc_value.kind() != "string" {
+							continue;
+						}
+						(desc_value.byte_range(), true)
+					}
+					_ => continue,
+				};
+				if !range.contains(&offset) {
+					continue;
+				}
+				return Ok(self.xml_id_target(&contents, range, groups, offset, current_module));
+			}
+		}
+		Ok(None)
+	}
+	/// Resolves the XML ID within the string literal at `range`, which may be a CSV list of `groups`.
+	fn xml_id_target(
+		&self,
+		contents: &str,
+		range: core::ops::Range<usize>,
+		groups: bool,
+		offset: usize,
+		current_module: Option<ModuleName>,
+	) -> Option<RecordTarget> {
+		let range = range.shrink(1);
+		let mut ref_ = Some((&contents[range.clone()], range));
+		if groups {
+			let (value, range) = ref_.take()?;
+			determine_csv_xmlid_subgroup(&mut ref_, (value, range), offset);
+		}
+		let (needle, range) = ref_?;
+		let leading = needle.len() - needle.trim_start().len();
+		let needle = needle.trim();
+		let start = range.start + leading;
+		let range = ByteOffset(start)..ByteOffset(start + needle.len());
+		self.index.record_target(needle, range, current_module)
+	}
+	/// Ranges in a Python file belonging to `module` that refer to `target`,
+	/// i.e. `env.ref()` and friends, `has_group()` and the `groups=` of field declarations.
+	pub(crate) fn python_record_edits(
+		&self,
+		path: &Path,
+		rope: RopeSlice<'_>,
+		module: Option<ModuleName>,
+		target: &RecordTarget,
+	) -> Vec<ByteRange> {
+		use FieldDescriptors as FD;
+		let contents = Cow::from(rope);
+		if !contents.contains(target.id.as_str()) {
+			return vec![];
+		}
+		let Some(ast) = self.python_ast(path, &contents) else {
+			return vec![];
+		};
+
+		let mut out = vec![];
+		let mut push = |range: core::ops::Range<usize>, groups: bool| {
+			let value = &contents[range.clone()];
+			let written = match groups {
+				true => csv_xml_ids(value),
+				false => vec![(value, 0..value.len())],
+			};
+			for (written, subrange) in written {
+				if let Some(id) = target.reference_in(written, module) {
+					let start = range.start + subrange.start;
+					out.push(ByteOffset(start + id.start)..ByteOffset(start + id.end));
+				}
+			}
+		};
+		let query = PyCompletions::query();
+		let mut cursor = QueryCursor::new();
+		let mut matches = cursor.matches(query, ast.root_node(), contents.as_bytes());
+		while let Some(match_) = matches.next() {
+			for capture in match_.captures {
+				let range = capture.node.byte_range();
+				match PyCompletions::from(capture.index) {
+					Some(PyCompletions::XmlId) => {
+						let groups = match_
+							.nodes_for_capture_index(PyCompletions::HasGroups as _)
+							.next()
+							.is_some();
+						push(range.shrink(1), groups);
+					}
+					Some(PyCompletions::FieldDescriptor) => {
+						if let Ok(FD::Groups) = FD::from_str(&contents[range])
+							&& let Some(desc_value) = capture.node.python_next_named_sibling()
+							&& desc_value.kind() == "string"
+						{
+							push(desc_value.byte_range().shrink(1), true);
+						}
+					}
+					Some(PyCompletions::Request)
+					| Some(PyCompletions::ForXmlId)
+					| Some(PyCompletions::HasGroups)
+					| Some(PyCompletions::Model)
+					| Some(PyCompletions::Mapped)
+					| Some(PyCompletions::MappedTarget)
+					| Some(PyCompletions::Depends)
+					| Some(PyCompletions::Prop)
+					| Some(PyCompletions::ReadFn)
+					| Some(PyCompletions::Scope)
+					| Some(PyCompletions::FieldType)
+					| None => {}
+				}
+			}
+		}
+		out
+	}
+	/// Like [`Backend::python_property_edits`], but leaves out the declarations of `prop`.
+	pub(crate) fn python_property_references(
+		&self,
+		path: &Path,
+		rope: RopeSlice<'_>,
+		models: &HashSet<ModelName>,
+		prop: &str,
+	) -> Vec<ByteRange> {
+		let mut ranges = self.python_property_edits(path, rope, models, prop);
+		if ranges.is_empty() {
+			return ranges;
+		}
+		let contents = Cow::from(rope);
+		let Some(ast) = self.python_ast(path, &contents) else {
+			return ranges;
+		};
+		let root = ast.root_node();
+		ranges.retain(|range| {
+			let node = root.descendant_for_byte_range(range.start.0, range.end.0);
+			!node.is_some_and(|node| node.kind() == "identifier" && declaring_class(node).is_some())
+		});
+		ranges
+	}
+	/// The AST of `path`, parsing `contents` afresh if the file is not open.
+	pub(super) fn python_ast(&self, path: &Path, contents: &str) -> Option<Tree> {
+		if let Some(ast) = path.to_str().and_then(|path| self.ast_map.get(path)) {
+			return Some(ast.clone());
+		}
+		let mut parser = Parser::new();
+		parser
+			.set_language(&tree_sitter_python::LANGUAGE.into())
+			.expect("bug: failed to init python parser");
+		parser.parse(contents.as_bytes(), None)
+	}
+	/// Attribute accesses such as `record.foo` within `function`, resolved with the same
+	/// scope analysis as [`Index::model_of_range`].
+	fn python_attribute_edits(
+		&self,
+		root: Node,
+		function: Node,
+		contents: &str,
+		models: &HashSet<ModelName>,
+		prop: &str,
+		out: &mut Vec<ByteRange>,
+	) {
+		let Some((self_type, fn_scope, self_param)) = determine_scope(root, contents, function.start_byte()) else {
+			return;
+		};
+		let mut scope = Scope::default();
+		let self_type = match self_type {
+			Some(type_) => &contents[type_.byte_range().shrink(1)],
+			None => "",
+		};
+		scope.super_ = Some(self_param.into());
+		scope.insert(self_param.to_string(), Type::Model(self_type.into()));
+		let scope_end = fn_scope.end_byte();
+		Index::walk_scope(fn_scope, Some(scope), |scope, node| {
+			let entered = (self.index).build_scope(scope, node, scope_end, contents)?;
+			if node.kind() != "attribute" {
+				return ControlFlow::Continue(entered);
+			}
+			let Some(attribute) = node.child_by_field_name("attribute") else {
+				return ControlFlow::Continue(entered);
+			};
+			if &contents[attribute.byte_range()] != prop {
+				return ControlFlow::Continue(entered);
+			}
+			let object = node.child_by_field_name("object").unwrap();
+			let Some(lhs_t) = (self.index).type_of(object, scope, contents) else {
+				return ControlFlow::Continue(entered);
+			};
+			let lhs_t = type_cache().resolve(lhs_t);
+			if let Some(model) = (self.index).try_resolve_model(lhs_t, scope)
+				&& models.contains(&model)
+			{
+				out.push(attribute.byte_range().map_unit(ByteOffset));
+			}
+			ControlFlow::Continue(entered)
+		});
+	}
+	/// `compute=`, `search=`, `inverse=`, `related=`, `inverse_name=` and `domain=` of a field declaration.
+	#[allow(clippy::too_many_arguments)]
+	fn field_descriptor_edits(
+		&self,
+		descriptor: Node,
+		match_: &QueryMatch,
+		this_model: Option<&str>,
+		contents: &str,
+		models: &HashSet<ModelName>,
+		prop: &str,
+		out: &mut Vec<ByteRange>,
+	) {
+		use FieldDescriptors as FD;
+		let Some(value) = descriptor.python_next_named_sibling() else {
+			return;
+		};
+		let comodel =
+			|| extract_comodel_name(match_.captures, contents).map(|comodel| &contents[comodel.byte_range().shrink(1)]);
+		let (model, paths) = match FD::from_str(&contents[descriptor.byte_range()]) {
+			Ok(FD::Compute | FD::Search | FD::Inverse | FD::Related) => (this_model, vec![value]),
+			Ok(FD::InverseName) => (comodel(), vec![value]),
+			Ok(FD::Domain) => (comodel(), domain_leaves(value)),
+			Ok(FD::ComodelName | FD::Groups) | Err(_) => return,
+		};
+		let Some(model) = model else {
+			return;
+		};
+		for path in paths {
+			if path.kind() != "string" {
+				continue;
+			}
+			let range = path.byte_range().shrink(1);
+			let mapped = &contents[range.clone()];
+			out.extend((self.index).mapped_property_ranges(model, mapped, range.start, models, prop));
+		}
+	}
+}
+
+/// If `identifier` names a field or method declared directly in a class body, returns the class.
+pub(super) fn declaring_class(identifier: Node) -> Option<Node> {
+	let parent = identifier.parent()?;
+	let member = match parent.kind() {
+		"assignment" if parent.child_by_field_name("left") == Some(identifier) => {
+			parent.parent().filter(|stmt| stmt.kind() == "expression_statement")?
+		}
+		"function_definition" if parent.child_by_field_name("name") == Some(identifier) => match parent.parent() {
+			Some(decorated) if decorated.kind() == "decorated_definition" => decorated,
+			_ => parent,
+		},
+		_ => return None,
+	};
+	let block = member.parent().filter(|block| block.kind() == "block")?;
+	block.parent().filter(|class| class.kind() == "class_definition")
+}
+
+pub(super) fn model_of_class<'model>(models: &'model [Model], class: Node) -> Option<&'model str> {
+	let model = models
+		.iter()
+		.find(|model| model.byte_range.start.0 == class.start_byte())?;
+	match &model.type_ {
+		ModelType::Base { name, .. } => Some(name.as_str()),
+		ModelType::Inherit(inherits) => inherits.first().map(|inherit| inherit.as_str()),
+	}
+}
+
+/// The field paths of a domain literal, e.g. `'partner_id.name'` in `[('partner_id.name', '=', 'foo')]`.
+fn domain_leaves(mut domain: Node) -> Vec<Node> {
+	if domain.kind() == "lambda"
+		&& let Some(body) = domain.child_by_field_name("body")
+	{
+		domain = body;
+	}
+	if domain.kind() != "list" {
+		return vec![];
+	}
+	domain
+		.named_children(&mut domain.walk())
+		.filter(|leaf| leaf.kind() == "tuple")
+		.filter_map(|leaf| leaf.python_nth_named_child::<0>())
+		.filter(|path| path.kind() == "string")
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+	use tree_sitter::Parser;
+
+	use crate::utils::PreTravel;
+
+	use super::{declaring_class, domain_leaves};
+
+	#[test]
+	fn test_declaring_class() {
+		let mut parser = Parser::new();
+		parser.set_language(&tree_sitter_python::LANGUAGE.into()).unwrap();
+		let contents = r#"
+class Foo(models.Model):
+	foo = fields.Char()
+
+	@api.depends('foo')
+	def _compute_bar(self):
+		bar = self.foo
+"#;
+		let ast = parser.parse(contents, None).unwrap();
+		let declared = PreTravel::new(ast.root_node())
+			.filter(|node| node.kind() == "identifier" && declaring_class(*node).is_some())
+			.map(|node| &contents[node.byte_range()])
+			.collect::<Vec<_>>();
+		assert_eq!(declared, ["foo", "_compute_bar"]);
+	}
+
+	#[test]
+	fn test_domain_leaves() {
+		let mut parser = Parser::new();
+		parser.set_language(&tree_sitter_python::LANGUAGE.into()).unwrap();
+		let contents = "lambda self: [('foo.bar', '=', 1), '|', ('baz', '!=', False)]";
+		let ast = parser.parse(contents, None).unwrap();
+		let lambda = ast.root_node().named_child(0).unwrap().named_child(0).unwrap();
+		let leaves = domain_leaves(lambda)
+			.into_iter()
+			.map(|leaf| &contents[leaf.byte_range()])
+			.collect::<Vec<_>>();
+		assert_eq!(leaves, ["'foo.bar'", "'baz'"]);
+	}
+}
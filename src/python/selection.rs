@@ -0,0 +1,167 @@
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::domain::string_value;
+use crate::model::ModelName;
+use crate::selection::diagnose_selection_value;
+
+#[rustfmt::skip]
+query! {
+	PySelections(Target, Values, Comparison, Field, Compared);
+
+((call
+  (attribute
+    (_) @TARGET (identifier) @_method)
+  (argument_list . [(dictionary) @VALUES (list (dictionary) @VALUES)]))
+  (#match? @_method "^(create|write)$"))
+
+((comparison_operator
+  (attribute
+    (_) @TARGET (identifier) @FIELD)
+  [(string) (tuple) (list) (set)] @COMPARED) @COMPARISON)
+}
+
+/// A string which should be a key of a selection field.
+struct PySelectionValue<'tree, 'text> {
+	node: Node<'tree>,
+	model: ModelName,
+	field: &'text str,
+}
+
+/// Python extensions for [`crate::selection`].
+impl Backend {
+	/// Values of selection fields under `root`, in `create`/`write` dictionaries and in comparisons like `rec.state == 'draft'`.
+	fn python_selection_values<'tree, 'text>(
+		&self,
+		root: Node<'tree>,
+		contents: &'text str,
+	) -> Vec<PySelectionValue<'tree, 'text>> {
+		let mut cursor = QueryCursor::new();
+		let mut matches = cursor.matches(PySelections::query(), root, contents.as_bytes());
+		let mut out = vec![];
+		while let Some(match_) = matches.next() {
+			let mut model = None;
+			let mut values = vec![];
+			let mut comparison = None;
+			let mut field = None;
+			let mut compared = None;
+			for capture in match_.captures {
+				match PySelections::from(capture.index) {
+					Some(PySelections::Target) => {
+						let range = capture.node.byte_range().map_unit(ByteOffset);
+						model = self.index.model_of_range(root, range, contents);
+					}
+					Some(PySelections::Values) => values.push(capture.node),
+					Some(PySelections::Comparison) => comparison = Some(capture.node),
+					Some(PySelections::Field) => field = Some(capture.node),
+					Some(PySelections::Compared) => compared = Some(capture.node),
+					None => {}
+				}
+			}
+			let Some(model) = model else { continue };
+			for dictionary in values {
+				for pair in dictionary.named_children(&mut dictionary.walk()) {
+					let key = pair.child_by_field_name("key");
+					let Some(field) = key.and_then(|key| string_value(key, contents)) else {
+						continue;
+					};
+					if let Some(node) = pair
+						.child_by_field_name("value")
+						.filter(|value| value.kind() == "string")
+					{
+						out.push(PySelectionValue { node, model, field });
+					}
+				}
+			}
+			if let (Some(comparison), Some(field), Some(compared)) = (comparison, field, compared) {
+				let Some(lhs) = field.parent() else { continue };
+				let operator = contents[lhs.end_byte()..compared.start_byte()].trim();
+				if !matches!(operator, "==" | "!=" | "in" | "not in") || comparison.named_child_count() != 2 {
+					continue;
+				}
+				let field = &contents[field.byte_range()];
+				let nodes = match compared.kind() {
+					"string" => vec![compared],
+					_ => (compared.named_children(&mut compared.walk()))
+						.filter(|item| item.kind() == "string")
+						.collect(),
+				};
+				out.extend(nodes.into_iter().map(|node| PySelectionValue { node, model, field }));
+			}
+		}
+		out
+	}
+	pub(super) fn diagnose_python_selections(
+		&self,
+		root: Node,
+		contents: &str,
+		rope: RopeSlice<'_>,
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
+		for value in self.python_selection_values(root, contents) {
+			let Some(selection) = self.index.selection_of(value.model, value.field) else {
+				continue;
+			};
+			let range = value.node.byte_range().shrink(1);
+			let Some(message) = diagnose_selection_value(value.field, &selection, &contents[range.clone()]) else {
+				continue;
+			};
+			diagnostics.push(Diagnostic {
+				range: rope_conv(range.map_unit(ByteOffset), rope),
+				severity: Some(DiagnosticSeverity::ERROR),
+				message,
+				..Default::default()
+			});
+		}
+	}
+	pub(super) fn python_selection_completions(
+		&self,
+		root: Node,
+		contents: &str,
+		offset: usize,
+		rope: RopeSlice<'_>,
+		completions_limit: usize,
+	) -> anyhow::Result<Option<CompletionResponse>> {
+		let value = some!(
+			(self.python_selection_values(root, contents).into_iter()).find(|value| value
+				.node
+				.byte_range()
+				.shrink(1)
+				.contains_end(offset))
+		);
+		let selection = some!(self.index.selection_of(value.model, value.field));
+		let range = value.node.byte_range().shrink(1).map_unit(ByteOffset);
+		let mut items = MaxVec::new(completions_limit);
+		self.index
+			.complete_selection(&selection, rope_conv(range, rope), &mut items);
+		Ok(Some(CompletionResponse::List(CompletionList {
+			is_incomplete: !items.has_space(),
+			items: items.into_inner(),
+		})))
+	}
+	pub(super) fn python_selection_hover(
+		&self,
+		root: Node,
+		contents: &str,
+		offset: usize,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<Hover>> {
+		let value = some!(
+			(self.python_selection_values(root, contents).into_iter()).find(|value| value
+				.node
+				.byte_range()
+				.shrink(1)
+				.contains_end(offset))
+		);
+		let selection = some!(self.index.selection_of(value.model, value.field));
+		let range = value.node.byte_range().shrink(1);
+		let hover = self.index.hover_selection_value(
+			&selection,
+			&contents[range.clone()],
+			rope_conv(range.map_unit(ByteOffset), rope),
+		);
+		Ok(hover)
+	}
+}
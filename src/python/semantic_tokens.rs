@@ -0,0 +1,251 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+use tree_sitter::QueryMatch;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::rename::csv_xml_ids;
+use crate::semantic_tokens::{Token, TokenKind};
+
+use super::{Mapped, PyCompletions, ThisModel};
+
+/// Python extensions for [`crate::semantic_tokens`].
+impl Backend {
+	/// Classifies the strings captured by [`PyCompletions`], following the same rules as [`Backend::diagnose_python`].
+	pub(crate) fn python_semantic_tokens(&self, uri: &Uri, rope: RopeSlice<'_>) -> anyhow::Result<Vec<Token>> {
+		let file_path = ok!(uri.to_file_path(), "Not a file: {}", uri.as_str());
+		let file_path_str = file_path.to_str().unwrap();
+		let ast = self
+			.ast_map
+			.get(file_path_str)
+			.ok_or_else(|| errloc!("Did not build AST for {}", file_path_str))?;
+		let contents = Cow::from(rope);
+		let root = ast.root_node();
+		let top_level_ranges = root
+			.named_children(&mut root.walk())
+			.map(|node| node.byte_range())
+			.collect::<Vec<_>>();
+
+		let mut tokens = vec![];
+		let mut this_model = ThisModel::default();
+		let mut cursor = QueryCursor::new();
+		let mut matches = cursor.matches(PyCompletions::query(), root, contents.as_bytes());
+		while let Some(match_) = matches.next() {
+			let mut field_descriptors = vec![];
+			let mut field_model = None;
+
+			for capture in match_.captures {
+				match PyCompletions::from(capture.index) {
+					Some(PyCompletions::XmlId) => {
+						let range = capture.node.byte_range().shrink(1);
+						let value = &contents[range.clone()];
+						let xml_ids = if match_
+							.nodes_for_capture_index(PyCompletions::HasGroups as _)
+							.next()
+							.is_some()
+						{
+							csv_xml_ids(value)
+						} else {
+							vec![(value, 0..value.len())]
+						};
+						for (xml_id, relative) in xml_ids {
+							let start = range.start + relative.start;
+							tokens.push(Token {
+								range: ByteOffset(start)..ByteOffset(start + xml_id.len()),
+								kind: TokenKind::Record,
+								resolved: _G(xml_id).is_some_and(|id| self.index.records.contains_key(&id)),
+							});
+						}
+					}
+					Some(PyCompletions::Model) => {
+						let range = capture.node.byte_range().shrink(1);
+						let model = &contents[range.clone()];
+						if let Some(field_type) = match_.nodes_for_capture_index(PyCompletions::FieldType as _).next() {
+							// the first argument of other fields is their label
+							if !matches!(
+								&contents[field_type.byte_range()],
+								"One2many" | "Many2one" | "Many2many"
+							) {
+								continue;
+							}
+							if field_model.is_none() {
+								field_model = Some(model);
+							}
+						} else if capture.node.parent().is_none_or(|parent| parent.kind() != "subscript") {
+							// only `_name` and `_inherit` hold model names among class attributes
+							let Some(prop) = match_.nodes_for_capture_index(PyCompletions::Prop as _).next() else {
+								continue;
+							};
+							if !matches!(&contents[prop.byte_range()], "_name" | "_inherit") {
+								continue;
+							}
+							let Ok(idx) = top_level_ranges.binary_search_by(|range| {
+								let needle = capture.node.end_byte();
+								if needle < range.start {
+									Ordering::Greater
+								} else if needle > range.end {
+									Ordering::Less
+								} else {
+									Ordering::Equal
+								}
+							}) else {
+								continue;
+							};
+							this_model.tag_model(capture.node, match_, top_level_ranges[idx].clone(), &contents);
+						}
+						tokens.push(self.model_token(model, range));
+					}
+					Some(PyCompletions::FieldDescriptor) => {
+						let Some(desc_value) = capture.node.python_next_named_sibling() else {
+							continue;
+						};
+						field_descriptors.push((&contents[capture.node.byte_range()], desc_value));
+					}
+					Some(PyCompletions::Mapped) => self.mapped_tokens(
+						root,
+						match_,
+						this_model.inner,
+						&contents,
+						capture.node.byte_range(),
+						true,
+						&mut tokens,
+					),
+					Some(PyCompletions::Request)
+					| Some(PyCompletions::ForXmlId)
+					| Some(PyCompletions::HasGroups)
+					| Some(PyCompletions::MappedTarget)
+					| Some(PyCompletions::Depends)
+					| Some(PyCompletions::Prop)
+					| Some(PyCompletions::ReadFn)
+					| Some(PyCompletions::Scope)
+					| Some(PyCompletions::FieldType)
+					| None => {}
+				}
+			}
+
+			for &(descriptor, node) in &field_descriptors {
+				match (descriptor, node.kind()) {
+					("compute" | "search" | "inverse" | "related", "string") => self.mapped_tokens(
+						root,
+						match_,
+						this_model.inner,
+						&contents,
+						node.byte_range(),
+						descriptor == "related",
+						&mut tokens,
+					),
+					("comodel_name", "string") => {
+						let range = node.byte_range().shrink(1);
+						tokens.push(self.model_token(&contents[range.clone()], range));
+					}
+					("domain", _) => {
+						let mut domain_node = node;
+						if domain_node.kind() == "lambda" {
+							let Some(body) = domain_node.child_by_field_name("body") else {
+								continue;
+							};
+							domain_node = body;
+						}
+						if domain_node.kind() != "list" {
+							continue;
+						}
+						let Some(comodel_name) = field_model.or_else(|| {
+							field_descriptors.iter().find_map(|&(desc, node)| {
+								(desc == "comodel_name" && node.kind() == "string")
+									.then(|| &contents[node.byte_range().shrink(1)])
+							})
+						}) else {
+							continue;
+						};
+						for domain in domain_node.named_children(&mut domain_node.walk()) {
+							if domain.kind() != "tuple" {
+								continue;
+							}
+							let Some(mapped) = domain.python_nth_named_child::<0>() else {
+								continue;
+							};
+							if mapped.kind() == "string" {
+								self.mapped_tokens(
+									root,
+									match_,
+									Some(comodel_name),
+									&contents,
+									mapped.byte_range(),
+									true,
+									&mut tokens,
+								);
+							}
+						}
+					}
+					_ => {}
+				}
+			}
+		}
+		Ok(tokens)
+	}
+	fn model_token(&self, model: &str, range: core::ops::Range<usize>) -> Token {
+		Token {
+			range: range.map_unit(ByteOffset),
+			kind: TokenKind::Model,
+			resolved: _G(model).is_some_and(|model| self.index.models.contains_key(&model)),
+		}
+	}
+	/// One token per segment of a dotted path, each resolved against the model of the previous segment.
+	///
+	/// The last segment is a method instead if `expect_field` is false.
+	#[allow(clippy::too_many_arguments)]
+	fn mapped_tokens(
+		&self,
+		root: Node<'_>,
+		match_: &QueryMatch<'_, '_>,
+		model: Option<&str>,
+		contents: &str,
+		mapped_range: core::ops::Range<usize>,
+		expect_field: bool,
+		tokens: &mut Vec<Token>,
+	) {
+		let Some(Mapped {
+			needle,
+			model,
+			single_field,
+			range,
+		}) = self.gather_mapped(
+			root,
+			match_,
+			None,
+			mapped_range,
+			model,
+			contents,
+			false,
+			(!expect_field).then_some(true),
+		)
+		else {
+			return;
+		};
+		let mut model = _G(model).filter(|model| self.index.models.contains_key(model));
+		let mut start = range.start.0;
+		let mut segments = needle.split('.').peekable();
+		while let Some(segment) = segments.next() {
+			let range = ByteOffset(start)..ByteOffset(start + segment.len());
+			start = range.end.0 + 1;
+			let is_last = segments.peek().is_none();
+			let is_field = expect_field || !is_last;
+			if !segment.is_empty() {
+				tokens.push(Token {
+					range,
+					kind: if is_field { TokenKind::Field } else { TokenKind::Method },
+					resolved: model.is_some_and(|model| self.index.has_property(model.into(), segment, is_field)),
+				});
+			}
+			// dotted access in single-field contexts is reported by diagnostics
+			if is_last || single_field {
+				break;
+			}
+			model = model
+				.zip(_G(segment))
+				.and_then(|(model, field)| self.index.models.resolve_related_field(field.into(), model));
+		}
+	}
+}
@@ -0,0 +1,129 @@
+use std::borrow::Cow;
+
+use tower_lsp_server::ls_types::{DocumentSymbol, SymbolKind};
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::index::index_models;
+use crate::model::{ModelProperties, ModelType};
+use crate::symbols::{document_symbol, union_range};
+
+/// Python extensions for [`crate::symbols`].
+impl Backend {
+	/// Lists each model class, with its fields grouped by type followed by its methods.
+	pub(crate) fn python_document_symbols(
+		&self,
+		uri: &Uri,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<Vec<DocumentSymbol>>> {
+		let file_path = some!(uri.to_file_path());
+		let file_path_str = file_path.to_str().unwrap();
+		let ast = self
+			.ast_map
+			.get(file_path_str)
+			.ok_or_else(|| errloc!("Did not build AST for {}", file_path_str))?;
+		let contents = Cow::from(rope);
+		let root = ast.root_node();
+
+		let mut symbols = vec![];
+		for model in index_models(&contents)? {
+			let range = model.byte_range.erase();
+			let Some(class) = root.named_descendant_for_byte_range(range.start, range.end) else {
+				continue;
+			};
+			let name = class.child_by_field_name("name").unwrap_or(class);
+			let detail = match &model.type_ {
+				ModelType::Base { name, .. } => format!("_name = '{name}'"),
+				ModelType::Inherit(inherits) => {
+					let inherits = inherits.iter().map(|inherit| inherit.as_str()).collect::<Vec<_>>();
+					format!("_inherit = '{}'", inherits.join("', '"))
+				}
+			};
+
+			// (type, fields), in order of first appearance
+			let mut fields = Vec::<(&str, Vec<DocumentSymbol>)>::new();
+			let mut methods = vec![];
+			let query = ModelProperties::query();
+			let mut cursor = QueryCursor::new();
+			cursor.set_byte_range(range);
+			let mut matches = cursor.matches(query, root, contents.as_bytes());
+			while let Some(match_) = matches.next() {
+				let mut field = None;
+				let mut type_ = None;
+				let mut method = None;
+				let mut method_body = None;
+				for capture in match_.captures {
+					match ModelProperties::from(capture.index) {
+						Some(ModelProperties::Field) => field = Some(capture.node),
+						Some(ModelProperties::Type) => type_ = Some(&contents[capture.node.byte_range()]),
+						Some(ModelProperties::Method) => method = Some(capture.node),
+						Some(ModelProperties::MethodBody) => method_body = Some(capture.node),
+						Some(ModelProperties::Relation)
+						| Some(ModelProperties::Arg)
+						| Some(ModelProperties::Value)
+						| None => {}
+					}
+				}
+				if let Some(field) = field
+					&& let Some(type_) = type_
+				{
+					let statement = field.parent().unwrap_or(field);
+					let symbol = document_symbol(
+						&contents[field.byte_range()],
+						Some(format!("fields.{type_}")),
+						SymbolKind::FIELD,
+						span_conv(statement.range()),
+						span_conv(field.range()),
+						vec![],
+					);
+					match fields.iter_mut().find(|(group, _)| *group == type_) {
+						Some((_, group)) => group.push(symbol),
+						None => fields.push((type_, vec![symbol])),
+					}
+				} else if let Some(method) = method
+					&& let Some(body) = method_body
+				{
+					methods.push(document_symbol(
+						&contents[method.byte_range()],
+						None,
+						SymbolKind::METHOD,
+						span_conv(body.range()),
+						span_conv(method.range()),
+						vec![],
+					));
+				}
+			}
+
+			let mut children = fields
+				.into_iter()
+				.map(|(type_, fields)| {
+					let range = fields
+						.iter()
+						.map(|field| field.range)
+						.reduce(union_range)
+						.expect("groups are never empty");
+					let selection_range = fields[0].selection_range;
+					document_symbol(
+						format!("fields.{type_}"),
+						Some(format!("{} fields", fields.len())),
+						SymbolKind::NAMESPACE,
+						range,
+						selection_range,
+						fields,
+					)
+				})
+				.collect::<Vec<_>>();
+			children.append(&mut methods);
+			symbols.push(document_symbol(
+				&contents[name.byte_range()],
+				Some(detail),
+				SymbolKind::CLASS,
+				span_conv(class.range()),
+				span_conv(name.range()),
+				children,
+			));
+		}
+		Ok(Some(symbols))
+	}
+}
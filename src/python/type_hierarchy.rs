@@ -0,0 +1,90 @@
+use std::borrow::Cow;
+
+use tower_lsp_server::ls_types::Position;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::index::index_models;
+use crate::model::ModelName;
+use crate::type_hierarchy::ModelTarget;
+
+use super::rename::model_of_class;
+
+/// Python extensions for [`crate::type_hierarchy`].
+impl Backend {
+	/// The model of the class named under the cursor, or of the model name string under the cursor.
+	pub(crate) fn python_model_target(
+		&self,
+		uri: &Uri,
+		position: Position,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<ModelTarget>> {
+		let file_path = some!(uri.to_file_path());
+		let contents = Cow::from(rope);
+		let ast = some!(self.python_ast(&file_path, &contents));
+		let ByteOffset(offset) = rope_conv(position, rope);
+		let node = some!(ast.root_node().descendant_for_byte_range(offset, offset));
+
+		if let Some(class) = named_class(node) {
+			let models = index_models(&contents)?;
+			let model = some!(model_of_class(&models, class).and_then(_G));
+			return Ok(Some(ModelTarget {
+				model: model.into(),
+				class: Some(ByteOffset(class.start_byte())),
+			}));
+		}
+
+		let string = some!(enclosing_string(node));
+		let model = ModelName::from(some!(_G(&contents[string.byte_range().shrink(1)])));
+		if !self.index.models.contains_key(&model) {
+			return Ok(None);
+		}
+		Ok(Some(ModelTarget { model, class: None }))
+	}
+}
+
+/// If `identifier` is the name of a class, returns the class.
+fn named_class(identifier: Node) -> Option<Node> {
+	let class = identifier.parent().filter(|class| class.kind() == "class_definition")?;
+	(class.child_by_field_name("name") == Some(identifier)).then_some(class)
+}
+
+/// The string literal containing `node`, if any.
+fn enclosing_string(node: Node) -> Option<Node> {
+	match node.kind() {
+		"string" => Some(node),
+		"string_start" | "string_content" | "string_end" => node.parent().filter(|parent| parent.kind() == "string"),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use tree_sitter::Parser;
+
+	use super::{enclosing_string, named_class};
+
+	#[test]
+	fn test_model_target_nodes() {
+		let mut parser = Parser::new();
+		parser.set_language(&tree_sitter_python::LANGUAGE.into()).unwrap();
+		let contents = r#"
+class SaleOrder(models.Model):
+	_inherit = ['sale.order', 'mail.thread']
+"#;
+		let ast = parser.parse(contents, None).unwrap();
+		let node_at = |needle: &str| {
+			let offset = contents.find(needle).unwrap() + 1;
+			ast.root_node().descendant_for_byte_range(offset, offset).unwrap()
+		};
+
+		let class = named_class(node_at("SaleOrder")).unwrap();
+		assert_eq!(class.start_byte(), contents.find("class").unwrap());
+		assert!(named_class(node_at("models")).is_none());
+
+		let string = enclosing_string(node_at("mail.thread")).unwrap();
+		assert_eq!(&contents[string.byte_range()], "'mail.thread'");
+		assert!(enclosing_string(node_at("_inherit")).is_none());
+	}
+}
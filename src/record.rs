@@ -9,7 +9,7 @@ use crate::index::{_I, _R, ModuleName, PathSymbol, RecordId};
 use crate::model::ModelName;
 use crate::{ImStr, errloc, some};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Record {
 	pub deleted: bool,
 	pub id: ImStr,
@@ -20,8 +20,11 @@ pub struct Record {
 }
 
 /// Used for data not essential to the record itself, but for other bookkeeping purposes.
+#[derive(Serialize, Deserialize)]
 pub enum RecordMetadata {
 	View(ModelName),
+	/// The unqualified `model_*` XML ID targeted by an `ir.model.access` rule.
+	Access(ImStr),
 }
 
 impl Record {
@@ -0,0 +1,718 @@
+//! Cross-language renaming of model properties and XML IDs.
+//!
+//! A rename is resolved in two steps: first the symbol under the cursor is turned into
+//! a [`PropertyTarget`] or [`RecordTarget`] by the language-specific extensions, then every
+//! reference to it is visited across Python, XML, CSV and Javascript sources.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering::Relaxed;
+
+use globwalk::FileType;
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::analyze::MODEL_METHODS;
+use crate::backend::Backend;
+use crate::csv::parse_csv;
+use crate::index::{Index, ModuleName, RecordId};
+use crate::model::{ModelLocation, ModelName};
+
+/// A model property found under the cursor.
+#[derive(Debug)]
+pub struct PropertyTarget {
+	pub model: ModelName,
+	pub prop: ImStr,
+	/// Range of the property's name in the requesting document.
+	pub range: ByteRange,
+}
+
+/// An XML ID found under the cursor.
+#[derive(Debug)]
+pub struct RecordTarget {
+	pub record: RecordId,
+	pub module: ModuleName,
+	/// The unqualified ID.
+	pub id: ImStr,
+	/// Range of the unqualified ID in the requesting document.
+	pub range: ByteRange,
+}
+
+enum RenameTarget {
+	Property(PropertyTarget),
+	Record(RecordTarget),
+}
+
+impl RecordTarget {
+	/// If `written` refers to this record from within `module`, returns the range of its unqualified part.
+	pub(crate) fn reference_in(&self, written: &str, module: Option<ModuleName>) -> Option<core::ops::Range<usize>> {
+		match written.split_once('.') {
+			Some((prefix, id)) => {
+				(id == self.id.as_str() && prefix == _R(self.module)).then(|| prefix.len() + 1..written.len())
+			}
+			None => (written == self.id.as_str() && module == Some(self.module)).then_some(0..written.len()),
+		}
+	}
+}
+
+/// Properties provided by `BaseModel` itself, which are never declared in any addon.
+#[rustfmt::skip]
+static FRAMEWORK_PROPERTIES: phf::Set<&str> = phf::phf_set!(
+	"id", "ids", "env", "display_name", "create_date", "create_uid", "write_date",
+	"write_uid", "mapped", "read", "write", "unlink", "name_get", "default_get",
+	"fields_get", "onchange", "grouped", "flush_model", "invalidate_recordset",
+);
+
+impl Backend {
+	pub fn prepare_rename_impl(
+		&self,
+		params: TextDocumentPositionParams,
+	) -> anyhow::Result<Option<PrepareRenameResponse>> {
+		let uri = &params.text_document.uri;
+		let rope = {
+			let document = some!(self.document_map.get(uri.path().as_str()));
+			document.rope.clone()
+		};
+		let rope = rope.slice(..);
+		let (range, placeholder) = match some!(self.rename_target_at(uri, params.position, rope)?) {
+			RenameTarget::Property(target) => {
+				self.ensure_renameable(&target)?;
+				(target.range, target.prop)
+			}
+			RenameTarget::Record(target) => (target.range, target.id),
+		};
+		Ok(Some(PrepareRenameResponse::RangeWithPlaceholder {
+			range: rope_conv(range, rope),
+			placeholder: placeholder.to_string(),
+		}))
+	}
+	pub fn rename_impl(&self, params: RenameParams) -> anyhow::Result<Option<WorkspaceEdit>> {
+		let uri = &params.text_document_position.text_document.uri;
+		let rope = {
+			let document = some!(self.document_map.get(uri.path().as_str()));
+			document.rope.clone()
+		};
+		let position = params.text_document_position.position;
+		match some!(self.rename_target_at(uri, position, rope.slice(..))?) {
+			RenameTarget::Property(target) => self.rename_property(target, &params.new_name).map(Some),
+			RenameTarget::Record(target) => self.rename_record(target, &params.new_name).map(Some),
+		}
+	}
+	fn rename_target_at(
+		&self,
+		uri: &Uri,
+		position: Position,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<RenameTarget>> {
+		let xml_id = match uri.path().as_str().rsplit_once('.') {
+			Some((_, "py")) => self.python_record_target(uri, position, rope)?,
+			Some((_, "xml")) => self.xml_record_target(uri, position, rope)?,
+			_ => None,
+		};
+		if let Some(target) = xml_id {
+			return Ok(Some(RenameTarget::Record(target)));
+		}
+		Ok(self
+			.property_target_at(uri, position, rope)?
+			.map(RenameTarget::Property))
+	}
+	fn rename_property(&self, target: PropertyTarget, new_name: &str) -> anyhow::Result<WorkspaceEdit> {
+		self.ensure_renameable(&target)?;
+		if !is_identifier(new_name) {
+			return Err(anyhow!("`{new_name}` is not a valid Python identifier"));
+		}
+
+		let prop = target.prop.as_str();
+		let family = self.index.property_family(target.model, prop);
+		debug!(
+			"(rename) {}.{prop} across {:?}",
+			_R(target.model),
+			family.iter().map(|model| _R(*model)).collect::<Vec<_>>()
+		);
+
+		let mut changes = HashMap::new();
+		for (path, rope, ranges) in self.property_ranges(&family, prop, true) {
+			push_edits(&mut changes, &path, rope.slice(..), ranges, new_name);
+		}
+
+		Ok(workspace_edit(changes))
+	}
+	/// Ranges referring to `prop` on any of `models` across Python, XML and Javascript files, grouped by file.
+	///
+	/// Declarations of `prop` in Python classes are only included if `declarations` is set.
+	pub(crate) fn property_ranges(
+		&self,
+		models: &HashSet<ModelName>,
+		prop: &str,
+		declarations: bool,
+	) -> Vec<(PathBuf, Rope, Vec<ByteRange>)> {
+		let mut out = vec![];
+		for path in self.index.python_paths_of_models() {
+			let Some(rope) = self.rope_of(&path) else { continue };
+			let ranges = if declarations {
+				self.python_property_edits(&path, rope.slice(..), models, prop)
+			} else {
+				self.python_property_references(&path, rope.slice(..), models, prop)
+			};
+			if !ranges.is_empty() {
+				out.push((path, rope, ranges));
+			}
+		}
+
+		for (path, records) in self.index.records_of_models(models) {
+			let Some(rope) = self.rope_of(&path) else { continue };
+			let mut ranges = vec![];
+			for (range, model) in records {
+				let range: ByteRange = rope_conv(range, rope.slice(..));
+				ranges.extend(self.xml_property_edits(rope.slice(..), range, model, models, prop));
+			}
+			if !ranges.is_empty() {
+				out.push((path, rope, ranges));
+			}
+		}
+
+		for path in self.index.module_files("static/src/**/*.js") {
+			let Some(rope) = self.rope_of(&path) else { continue };
+			let ranges = self.js_property_edits(rope.slice(..), models, prop);
+			if !ranges.is_empty() {
+				out.push((path, rope, ranges));
+			}
+		}
+
+		out
+	}
+	fn rename_record(&self, target: RecordTarget, new_name: &str) -> anyhow::Result<WorkspaceEdit> {
+		let module = _R(target.module);
+		let new_id = match new_name.split_once('.') {
+			Some((new_module, new_id)) if new_module == module => new_id,
+			Some(_) => return Err(anyhow!("`{}` cannot be moved out of module `{module}`", target.id)),
+			None => new_name,
+		};
+		if new_id.is_empty() || !new_id.chars().all(|c| c == '_' || c.is_alphanumeric()) {
+			return Err(anyhow!("`{new_id}` is not a valid XML ID"));
+		}
+		if new_id != target.id.as_str()
+			&& let Some(existing) = _G(format!("{module}.{new_id}"))
+			&& self.index.records.contains_key(&RecordId::from(existing))
+		{
+			return Err(anyhow!("An XML record with ID `{module}.{new_id}` already exists"));
+		}
+
+		let mut changes = HashMap::new();
+		for path in self.index.module_files("**/*.xml") {
+			let Some(rope) = self.rope_of(&path) else { continue };
+			let current_module = self.index.find_module_of(&path);
+			let ranges = self.xml_record_edits(rope.slice(..), current_module, &target);
+			push_edits(&mut changes, &path, rope.slice(..), ranges, new_id);
+		}
+		for path in self.index.module_files("**/*.py") {
+			let Some(rope) = self.rope_of(&path) else { continue };
+			let current_module = self.index.find_module_of(&path);
+			let ranges = self.python_record_edits(&path, rope.slice(..), current_module, &target);
+			push_edits(&mut changes, &path, rope.slice(..), ranges, new_id);
+		}
+		for path in self.index.module_files("**/*.csv") {
+			let Some(rope) = self.rope_of(&path) else { continue };
+			let current_module = self.index.find_module_of(&path);
+			let ranges = csv_record_edits(&Cow::from(rope.slice(..)), current_module, &target);
+			push_edits(&mut changes, &path, rope.slice(..), ranges, new_id);
+		}
+
+		// Let subsequent requests see the new ID without waiting for the files to be reindexed.
+		self.index.records.rename(target.record, new_id);
+
+		Ok(workspace_edit(changes))
+	}
+	pub(crate) fn property_target_at(
+		&self,
+		uri: &Uri,
+		position: Position,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<PropertyTarget>> {
+		match uri.path().as_str().rsplit_once('.') {
+			Some((_, "py")) => self.python_property_target(uri, position, rope),
+			Some((_, "xml")) => self.xml_property_target(uri, position, rope),
+			Some((_, "js")) => self.js_property_target(uri, position, rope),
+			_ => Ok(None),
+		}
+	}
+	/// Refuses to rename properties that are owned by the framework, i.e. those defined by
+	/// `BaseModel` or by the `base` module.
+	fn ensure_renameable(&self, target: &PropertyTarget) -> anyhow::Result<()> {
+		let prop = target.prop.as_str();
+		if prop.starts_with("__") || FRAMEWORK_PROPERTIES.contains(prop) || MODEL_METHODS.contains(prop) {
+			return Err(anyhow!("`{prop}` is provided by the framework and cannot be renamed"));
+		}
+		let entry = (self.index.models)
+			.populate_properties(target.model, &[])
+			.ok_or_else(|| anyhow!("Model `{}` is not indexed", _R(target.model)))?;
+		let key = _G(prop).ok_or_else(|| anyhow!("Model `{}` has no property `{prop}`", _R(target.model)))?;
+		let definition = if let Some(field) = entry.fields.as_ref().and_then(|fields| fields.get(&key)) {
+			Some(field.location.path)
+		} else {
+			(entry.methods.as_ref())
+				.and_then(|methods| methods.get(&key))
+				.and_then(|method| Some(method.locations.first()?.path))
+		};
+		drop(entry);
+		let definition = definition.ok_or_else(|| anyhow!("Model `{}` has no property `{prop}`", _R(target.model)))?;
+		match self.index.find_module_of(&definition.to_path()) {
+			Some(module) if _R(module) != "base" => Ok(()),
+			_ => Err(anyhow!("`{prop}` is defined by the framework and cannot be renamed")),
+		}
+	}
+	/// Returns the latest contents of `path`, preferring open documents over what's on disk.
+	pub(crate) fn rope_of(&self, path: &Path) -> Option<Rope> {
+		if let Some(uri) = Uri::from_file_path(path)
+			&& let Some(document) = self.document_map.get(uri.path().as_str())
+		{
+			return Some(document.rope.clone());
+		}
+		let contents = test_utils::fs::read_to_string(path)
+			.inspect_err(|err| debug!("(rope_of) {}: {err}", path.display()))
+			.ok()?;
+		Some(Rope::from_str(&contents))
+	}
+	/// Like [`Backend::rope_of`], but reads from disk instead of waiting on a document locked by the caller.
+	pub(crate) fn rope_of_unlocked(&self, path: &Path) -> Option<Rope> {
+		if let Some(uri) = Uri::from_file_path(path)
+			&& let Some(document) = self.document_map.try_get(uri.path().as_str()).try_unwrap()
+		{
+			return Some(document.rope.clone());
+		}
+		let contents = test_utils::fs::read_to_string(path)
+			.inspect_err(|err| debug!("(rope_of_unlocked) {}: {err}", path.display()))
+			.ok()?;
+		Some(Rope::from_str(&contents))
+	}
+}
+
+impl Index {
+	/// Returns every model sharing the definition of `prop` with `model`.
+	///
+	/// This includes the ancestors and delegates that `prop` was inherited from, and all models that
+	/// inherit from or delegate to any of them in turn.
+	pub fn property_family(&self, model: ModelName, prop: &str) -> HashSet<ModelName> {
+		let Some(prop) = _G(prop) else {
+			return HashSet::from([model]);
+		};
+		// `_inherits` children read the fields of their delegates as their own.
+		let graph = (self.models.iter())
+			.map(|entry| {
+				let parents = (entry.ancestors.iter().copied())
+					.chain(entry.delegates.iter().map(|delegate| delegate.model))
+					.collect::<Vec<_>>();
+				(*entry.key(), parents)
+			})
+			.collect::<HashMap<_, _>>();
+		let has_prop = |model: ModelName| {
+			(self.models)
+				.populate_properties(model, &[])
+				.is_some_and(|entry| entry.prop_kind(prop).is_some())
+		};
+
+		let mut owners = HashSet::new();
+		let mut stack = vec![model];
+		while let Some(current) = stack.pop() {
+			if !owners.insert(current) {
+				continue;
+			}
+			for &ancestor in graph.get(&current).into_iter().flatten() {
+				if has_prop(ancestor) {
+					stack.push(ancestor);
+				}
+			}
+		}
+
+		let mut family = owners.clone();
+		for &candidate in graph.keys() {
+			if family.contains(&candidate) {
+				continue;
+			}
+			let mut visited = HashSet::new();
+			let mut stack = graph.get(&candidate).cloned().unwrap_or_default();
+			while let Some(ancestor) = stack.pop() {
+				if owners.contains(&ancestor) {
+					family.insert(candidate);
+					break;
+				}
+				if visited.insert(ancestor) {
+					stack.extend(graph.get(&ancestor).into_iter().flatten().copied());
+				}
+			}
+		}
+
+		family
+	}
+	/// All Python files declaring at least one model.
+	pub(crate) fn python_paths_of_models(&self) -> Vec<PathBuf> {
+		let mut paths = HashSet::new();
+		for entry in self.models.iter() {
+			for ModelLocation(loc, _) in entry.base.iter().chain(&entry.descendants) {
+				paths.insert(loc.path);
+			}
+		}
+		paths.into_iter().map(|path| path.to_path()).collect()
+	}
+	/// Records (including views) of `models`, grouped by file.
+	///
+	/// Views inheriting from these records are also included, since they may not declare their own model.
+	pub(crate) fn records_of_models(&self, models: &HashSet<ModelName>) -> HashMap<PathBuf, Vec<(Range, ModelName)>> {
+		let mut out = HashMap::<PathBuf, Vec<(Range, ModelName)>>::new();
+		let mut seen = HashSet::new();
+		let mut stack = vec![];
+		for model in models {
+			let records = self.records.by_model(model).chain(self.records.views_by_model(model));
+			stack.extend(records.map(|record| (*record.key(), *model)));
+		}
+		while let Some((id, model)) = stack.pop() {
+			if !seen.insert(id) {
+				continue;
+			}
+			let Some(record) = self.records.get(&id) else { continue };
+			out.entry(record.location.path.to_path())
+				.or_default()
+				.push((record.location.range, model));
+			drop(record);
+			stack.extend(self.records.by_inherit_id(&id).map(|record| (*record.key(), model)));
+		}
+		out
+	}
+	/// Ranges within a dotted path such as `partner_id.country_id.code` that refer to `prop` on any of `models`.
+	///
+	/// `start` is the offset of `mapped` in its containing document, and `model` is the model it is evaluated against.
+	pub(crate) fn mapped_property_ranges(
+		&self,
+		model: &str,
+		mapped: &str,
+		start: usize,
+		models: &HashSet<ModelName>,
+		prop: &str,
+	) -> Vec<ByteRange> {
+		let mut out = vec![];
+		let Some(mut model) = _G(model) else {
+			return out;
+		};
+		let mut start = start;
+		let mut segments = mapped.split('.').peekable();
+		while let Some(segment) = segments.next() {
+			if segment == prop && models.contains(&ModelName::from(model)) {
+				out.push(ByteOffset(start)..ByteOffset(start + segment.len()));
+			}
+			if segments.peek().is_none() {
+				break;
+			}
+			let Some(field) = _G(segment) else { break };
+			let Some(next) = self.models.resolve_related_field(field.into(), model) else {
+				break;
+			};
+			model = next;
+			start += segment.len() + 1;
+		}
+		out
+	}
+	/// Resolves an XML ID as written in `current_module` to an indexed record.
+	///
+	/// `range` spans `written`, and is narrowed down to its unqualified part.
+	pub(crate) fn record_target(
+		&self,
+		written: &str,
+		range: ByteRange,
+		current_module: Option<ModuleName>,
+	) -> Option<RecordTarget> {
+		let (module, id, range) = match written.split_once('.') {
+			Some((module, id)) => {
+				let start = range.start.0 + module.len() + 1;
+				(ModuleName::from(_G(module)?), id, ByteOffset(start)..range.end)
+			}
+			None => (current_module?, written, range),
+		};
+		let record = RecordId::from(_G(format!("{}.{id}", _R(module)))?);
+		if !self.records.contains_key(&record) {
+			return None;
+		}
+		Some(RecordTarget {
+			record,
+			module,
+			id: ImStr::from(id),
+			range,
+		})
+	}
+	/// Files matching `glob` relative to each loaded module.
+	pub(crate) fn module_files(&self, glob: &str) -> Vec<PathBuf> {
+		let mut out = vec![];
+		for root in self.roots.iter() {
+			for module in root.values() {
+				if !module.loaded.load(Relaxed) {
+					continue;
+				}
+				let module_dir = root.key().join(module.path.as_str());
+				let Ok(files) = globwalk::glob_builder(format!("{}/{glob}", module_dir.display()))
+					.file_type(FileType::FILE | FileType::SYMLINK)
+					.follow_links(true)
+					.build()
+				else {
+					continue;
+				};
+				out.extend(files.flatten().map(|entry| entry.path().to_path_buf()));
+			}
+		}
+		out
+	}
+}
+
+fn push_edits(
+	changes: &mut HashMap<Uri, Vec<TextEdit>>,
+	path: &Path,
+	rope: RopeSlice<'_>,
+	ranges: Vec<ByteRange>,
+	new_text: &str,
+) {
+	if ranges.is_empty() {
+		return;
+	}
+	let Some(uri) = Uri::from_file_path(path) else {
+		return;
+	};
+	let edits = changes.entry(uri).or_default();
+	for range in ranges {
+		edits.push(TextEdit {
+			range: rope_conv(range, rope),
+			new_text: new_text.to_string(),
+		});
+	}
+}
+
+fn workspace_edit(mut changes: HashMap<Uri, Vec<TextEdit>>) -> WorkspaceEdit {
+	for edits in changes.values_mut() {
+		edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+		edits.dedup_by_key(|edit| edit.range);
+	}
+	WorkspaceEdit {
+		changes: Some(changes),
+		..Default::default()
+	}
+}
+
+/// Splits a comma-separated list of XML IDs such as `groups="base.group_user,!base.group_portal"`.
+///
+/// Returns each ID along with its range relative to `value`.
+pub(crate) fn csv_xml_ids(value: &str) -> Vec<(&str, core::ops::Range<usize>)> {
+	let mut out = vec![];
+	let mut start = 0;
+	for item in value.split(',') {
+		let trimmed = item.trim_start();
+		let offset = start + (item.len() - trimmed.len());
+		let negated = trimmed.strip_prefix('!').unwrap_or(trimmed);
+		let offset = offset + (trimmed.len() - negated.len());
+		let id = negated.trim_end();
+		if !id.is_empty() {
+			out.push((id, offset..offset + id.len()));
+		}
+		start += item.len() + 1;
+	}
+	out
+}
+
+/// Ranges of XML IDs embedded in expressions, i.e. `%(xml_id)d` and `ref('xml_id')`.
+pub(crate) fn embedded_xml_ids(value: &str) -> Vec<core::ops::Range<usize>> {
+	let mut out = vec![];
+	let mut rest = 0;
+	while let Some(idx) = value[rest..].find("%(") {
+		let start = rest + idx + 2;
+		let Some(len) = value[start..].find(')') else { break };
+		out.push(start..start + len);
+		rest = start + len;
+	}
+	let mut rest = 0;
+	while let Some(idx) = value[rest..].find("ref(") {
+		let quote = rest + idx + 4;
+		rest = quote;
+		let Some(delimiter @ (b'\'' | b'"')) = value.as_bytes().get(quote).copied() else {
+			continue;
+		};
+		let start = quote + 1;
+		let Some(len) = value[start..].find(delimiter as char) else {
+			break;
+		};
+		out.push(start..start + len);
+		rest = start + len;
+	}
+	out
+}
+
+/// CSV data files, most notably `ir.model.access.csv`, reference XML IDs in their `id` and `*:id` columns.
+fn csv_record_edits(contents: &str, module: Option<ModuleName>, target: &RecordTarget) -> Vec<ByteRange> {
+	let mut out = vec![];
+	if !contents.contains(target.id.as_str()) {
+		return out;
+	}
+	let mut rows = parse_csv(contents).into_iter();
+	let Some(header) = rows.next() else {
+		return out;
+	};
+	let columns = (header.iter())
+		.map(|column| {
+			let column = column.value.trim();
+			column == "id" || column.ends_with(":id")
+		})
+		.collect::<Vec<_>>();
+	for row in rows {
+		for (cell, _) in row.iter().zip(&columns).filter(|(_, is_id)| **is_id) {
+			if let Some(range) = target.reference_in(cell.value, module) {
+				let start = cell.range.start;
+				out.push(ByteOffset(start + range.start)..ByteOffset(start + range.end));
+			}
+		}
+	}
+	out
+}
+
+fn is_identifier(name: &str) -> bool {
+	let mut chars = name.chars();
+	chars.next().is_some_and(|first| first == '_' || first.is_alphabetic())
+		&& chars.all(|c| c == '_' || c.is_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashSet;
+
+	use std::path::Path;
+
+	use crate::index::{_I, Index, PathSymbol, index_models};
+	use crate::model::{ModelIndex, ModelName};
+	use crate::test_utils;
+	use crate::test_utils::cases::foo::prepare_foo_index;
+	use crate::utils::ByteOffset;
+
+	use super::{RecordTarget, csv_record_edits, csv_xml_ids, embedded_xml_ids, is_identifier};
+
+	fn record_target(module: &str, id: &str) -> RecordTarget {
+		RecordTarget {
+			record: _I(format!("{module}.{id}")).into(),
+			module: _I(module).into(),
+			id: id.into(),
+			range: ByteOffset(0)..ByteOffset(0),
+		}
+	}
+
+	#[test]
+	fn test_property_family() {
+		let index = Index {
+			models: prepare_foo_index(),
+			..Default::default()
+		};
+		let model = |name: &str| ModelName::from(_I(name));
+
+		let family = index.property_family(model("quux"), "bar");
+		assert_eq!(family, HashSet::from([model("foo"), model("bar"), model("quux")]));
+
+		let family = index.property_family(model("quux"), "baz");
+		assert_eq!(family, HashSet::from([model("bar"), model("quux")]));
+	}
+
+	#[test]
+	fn test_property_family_through_delegates() {
+		const PY: &str = r#"
+class Partner(models.Model):
+	_name = 'res.partner'
+	email = fields.Char()
+
+class Users(models.Model):
+	_name = 'res.users'
+	_inherits = {'res.partner': 'partner_id'}
+	partner_id = fields.Many2one('res.partner')
+	login = fields.Char()
+
+class Message(models.Model):
+	_name = 'mail.message'
+	user_id = fields.Many2one('res.users')
+	email_from = fields.Char(related='user_id.email')
+"#;
+		if let Ok(mut fs) = test_utils::fs::TEST_FS.write() {
+			fs.insert("/addons/users.py".into(), PY.as_bytes());
+		}
+		let models = ModelIndex::default();
+		models.append(
+			PathSymbol::strip_root(_I("/addons"), Path::new("/addons/users.py")),
+			false,
+			&index_models(PY).unwrap(),
+		);
+		let index = Index {
+			models,
+			..Default::default()
+		};
+		let model = |name: &str| ModelName::from(_I(name));
+
+		let family = HashSet::from([model("res.partner"), model("res.users")]);
+		assert_eq!(index.property_family(model("res.users"), "email"), family);
+		assert_eq!(index.property_family(model("res.partner"), "email"), family);
+		assert_eq!(
+			index.property_family(model("res.users"), "login"),
+			HashSet::from([model("res.users")])
+		);
+	}
+
+	#[test]
+	fn test_is_identifier() {
+		assert!(is_identifier("partner_id"));
+		assert!(is_identifier("_compute_total"));
+		assert!(!is_identifier("1st"));
+		assert!(!is_identifier("partner.id"));
+		assert!(!is_identifier(""));
+	}
+
+	#[test]
+	fn test_record_reference_in() {
+		let target = record_target("foo", "view_form");
+		let foo = Some(_I("foo").into());
+		let bar = Some(_I("bar").into());
+		assert_eq!(target.reference_in("foo.view_form", bar), Some(4..13));
+		assert_eq!(target.reference_in("view_form", foo), Some(0..9));
+		assert_eq!(target.reference_in("view_form", bar), None);
+		assert_eq!(target.reference_in("bar.view_form", foo), None);
+		assert_eq!(target.reference_in("foo.view_form_inherit", foo), None);
+	}
+
+	#[test]
+	fn test_csv_xml_ids() {
+		let value = "base.group_user, !base.group_portal,,group_local ";
+		let ids = csv_xml_ids(value);
+		assert_eq!(
+			ids.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+			["base.group_user", "base.group_portal", "group_local"]
+		);
+		for (id, range) in ids {
+			assert_eq!(&value[range], id);
+		}
+	}
+
+	#[test]
+	fn test_embedded_xml_ids() {
+		let value = r#"[('user_id', '=', %(base.user_admin)d), ('group_id', '=', ref("base.group_user"))]"#;
+		let ids = embedded_xml_ids(value)
+			.into_iter()
+			.map(|range| &value[range])
+			.collect::<Vec<_>>();
+		assert_eq!(ids, ["base.user_admin", "base.group_user"]);
+	}
+
+	#[test]
+	fn test_csv_record_edits() {
+		let contents = "id,name,model_id:id,group_id:id,perm_read\n\
+			access_foo,foo,model_foo,\"foo.group_manager\",1\n\
+			access_bar,\"bar, group_manager\",model_bar,group_manager,1\r\n";
+		let target = record_target("foo", "group_manager");
+		let edits = csv_record_edits(contents, Some(_I("foo").into()), &target)
+			.into_iter()
+			.map(|range| &contents[range.start.0..range.end.0])
+			.collect::<Vec<_>>();
+		assert_eq!(edits, ["group_manager", "group_manager"]);
+		let edits = csv_record_edits(contents, Some(_I("foo").into()), &target);
+		assert_eq!(edits[1].start.0, contents.rfind("group_manager").unwrap());
+		assert_eq!(csv_record_edits(contents, Some(_I("bar").into()), &target).len(), 1);
+	}
+}
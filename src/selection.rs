@@ -0,0 +1,217 @@
+//! Keys of selection fields, e.g. `'draft'` in `fields.Selection([('draft', "Draft"), ('done', "Done")])`.
+//!
+//! Every declaration of a field may contribute keys through `selection` or `selection_add`.
+//! They are merged in module dependency order, so that keys added by a module follow those of its dependencies,
+//! and a later label for the same key replaces the earlier one.
+
+use fomat_macros::fomat;
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::index::Index;
+use crate::model::{FieldKind, ModelName, Selection, SelectionOption};
+
+impl Index {
+	/// The keys of the selection field `field` on `model`, following related fields.
+	///
+	/// Returns `None` if the field does not exist or is not a selection field.
+	pub fn selection_of(&self, model: ModelName, field: &str) -> Option<Selection> {
+		let mut model: Spur = model.into();
+		let mut name = ImStr::from(field);
+		let mut seen = vec![];
+		let field = loop {
+			if seen.contains(&(model, name.clone())) {
+				return None;
+			}
+			seen.push((model, name.clone()));
+			let field = {
+				let entry = self.models.populate_properties(model.into(), &[])?;
+				entry.fields.as_ref()?.get(&_G(&name)?)?.clone()
+			};
+			let FieldKind::Related(related) = &field.kind else {
+				break field;
+			};
+			let mut needle = related.as_str();
+			self.models.resolve_mapped(&mut model, &mut needle, None).ok()?;
+			name = needle.into();
+		};
+
+		let mut selection = field.selection.clone()?;
+		// keys of a module follow those of the modules it depends on
+		selection
+			.options
+			.sort_by_cached_key(|option| self.module_depth(&option.location.path.to_path()));
+		let mut options = Vec::<SelectionOption>::with_capacity(selection.options.len());
+		for option in selection.options {
+			match options.iter_mut().find(|known| known.value == option.value) {
+				Some(known) => {
+					if option.label.is_some() {
+						known.label = option.label;
+					}
+				}
+				None => options.push(option),
+			}
+		}
+		selection.options = options;
+		Some(selection)
+	}
+	pub fn complete_selection(&self, selection: &Selection, range: Range, items: &mut MaxVec<CompletionItem>) {
+		items.extend(selection.options.iter().map(|option| CompletionItem {
+			label: option.value.to_string(),
+			kind: Some(CompletionItemKind::ENUM_MEMBER),
+			detail: option.label.as_ref().map(ToString::to_string),
+			text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+				range,
+				new_text: option.value.to_string(),
+			})),
+			..Default::default()
+		}));
+	}
+	/// Shows the label of a key, and the module that added it.
+	pub fn hover_selection_value(&self, selection: &Selection, value: &str, range: Range) -> Option<Hover> {
+		let option = selection.options.iter().find(|option| option.value == value)?;
+		let module = self.find_module_of(&option.location.path.to_path());
+		Some(Hover {
+			contents: HoverContents::Markup(MarkupContent {
+				kind: MarkupKind::Markdown,
+				value: fomat! {
+					"`" (option.value) "`"
+					if let Some(label) = &option.label { ": " (label) }
+					if let Some(module) = module { "  \n*Added by:* `" (_R(module)) "`" }
+				},
+			}),
+			range: Some(range),
+		})
+	}
+}
+
+/// Reports `value` if it is not a key of the selection field `field`.
+///
+/// Nothing is reported unless all keys of the field are known.
+pub fn diagnose_selection_value(field: &str, selection: &Selection, value: &str) -> Option<String> {
+	if value.is_empty() || selection.options.is_empty() || !selection.computed_in.is_empty() {
+		return None;
+	}
+	if selection.options.iter().any(|option| option.value == value) {
+		return None;
+	}
+	Some(format!("Selection field `{field}` has no key `{value}`"))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+	use std::path::{Path, PathBuf};
+
+	use pretty_assertions::assert_eq;
+
+	use crate::index::{_I, Index, ModuleEntry, ModuleName, PathSymbol, index_models};
+	use crate::test_utils;
+
+	use super::diagnose_selection_value;
+
+	#[test]
+	fn test_selection_of() {
+		const PY: &str = r#"
+class Order(models.Model):
+	_name = 'selection.order'
+	state = fields.Selection([('draft', _("Draft")), ('done', "Done")])
+	kind = fields.Selection('_selection_kind')
+
+class OrderExtension(models.Model):
+	_inherit = 'selection.order'
+	state = fields.Selection(selection_add=[('sent', "Sent"), ('done', "Completed")])
+
+class Line(models.Model):
+	_name = 'selection.line'
+	order_id = fields.Many2one('selection.order')
+	state = fields.Selection(related='order_id.state')
+"#;
+		if let Ok(mut fs) = test_utils::fs::TEST_FS.write() {
+			fs.insert("/addons/selection.py".into(), PY.as_bytes());
+		}
+		let index = Index::default();
+		let models = index_models(PY).unwrap();
+		let path = PathSymbol::strip_root(_I("/addons"), Path::new("/addons/selection.py"));
+		index.models.append(path, false, &models);
+
+		let keys = |model: &str, field: &str| {
+			let selection = index.selection_of(_I(model).into(), field).unwrap();
+			(selection.options.into_iter())
+				.map(|option| (option.value.to_string(), option.label.map(|label| label.to_string())))
+				.collect::<Vec<_>>()
+		};
+		let state = [
+			("draft".to_string(), Some("Draft".to_string())),
+			("done".to_string(), Some("Completed".to_string())),
+			("sent".to_string(), Some("Sent".to_string())),
+		];
+		assert_eq!(keys("selection.order", "state"), state);
+		assert_eq!(keys("selection.line", "state"), state);
+
+		let state = index.selection_of(_I("selection.order").into(), "state").unwrap();
+		assert_eq!(diagnose_selection_value("state", &state, "sent"), None);
+		assert_eq!(
+			diagnose_selection_value("state", &state, "cancel").as_deref(),
+			Some("Selection field `state` has no key `cancel`")
+		);
+		let kind = index.selection_of(_I("selection.order").into(), "kind").unwrap();
+		assert_eq!(diagnose_selection_value("kind", &kind, "anything"), None);
+	}
+	#[test]
+	fn test_selection_merged_in_dependency_order() {
+		const BASE: &str = r#"
+class Order(models.Model):
+	_name = 'selection.merged'
+	state = fields.Selection([('draft', "Draft")])
+"#;
+		const SENT: &str = r#"
+class Order(models.Model):
+	_inherit = 'selection.merged'
+	state = fields.Selection(selection_add=[('sent', "Sent")])
+"#;
+		const CANCEL: &str = r#"
+class Order(models.Model):
+	_inherit = 'selection.merged'
+	state = fields.Selection(selection_add=[('cancel', "Cancelled")])
+"#;
+		let index = Index::default();
+		let module = |name: &str, dependencies: &[&str]| {
+			let entry = ModuleEntry {
+				path: name.into(),
+				dependencies: dependencies.iter().map(|dep| _I(dep).into()).collect(),
+				auto_install: false,
+				loaded: Default::default(),
+				loaded_dependents: Default::default(),
+			};
+			(ModuleName::from(_I(name)), entry)
+		};
+		let modules = HashMap::from([
+			module("merged", &[]),
+			module("merged_sent", &["merged"]),
+			module("merged_cancel", &["merged_sent"]),
+		]);
+		index.roots.insert(PathBuf::from("/merged"), modules);
+
+		// dependents are loaded first, as may happen when modules are loaded concurrently
+		for (module, contents) in [("merged_cancel", CANCEL), ("merged", BASE), ("merged_sent", SENT)] {
+			let path = format!("/merged/{module}/models.py");
+			if let Ok(mut fs) = test_utils::fs::TEST_FS.write() {
+				fs.insert(PathBuf::from(&path), contents.as_bytes());
+			}
+			let path = PathSymbol::strip_root(_I("/merged"), Path::new(&path));
+			index.append_models(path, false, &index_models(contents).unwrap());
+		}
+
+		let entry = index
+			.models
+			.populate_properties(_I("selection.merged").into(), &[])
+			.unwrap();
+		let state = entry.fields.as_ref().unwrap().get(&_I("state")).unwrap();
+		let keys = (state.selection.as_ref().unwrap().options.iter())
+			.map(|option| option.value.to_string())
+			.collect::<Vec<_>>();
+		assert_eq!(keys, ["draft", "sent", "cancel"]);
+	}
+}
@@ -0,0 +1,169 @@
+//! Semantic tokens, i.e. highlighting of model names, XML IDs, fields and methods found in strings.
+//!
+//! Each language contributes the references it knows about as [`Token`]s, using the same queries
+//! and readers used for completions and diagnostics, which are then encoded relative to each other
+//! as required by the protocol.
+
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::index::Index;
+use crate::model::ModelName;
+use crate::xml::MAGIC_FIELDS;
+
+/// The kind of a reference, in the order of the token types of [`legend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+	Model,
+	Record,
+	Field,
+	Method,
+}
+
+/// A reference to a model, record, field or method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Token {
+	pub range: ByteRange,
+	pub kind: TokenKind,
+	/// Whether the reference points to something known to the index.
+	pub resolved: bool,
+}
+
+/// Applied to references that could not be resolved.
+const UNRESOLVED: u32 = 1 << 0;
+
+pub fn legend() -> SemanticTokensLegend {
+	SemanticTokensLegend {
+		token_types: vec![
+			SemanticTokenType::new("model"),
+			SemanticTokenType::new("record"),
+			SemanticTokenType::new("field"),
+			SemanticTokenType::METHOD,
+		],
+		token_modifiers: vec![SemanticTokenModifier::new("unresolved")],
+	}
+}
+
+impl Backend {
+	/// Tokens for the whole document, or only those intersecting `range`.
+	pub fn semantic_tokens_impl(&self, uri: &Uri, range: Option<Range>) -> anyhow::Result<Option<SemanticTokens>> {
+		let rope = {
+			let document = some!(self.document_map.get(uri.path().as_str()));
+			document.rope.clone()
+		};
+		let rope = rope.slice(..);
+		let mut tokens = match uri.path().as_str().rsplit_once('.') {
+			Some((_, "py")) => self.python_semantic_tokens(uri, rope)?,
+			Some((_, "xml")) => self.xml_semantic_tokens(uri, rope)?,
+			Some((_, "js")) => self.js_semantic_tokens(uri, rope)?,
+			_ => return Ok(None),
+		};
+		if let Some(range) = range {
+			let range: ByteRange = rope_conv(range, rope);
+			tokens.retain(|token| token.range.start < range.end && range.start < token.range.end);
+		}
+		Ok(Some(SemanticTokens {
+			result_id: None,
+			data: encode_tokens(tokens, rope),
+		}))
+	}
+}
+
+impl Index {
+	/// Whether `model` has a field or method named `name`.
+	pub(crate) fn has_property(&self, model: ModelName, name: &str, is_field: bool) -> bool {
+		if is_field && MAGIC_FIELDS.contains(name) {
+			return true;
+		}
+		let Some(entry) = self.models.populate_properties(model, &[]) else {
+			return false;
+		};
+		let Some(key) = _G(name) else {
+			return false;
+		};
+		if is_field {
+			entry.fields.as_ref().is_some_and(|fields| fields.contains_key(&key))
+		} else {
+			entry.methods.as_ref().is_some_and(|methods| methods.contains_key(&key))
+		}
+	}
+}
+
+/// Sorts `tokens` and encodes each relative to the previous one.
+///
+/// Tokens spanning multiple lines or overlapping a previous token are dropped, as not all clients support them.
+pub(crate) fn encode_tokens(mut tokens: Vec<Token>, rope: RopeSlice<'_>) -> Vec<SemanticToken> {
+	tokens.sort_by_key(|token| (token.range.start, token.range.end));
+	let mut out = Vec::with_capacity(tokens.len());
+	let mut last_end = ByteOffset(0);
+	let mut last = Position::new(0, 0);
+	for token in tokens {
+		if token.range.start < last_end || token.range.start == token.range.end {
+			continue;
+		}
+		let Range { start, end } = rope_conv(token.range.clone(), rope);
+		if start.line != end.line {
+			continue;
+		}
+		out.push(SemanticToken {
+			delta_line: start.line - last.line,
+			delta_start: if start.line == last.line {
+				start.character - last.character
+			} else {
+				start.character
+			},
+			length: end.character - start.character,
+			token_type: token.kind as u32,
+			token_modifiers_bitset: if token.resolved { 0 } else { UNRESOLVED },
+		});
+		last = start;
+		last_end = token.range.end;
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+	use ropey::Rope;
+	use tower_lsp_server::ls_types::SemanticToken;
+
+	use super::{Token, TokenKind, UNRESOLVED, encode_tokens};
+	use crate::utils::ByteOffset;
+
+	#[test]
+	fn test_encode_tokens() {
+		let contents = "env['res.partner']\nfoo.mapped('bar.baz')\n";
+		let rope = Rope::from_str(contents);
+		let token = |needle: &str, kind, resolved| {
+			let start = contents.find(needle).unwrap();
+			Token {
+				range: ByteOffset(start)..ByteOffset(start + needle.len()),
+				kind,
+				resolved,
+			}
+		};
+		let tokens = vec![
+			token("baz", TokenKind::Field, false),
+			token("res.partner", TokenKind::Model, true),
+			token("bar", TokenKind::Field, true),
+		];
+		let semantic_token = |delta_line, delta_start, length, token_type, token_modifiers_bitset| SemanticToken {
+			delta_line,
+			delta_start,
+			length,
+			token_type,
+			token_modifiers_bitset,
+		};
+		assert_eq!(
+			encode_tokens(tokens, rope.slice(..)),
+			[
+				semantic_token(0, 5, 11, TokenKind::Model as u32, 0),
+				semantic_token(1, 12, 3, TokenKind::Field as u32, 0),
+				semantic_token(0, 4, 3, TokenKind::Field as u32, UNRESOLVED),
+			]
+		);
+	}
+}
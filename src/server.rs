@@ -11,11 +11,12 @@ use tower_lsp_server::ls_types::request::WorkDoneProgressCreate;
 use tower_lsp_server::ls_types::*;
 use tracing::{debug, error, info, instrument, warn};
 
-use crate::xml::add_xml_snippets;
+use crate::xml::{VIEW_ARCH_SCHEME, add_xml_snippets};
 use crate::{GITVER, NAME, VERSION, await_did_open_document, format_loc, loc};
 
 use crate::backend::{Backend, Document, Language, Text};
 use crate::index::{_G, _R};
+use crate::semantic_tokens;
 use crate::{backend, some, utils::*};
 
 impl LanguageServer for Backend {
@@ -61,6 +62,17 @@ impl LanguageServer for Backend {
 			self.capabilities.pull_diagnostics.store(true, Relaxed);
 		}
 
+		if let Some(TextDocumentClientCapabilities {
+			type_hierarchy:
+				Some(TypeHierarchyClientCapabilities {
+					dynamic_registration: Some(true),
+				}),
+			..
+		}) = params.capabilities.text_document.as_ref()
+		{
+			self.capabilities.can_register_type_hierarchy.store(true, Relaxed);
+		}
+
 		if let Some(WindowClientCapabilities {
 			work_done_progress: Some(true),
 			..
@@ -79,12 +91,38 @@ impl LanguageServer for Backend {
 				definition_provider: Some(OneOf::Left(true)),
 				hover_provider: Some(HoverProviderCapability::Simple(true)),
 				references_provider: Some(OneOf::Left(true)),
+				rename_provider: Some(OneOf::Right(RenameOptions {
+					prepare_provider: Some(true),
+					work_done_progress_options: Default::default(),
+				})),
+				call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+				code_lens_provider: Some(CodeLensOptions {
+					resolve_provider: Some(true),
+				}),
+				document_link_provider: Some(DocumentLinkOptions {
+					resolve_provider: Some(false),
+					work_done_progress_options: Default::default(),
+				}),
 				workspace_symbol_provider: Some(OneOf::Left(true)),
-				diagnostic_provider: Some(DiagnosticServerCapabilities::Options(Default::default())),
+				document_symbol_provider: Some(OneOf::Left(true)),
+				inlay_hint_provider: Some(OneOf::Left(true)),
+				semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+					SemanticTokensOptions {
+						legend: semantic_tokens::legend(),
+						range: Some(true),
+						full: Some(SemanticTokensFullOptions::Bool(true)),
+						work_done_progress_options: Default::default(),
+					},
+				)),
+				diagnostic_provider: Some(DiagnosticServerCapabilities::Options(DiagnosticOptions {
+					inter_file_dependencies: true,
+					workspace_diagnostics: true,
+					..Default::default()
+				})),
 				// XML code actions are done in 1 pass only
 				code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
 				execute_command_provider: Some(ExecuteCommandOptions {
-					commands: vec!["goto_owl".to_string(), "jump_view".to_string()],
+					commands: vec!["goto_owl".to_string(), "jump_view".to_string(), "view_arch".to_string()],
 					..Default::default()
 				}),
 				text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
@@ -168,6 +206,17 @@ impl LanguageServer for Backend {
 			});
 		}
 
+		// Type hierarchies are only advertised through dynamic registration.
+		if self.capabilities.can_register_type_hierarchy.load(Relaxed) {
+			registrations.push(Registration {
+				id: "odoo-lsp/type-hierarchy".to_string(),
+				method: "textDocument/prepareTypeHierarchy".to_string(),
+				register_options: Some(serde_json::json!({
+					"documentSelector": [{ "language": "python" }, { "language": "xml" }]
+				})),
+			});
+		}
+
 		if !registrations.is_empty() {
 			_ = self.client.register_capability(registrations).await;
 		}
@@ -202,6 +251,7 @@ impl LanguageServer for Backend {
 			("python", _) | (_, Some((_, "py"))) => Language::Python,
 			("javascript", _) | (_, Some((_, "js"))) => Language::Javascript,
 			("xml", _) | (_, Some((_, "xml"))) => Language::Xml,
+			("csv", _) | (_, Some((_, "csv"))) => Language::Csv,
 			_ => {
 				debug!(
 					"Could not determine language, or language not supported:\nlanguage_id={language_id} split_uri={split_uri:?}"
@@ -314,7 +364,12 @@ impl LanguageServer for Backend {
 	#[instrument(skip_all, ret, fields(uri = params.text_document.uri.as_str()))]
 	async fn did_save(&self, params: DidSaveTextDocumentParams) {
 		self.root_setup.wait(loc!()).await;
+		let uri = params.text_document.uri.clone();
 		_ = self.did_save_impl(params).await.inspect_err(|err| warn!("{err}"));
+		if !self.capabilities.pull_diagnostics.load(Relaxed) {
+			self.push_diagnostics(uri.clone(), None).await;
+			self.republish_dependents(&uri).await;
+		}
 	}
 	#[instrument(skip_all, ret, fields(uri = params.text_document_position_params.text_document.uri.as_str()))]
 	async fn goto_definition(&self, params: GotoDefinitionParams) -> Result<Option<GotoDefinitionResponse>> {
@@ -339,6 +394,7 @@ impl LanguageServer for Backend {
 			"xml" => self.xml_jump_def(params, rope),
 			"py" => self.python_jump_def(params, rope),
 			"js" => self.js_jump_def(params, rope),
+			"csv" => self.csv_jump_def(params, rope),
 			_ => {
 				debug!("(goto_definition) unsupported: {}", uri.path().as_str());
 				return Ok(None);
@@ -375,11 +431,120 @@ impl LanguageServer for Backend {
 			"py" => self.python_references(params, rope),
 			"xml" => self.xml_references(params, rope),
 			"js" => self.js_references(params, rope),
+			"csv" => self.csv_references(params, rope),
 			_ => return Ok(None),
 		};
 
 		Ok(refs.inspect_err(|err| warn!("{err}")).ok().flatten())
 	}
+	#[instrument(skip_all, fields(uri = params.text_document.uri.as_str()))]
+	async fn prepare_rename(&self, params: TextDocumentPositionParams) -> Result<Option<PrepareRenameResponse>> {
+		self.root_setup.wait(loc!()).await;
+
+		let path = params.text_document.uri.path().as_str();
+		await_did_open_document!(self, path);
+
+		self.prepare_rename_impl(params)
+			.map_err(|err| tower_lsp_server::jsonrpc::Error::invalid_params(err.to_string()))
+	}
+	#[instrument(skip_all, fields(uri = params.text_document_position.text_document.uri.as_str()))]
+	async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+		self.root_setup.wait(loc!()).await;
+
+		let uri = &params.text_document_position.text_document.uri;
+		let path = uri.path().as_str();
+		let module_key = some!(self.index.find_module_of(&some!(uri.to_file_path())));
+		self.index.load_modules_dependent_on(module_key).await;
+
+		await_did_open_document!(self, path);
+
+		self.rename_impl(params)
+			.map_err(|err| tower_lsp_server::jsonrpc::Error::invalid_params(err.to_string()))
+	}
+	#[instrument(skip_all, fields(uri = params.text_document_position_params.text_document.uri.as_str()))]
+	async fn prepare_call_hierarchy(
+		&self,
+		params: CallHierarchyPrepareParams,
+	) -> Result<Option<Vec<CallHierarchyItem>>> {
+		self.root_setup.wait(loc!()).await;
+
+		let path = params.text_document_position_params.text_document.uri.path().as_str();
+		await_did_open_document!(self, path);
+
+		Ok(self
+			.prepare_call_hierarchy_impl(params)
+			.inspect_err(|err| warn!("(prepare_call_hierarchy) {err}"))
+			.unwrap_or(None))
+	}
+	#[instrument(skip_all, fields(uri = params.item.uri.as_str()))]
+	async fn incoming_calls(
+		&self,
+		params: CallHierarchyIncomingCallsParams,
+	) -> Result<Option<Vec<CallHierarchyIncomingCall>>> {
+		self.root_setup.wait(loc!()).await;
+
+		if let Some(path) = params.item.uri.to_file_path()
+			&& let Some(module_key) = self.index.find_module_of(&path)
+		{
+			self.index.load_modules_dependent_on(module_key).await;
+		}
+
+		Ok(self
+			.incoming_calls_impl(params)
+			.inspect_err(|err| warn!("(incoming_calls) {err}"))
+			.unwrap_or(None))
+	}
+	#[instrument(skip_all, fields(uri = params.item.uri.as_str()))]
+	async fn outgoing_calls(
+		&self,
+		params: CallHierarchyOutgoingCallsParams,
+	) -> Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+		self.root_setup.wait(loc!()).await;
+
+		Ok(self
+			.outgoing_calls_impl(params)
+			.inspect_err(|err| warn!("(outgoing_calls) {err}"))
+			.unwrap_or(None))
+	}
+	#[instrument(skip_all, fields(uri = params.text_document_position_params.text_document.uri.as_str()))]
+	async fn prepare_type_hierarchy(
+		&self,
+		params: TypeHierarchyPrepareParams,
+	) -> Result<Option<Vec<TypeHierarchyItem>>> {
+		self.root_setup.wait(loc!()).await;
+
+		let path = params.text_document_position_params.text_document.uri.path().as_str();
+		await_did_open_document!(self, path);
+
+		Ok(self
+			.prepare_type_hierarchy_impl(params)
+			.inspect_err(|err| warn!("(prepare_type_hierarchy) {err}"))
+			.unwrap_or(None))
+	}
+	#[instrument(skip_all, fields(uri = params.item.uri.as_str()))]
+	async fn supertypes(&self, params: TypeHierarchySupertypesParams) -> Result<Option<Vec<TypeHierarchyItem>>> {
+		self.root_setup.wait(loc!()).await;
+
+		Ok(self
+			.supertypes_impl(params)
+			.inspect_err(|err| warn!("(supertypes) {err}"))
+			.unwrap_or(None))
+	}
+	#[instrument(skip_all, fields(uri = params.item.uri.as_str()))]
+	async fn subtypes(&self, params: TypeHierarchySubtypesParams) -> Result<Option<Vec<TypeHierarchyItem>>> {
+		self.root_setup.wait(loc!()).await;
+
+		if let Some(path) = params.item.uri.to_file_path()
+			&& let Some(module_key) = self.index.find_module_of(&path)
+		{
+			self.index.load_modules_dependent_on(module_key).await;
+		}
+
+		Ok(self
+			.subtypes_impl(params)
+			.inspect_err(|err| warn!("(subtypes) {err}"))
+			.unwrap_or(None))
+	}
 	#[instrument(skip_all, fields(uri))]
 	async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
 		self.root_setup.wait(loc!()).await;
@@ -458,6 +623,17 @@ impl LanguageServer for Backend {
 					Ok(None)
 				}
 			}
+		} else if ext == "csv" {
+			let completions = self.csv_completions(&params, rope.slice(..));
+			match completions {
+				Ok(ret) => Ok(ret),
+				Err(err) => {
+					self.client
+						.show_message(MessageType::ERROR, format!("error during csv completion:\n{err}"))
+						.await;
+					Ok(None)
+				}
+			}
 		} else {
 			debug!("(completion) unsupported {}", uri.path().as_str());
 			Ok(None)
@@ -514,6 +690,27 @@ impl LanguageServer for Backend {
 		let path = uri.path().as_str();
 		await_did_open_document!(self, path);
 
+		if path.ends_with("__manifest__.py") {
+			// asset globs and bundles are expanded by walking the filesystem
+			let backend = self.clone();
+			let hover = tokio::task::spawn_blocking(move || {
+				let uri = &params.text_document_position_params.text_document.uri;
+				let rope = some!(backend.document_map.get(uri.path().as_str())).rope.clone();
+				backend.python_hover(params, rope.slice(..))
+			});
+			return match hover.await {
+				Ok(Ok(ret)) => Ok(ret),
+				Ok(Err(err)) => {
+					error!("{err}");
+					Ok(None)
+				}
+				Err(err) => {
+					error!("(hover) panicked: {err}");
+					Ok(None)
+				}
+			};
+		}
+
 		let document = some!(self.document_map.get(uri.path().as_str()));
 		let (_, ext) = some!(uri.path().as_str().rsplit_once('.'));
 		let rope = document.rope.slice(..);
@@ -699,6 +896,99 @@ impl LanguageServer for Backend {
 			)))
 		}
 	}
+	#[instrument(skip_all, fields(uri = params.text_document.uri.as_str()))]
+	async fn document_symbol(&self, params: DocumentSymbolParams) -> Result<Option<DocumentSymbolResponse>> {
+		self.root_setup.wait(loc!()).await;
+
+		let path = params.text_document.uri.path().as_str();
+		await_did_open_document!(self, path);
+
+		Ok(self
+			.document_symbol_impl(params)
+			.inspect_err(|err| warn!("(document_symbol) {err}"))
+			.unwrap_or(None))
+	}
+	#[instrument(skip_all, fields(uri = params.text_document.uri.as_str()))]
+	async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+		self.root_setup.wait(loc!()).await;
+
+		let path = params.text_document.uri.path().as_str();
+		if !path.ends_with(".py") {
+			return Ok(None);
+		}
+		await_did_open_document!(self, path);
+
+		Ok(self
+			.python_inlay_hints(params)
+			.inspect_err(|err| warn!("(inlay_hint) {err}"))
+			.unwrap_or(None))
+	}
+	#[instrument(skip_all, fields(uri = params.text_document.uri.as_str()))]
+	async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+		self.root_setup.wait(loc!()).await;
+
+		let path = params.text_document.uri.path().as_str();
+		if !path.ends_with(".py") {
+			return Ok(None);
+		}
+		await_did_open_document!(self, path);
+
+		Ok(self
+			.code_lens_impl(params)
+			.inspect_err(|err| warn!("(code_lens) {err}"))
+			.unwrap_or(None))
+	}
+	#[instrument(skip_all, fields(uri = params.text_document.uri.as_str()))]
+	async fn document_link(&self, params: DocumentLinkParams) -> Result<Option<Vec<DocumentLink>>> {
+		self.root_setup.wait(loc!()).await;
+
+		let path = params.text_document.uri.path().as_str();
+		if !path.ends_with("/__manifest__.py") {
+			return Ok(None);
+		}
+		await_did_open_document!(self, path);
+
+		Ok(self
+			.manifest_document_links(&params.text_document.uri)
+			.inspect_err(|err| warn!("(document_link) {err}"))
+			.unwrap_or(None))
+	}
+	#[instrument(skip_all)]
+	async fn code_lens_resolve(&self, lens: CodeLens) -> Result<CodeLens> {
+		self.root_setup.wait(loc!()).await;
+
+		self.code_lens_resolve_impl(lens)
+			.map_err(|err| tower_lsp_server::jsonrpc::Error::invalid_params(err.to_string()))
+	}
+	#[instrument(skip_all, fields(uri = params.text_document.uri.as_str()))]
+	async fn semantic_tokens_full(&self, params: SemanticTokensParams) -> Result<Option<SemanticTokensResult>> {
+		self.root_setup.wait(loc!()).await;
+
+		let path = params.text_document.uri.path().as_str();
+		await_did_open_document!(self, path);
+
+		Ok(self
+			.semantic_tokens_impl(&params.text_document.uri, None)
+			.inspect_err(|err| warn!("(semantic_tokens_full) {err}"))
+			.unwrap_or(None)
+			.map(SemanticTokensResult::Tokens))
+	}
+	#[instrument(skip_all, fields(uri = params.text_document.uri.as_str()))]
+	async fn semantic_tokens_range(
+		&self,
+		params: SemanticTokensRangeParams,
+	) -> Result<Option<SemanticTokensRangeResult>> {
+		self.root_setup.wait(loc!()).await;
+
+		let path = params.text_document.uri.path().as_str();
+		await_did_open_document!(self, path);
+
+		Ok(self
+			.semantic_tokens_impl(&params.text_document.uri, Some(params.range))
+			.inspect_err(|err| warn!("(semantic_tokens_range) {err}"))
+			.unwrap_or(None)
+			.map(SemanticTokensRangeResult::Tokens))
+	}
 	#[instrument(skip_all, fields(path))]
 	async fn diagnostic(&self, params: DocumentDiagnosticParams) -> Result<DocumentDiagnosticReportResult> {
 		self.root_setup.wait(loc!()).await;
@@ -706,21 +996,7 @@ impl LanguageServer for Backend {
 		let path = params.text_document.uri.path().as_str();
 		await_did_open_document!(self, path);
 
-		let mut diagnostics = vec![];
-		if let Some((_, "py")) = path.rsplit_once('.')
-			&& let Some(mut document) = self.document_map.get_mut(path)
-		{
-			let damage_zone = document.damage_zone.take();
-			let rope = document.rope.clone();
-			let file_path = params.text_document.uri.to_file_path().unwrap();
-			self.diagnose_python(
-				file_path.to_str().unwrap(),
-				rope.slice(..),
-				damage_zone,
-				&mut document.diagnostics_cache,
-			);
-			diagnostics.clone_from(&document.diagnostics_cache);
-		}
+		let diagnostics = self.refresh_diagnostics(&params.text_document.uri).unwrap_or_default();
 		Ok(DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Full(
 			RelatedFullDocumentDiagnosticReport {
 				related_documents: None,
@@ -731,6 +1007,14 @@ impl LanguageServer for Backend {
 			},
 		)))
 	}
+	#[instrument(skip_all)]
+	async fn workspace_diagnostic(&self, params: WorkspaceDiagnosticParams) -> Result<WorkspaceDiagnosticReportResult> {
+		self.root_setup.wait(loc!()).await;
+
+		Ok(WorkspaceDiagnosticReportResult::Report(
+			self.workspace_diagnostic_impl(params).await,
+		))
+	}
 	#[instrument(skip_all, ret, fields(uri = params.text_document.uri.as_str()))]
 	async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
 		if self.root_setup.should_wait() {
@@ -806,6 +1090,20 @@ impl LanguageServer for Backend {
 					selection: Some(location.range),
 				})
 				.await;
+		} else if let "view_arch" = params.command.as_str()
+			&& let [Value::String(id)] = params.arguments.as_slice()
+		{
+			// the client resolves this through `odoo-lsp/view-arch`
+			let uri = some!(format!("{VIEW_ARCH_SCHEME}:{id}.xml").parse::<Uri>().ok());
+			_ = self
+				.client
+				.show_document(ShowDocumentParams {
+					uri,
+					external: Some(false),
+					take_focus: Some(true),
+					selection: None,
+				})
+				.await;
 		}
 
 		Ok(None)
@@ -84,6 +84,19 @@ impl Display for ImStr {
 	}
 }
 
+impl serde::Serialize for ImStr {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(self)
+	}
+}
+
+impl<'de> serde::Deserialize<'de> for ImStr {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let value = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+		Ok(value.as_ref().into())
+	}
+}
+
 impl std::fmt::Debug for ImStr {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		std::fmt::Debug::fmt(self.deref(), f)
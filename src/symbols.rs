@@ -0,0 +1,76 @@
+//! Document symbols, i.e. the outline of models, records, templates and components in a file.
+//!
+//! Each language contributes its own extension which reuses the same queries and readers
+//! used for indexing, so that the outline agrees with what the index sees.
+
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+
+impl Backend {
+	pub fn document_symbol_impl(&self, params: DocumentSymbolParams) -> anyhow::Result<Option<DocumentSymbolResponse>> {
+		let uri = &params.text_document.uri;
+		let rope = {
+			let document = some!(self.document_map.get(uri.path().as_str()));
+			document.rope.clone()
+		};
+		let rope = rope.slice(..);
+		let symbols = match uri.path().as_str().rsplit_once('.') {
+			Some((_, "py")) => self.python_document_symbols(uri, rope)?,
+			Some((_, "xml")) => self.xml_document_symbols(uri, rope)?,
+			Some((_, "js")) => self.js_document_symbols(uri, rope)?,
+			_ => return Ok(None),
+		};
+		Ok(symbols.map(DocumentSymbolResponse::Nested))
+	}
+}
+
+pub(crate) fn document_symbol(
+	name: impl Into<String>,
+	detail: Option<String>,
+	kind: SymbolKind,
+	range: Range,
+	selection_range: Range,
+	children: Vec<DocumentSymbol>,
+) -> DocumentSymbol {
+	#[allow(deprecated)]
+	DocumentSymbol {
+		name: name.into(),
+		detail,
+		kind,
+		tags: None,
+		deprecated: None,
+		range,
+		selection_range,
+		children: (!children.is_empty()).then_some(children),
+	}
+}
+
+/// The smallest range enclosing both `lhs` and `rhs`.
+pub(crate) fn union_range(lhs: Range, rhs: Range) -> Range {
+	Range {
+		start: lhs.start.min(rhs.start),
+		end: lhs.end.max(rhs.end),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use tower_lsp_server::ls_types::{Position, Range};
+
+	use super::union_range;
+
+	#[test]
+	fn test_union_range() {
+		let range = |start: (u32, u32), end: (u32, u32)| Range {
+			start: Position::new(start.0, start.1),
+			end: Position::new(end.0, end.1),
+		};
+		assert_eq!(
+			union_range(range((3, 4), (5, 0)), range((1, 8), (4, 2))),
+			range((1, 8), (5, 0))
+		);
+	}
+}
@@ -4,7 +4,7 @@ use xmlparser::{ElementEnd, Token, Tokenizer};
 
 use crate::prelude::*;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Serialize, Deserialize)]
 pub struct Template {
 	pub location: Option<MinLoc>,
 	pub descendants: Vec<Template>,
@@ -14,7 +14,7 @@ pub struct Template {
 /// otherwise t-inherit
 pub type TemplateName = Symbol<Template>;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NewTemplate {
 	pub base: bool,
 	pub name: TemplateName,
@@ -0,0 +1,157 @@
+//! Type hierarchy of models.
+//!
+//! Each item stands for a class contributing to a model: either the class declaring its `_name`,
+//! or an extension class re-declaring `_inherit`. The supertypes of a class are the models it
+//! inherits from, including mixins and `_inherits` delegates, while the subtypes of a base class
+//! are its extensions in module load order, followed by the models inheriting from it.
+
+use std::borrow::Cow;
+
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::index::index_models;
+use crate::model::{ModelLocation, ModelName, ModelType};
+
+/// A model found under the cursor.
+pub struct ModelTarget {
+	pub model: ModelName,
+	/// Start of the class under the cursor, if any.
+	pub class: Option<ByteOffset>,
+}
+
+/// Identifies the model behind a [`TypeHierarchyItem`].
+#[derive(Serialize, Deserialize)]
+struct ModelData {
+	model: String,
+}
+
+impl Backend {
+	pub fn prepare_type_hierarchy_impl(
+		&self,
+		params: TypeHierarchyPrepareParams,
+	) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+		let uri = &params.text_document_position_params.text_document.uri;
+		let position = params.text_document_position_params.position;
+		let rope = {
+			let document = some!(self.document_map.get(uri.path().as_str()));
+			document.rope.clone()
+		};
+		let rope = rope.slice(..);
+		let target = match uri.path().as_str().rsplit_once('.') {
+			Some((_, "py")) => self.python_model_target(uri, position, rope)?,
+			Some((_, "xml")) => self.xml_model_target(uri, position, rope)?,
+			_ => None,
+		};
+		let ModelTarget { model, class } = some!(target);
+		let classes = self.model_classes(model);
+		let class = match class {
+			Some(start) => classes.iter().find(|ModelLocation(_, range)| range.start == start),
+			None => classes.first(),
+		};
+		Ok(Some(vec![self.model_item(model, some!(class))]))
+	}
+	pub fn supertypes_impl(
+		&self,
+		params: TypeHierarchySupertypesParams,
+	) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+		let item = params.item;
+		let path = some!(item.uri.to_file_path());
+		let rope = some!(self.rope_of(&path));
+		let contents = Cow::from(rope.slice(..));
+		let declaration = index_models(&contents)?
+			.into_iter()
+			.find(|declaration| declaration.range.start == item.range.start);
+		let declaration = some!(declaration);
+		let mut ancestors = match declaration.type_ {
+			ModelType::Base { ancestors, .. } => ancestors,
+			// the primary `_inherit` of an extension resolves to the base class of its model
+			ModelType::Inherit(inherits) => inherits,
+		};
+		ancestors.extend(declaration.delegates.into_iter().map(|(parent, _)| parent));
+
+		let mut out = vec![];
+		for ancestor in ancestors {
+			let Some(ancestor) = _G(&ancestor) else { continue };
+			let ancestor = ModelName::from(ancestor);
+			let Some(class) = self.model_classes(ancestor).into_iter().next() else {
+				continue;
+			};
+			if !is_item_of(&class, &item) {
+				out.push(self.model_item(ancestor, &class));
+			}
+		}
+		Ok(Some(out))
+	}
+	pub fn subtypes_impl(&self, params: TypeHierarchySubtypesParams) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+		let item = params.item;
+		let ModelData { model } = some!(serde_json::from_value(some!(item.data.clone())).ok());
+		let model = ModelName::from(some!(_G(model)));
+		let classes = self.model_classes(model);
+		// only the base class is refined by other classes
+		let Some((_, extensions)) = classes.split_first().filter(|(base, _)| is_item_of(base, &item)) else {
+			return Ok(Some(vec![]));
+		};
+
+		let mut out = (extensions.iter())
+			.map(|class| self.model_item(model, class))
+			.collect::<Vec<_>>();
+		let mut inheritors = (self.index.models.iter())
+			.filter(|entry| {
+				*entry.key() != model
+					&& (entry.ancestors.contains(&model)
+						|| entry.delegates.iter().any(|delegate| delegate.model == model))
+			})
+			.map(|entry| *entry.key())
+			.collect::<Vec<_>>();
+		inheritors.sort_by_key(|inheritor| _R(*inheritor));
+		for inheritor in inheritors {
+			if let Some(class) = self.model_classes(inheritor).first() {
+				out.push(self.model_item(inheritor, class));
+			}
+		}
+		Ok(Some(out))
+	}
+	/// Classes contributing to `model`, starting with its base and followed by its extensions in module load order.
+	fn model_classes(&self, model: ModelName) -> Vec<ModelLocation> {
+		let Some(entry) = self.index.models.get(&model) else {
+			return vec![];
+		};
+		let base = entry.base.clone();
+		let mut extensions = entry.descendants.clone();
+		drop(entry);
+		// a module is loaded after all of its dependencies, and therefore has more of them
+		extensions.sort_by_cached_key(|ModelLocation(location, _)| {
+			(self.index.find_module_of(&location.path.to_path()))
+				.map(|module| self.index.resolve_transitive_dependencies(module).len())
+				.unwrap_or_default()
+		});
+		base.into_iter().chain(extensions).collect()
+	}
+	fn model_item(&self, model: ModelName, class: &ModelLocation) -> TypeHierarchyItem {
+		let ModelLocation(location, _) = class;
+		let module = self.index.find_module_of(&location.path.to_path());
+		let Location { uri, range } = location.clone().into();
+		TypeHierarchyItem {
+			name: _R(model).to_string(),
+			kind: SymbolKind::CLASS,
+			tags: None,
+			detail: module.map(|module| _R(module).to_string()),
+			uri,
+			range,
+			selection_range: range,
+			data: serde_json::to_value(ModelData {
+				model: _R(model).to_string(),
+			})
+			.ok(),
+		}
+	}
+}
+
+fn is_item_of(class: &ModelLocation, item: &TypeHierarchyItem) -> bool {
+	let ModelLocation(location, _) = class;
+	location.range == item.range
+		&& (item.uri.to_file_path()).is_some_and(|path| location.path.to_path().as_path() == &*path)
+}
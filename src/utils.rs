@@ -154,7 +154,7 @@ impl<'a, T> EarlyReturn<'a, T> {
 }
 
 /// A more economical version of [Location].
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MinLoc {
 	pub path: PathSymbol,
 	pub range: Range,
@@ -354,7 +354,7 @@ pub const fn unlikely(cond: bool) -> bool {
 	}
 }
 
-#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[repr(transparent)]
 pub struct ByteOffset(pub usize);
 pub type ByteRange = core::ops::Range<ByteOffset>;
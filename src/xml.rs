@@ -15,16 +15,32 @@ use crate::prelude::*;
 
 use crate::analyze::{Scope, Type, normalize, type_cache};
 use crate::component::{ComponentTemplate, PropType};
-use crate::index::Index;
+use crate::index::{Index, RecordId};
 use crate::model::{Field, FieldKind, PropertyKind};
 use crate::record::Record;
 use crate::template::gather_templates;
 use crate::{ImStr, errloc, format_loc, some, utils::*};
 use crate::{backend::Backend, backend::Text};
 
+mod arch;
+mod call_hierarchy;
+mod context;
+mod diagnostics;
+mod domain;
+mod rename;
+mod selection;
+mod semantic_tokens;
+mod symbols;
+mod type_hierarchy;
+mod xpath;
+
 #[cfg(test)]
 mod tests;
 
+pub(crate) use arch::{ParentArch, VIEW_ARCH_SCHEME};
+pub(crate) use call_hierarchy::function_tag_calls;
+pub(crate) use diagnostics::MAGIC_FIELDS;
+
 /// All Odoo action model types that can be referenced in menuitem action attributes
 const ACTION_MODELS: &[&str] = &[
 	"ir.actions.act_window",
@@ -89,6 +105,20 @@ impl Backend {
 		rope: RopeSlice<'_>,
 		did_save: bool,
 	) -> anyhow::Result<()> {
+		let record_ranges = self.index_xml_records(root, text, uri, rope, did_save)?;
+		self.record_ranges
+			.insert(uri.path().as_str().to_string(), record_ranges.into_boxed_slice());
+		Ok(())
+	}
+	/// Indexes the records and templates of an XML file, returning their ranges in document order.
+	pub(crate) fn index_xml_records(
+		&self,
+		root: Spur,
+		text: &Text,
+		uri: &Uri,
+		rope: RopeSlice<'_>,
+		did_save: bool,
+	) -> anyhow::Result<Vec<ByteRange>> {
 		let text = match text {
 			Text::Full(full) => Cow::Borrowed(full.as_str()),
 			// Assume rope is up to date
@@ -114,6 +144,7 @@ impl Backend {
 		};
 		let path = uri.to_file_path().unwrap();
 		let path_uri = PathSymbol::strip_root(root, &path);
+		self.index.invalidate_parent_archs(path_uri);
 		loop {
 			match reader.next() {
 				Some(Ok(Token::ElementStart { local, span, .. })) => {
@@ -155,12 +186,14 @@ impl Backend {
 						};
 						let range = rope_conv(record.location.range, rope);
 						record_ranges.push(range);
-						self.index.records.insert(
-							_I(record.qualified_id()).into(),
-							record,
-							metadata,
-							record_prefix.as_deref_mut(),
-						);
+						let qualified_id = RecordId::from(_I(record.qualified_id()));
+						if record.inherit_id.is_some() && !self.index.records.contains_key(&qualified_id) {
+							// a new view may be applied before those whose parent archs are cached
+							self.index.parent_archs.clear();
+						}
+						self.index
+							.records
+							.insert(qualified_id, record, metadata, record_prefix.as_deref_mut());
 					} else if local.as_str() == "templates" {
 						let mut entries = vec![];
 						if let Err(err) = gather_templates(path_uri, &mut reader, rope, &mut entries, false) {
@@ -182,9 +215,7 @@ impl Backend {
 				_ => {}
 			}
 		}
-		self.record_ranges
-			.insert(uri.path().as_str().to_string(), record_ranges.into_boxed_slice());
-		Ok(())
+		Ok(record_ranges)
 	}
 	/// Returns `(slice, offset_at_cursor, relative_offset)`.
 	/// - `slice` spans the record range that contains the cursor.
@@ -254,6 +285,29 @@ impl Backend {
 			.find_workspace_of(&path, |_, ws| ws.completions.limit)
 			.unwrap_or_else(|| self.project_config.completions_limit.load(Relaxed));
 
+		if let Some(completions) = self.index.xml_selection_completions(
+			&slice_str,
+			offset_at_cursor.0,
+			relative_offset,
+			rope,
+			completions_limit,
+		)? {
+			return Ok(Some(completions));
+		}
+		if let Some(completions) =
+			self.xml_context_completions(&slice_str, offset_at_cursor.0, relative_offset, rope, completions_limit)?
+		{
+			return Ok(Some(completions));
+		}
+		if let Some(completions) = self.index.xml_domain_completions(
+			&slice_str,
+			offset_at_cursor.0,
+			relative_offset,
+			rope,
+			completions_limit,
+		)? {
+			return Ok(Some(completions));
+		}
 		self.index.xml_completions(
 			&path,
 			completions_limit,
@@ -279,8 +333,17 @@ impl Backend {
 	pub fn xml_jump_def(&self, params: GotoDefinitionParams, rope: RopeSlice<'_>) -> anyhow::Result<Option<Location>> {
 		let position = params.text_document_position_params.position;
 		let uri = &params.text_document_position_params.text_document.uri;
-		let (slice, cursor_by_char, _) = self.record_slice(rope, uri, position)?;
+		let (slice, cursor_by_char, relative_offset) = self.record_slice(rope, uri, position)?;
 		let slice_str = Cow::from(slice);
+		if let Some(location) = self.jump_def_spec_target(uri, &slice_str, cursor_by_char, relative_offset, rope)? {
+			return Ok(Some(location));
+		}
+		if let Some(location) = self.index.xml_domain_jump_def(&slice_str, cursor_by_char.0)? {
+			return Ok(Some(location));
+		}
+		if let Some(location) = self.xml_context_jump_def(&slice_str, cursor_by_char.0)? {
+			return Ok(Some(location));
+		}
 		let mut reader = Tokenizer::from(slice_str.as_ref());
 		let XmlRefs {
 			ref_at_cursor,
@@ -361,6 +424,9 @@ impl Backend {
 		let path = some!(uri.to_file_path());
 		let (slice, cursor_by_char, _) = self.record_slice(rope, uri, position)?;
 		let slice_str = Cow::from(slice);
+		if let Some(references) = self.xml_context_references(&slice_str, cursor_by_char.0) {
+			return Ok(Some(references));
+		}
 		let mut reader = Tokenizer::from(slice_str.as_ref());
 		let XmlRefs {
 			ref_at_cursor: cursor_value,
@@ -394,6 +460,12 @@ impl Backend {
 		let path = some!(uri.to_file_path());
 		let (slice, offset_at_cursor, relative_offset) = self.record_slice(rope, uri, position)?;
 		let slice_str = Cow::from(slice);
+		if let Some(hover) = (self.index).xml_domain_hover(&slice_str, offset_at_cursor.0, relative_offset, rope)? {
+			return Ok(Some(hover));
+		}
+		if let Some(hover) = (self.index).xml_selection_hover(&slice_str, offset_at_cursor.0, relative_offset, rope)? {
+			return Ok(Some(hover));
+		}
 		let mut reader = Tokenizer::from(slice_str.as_ref());
 		let XmlRefs {
 			ref_at_cursor,
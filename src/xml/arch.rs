@@ -0,0 +1,1176 @@
+//! Computed view architectures, i.e. the arch of a view with all of its inheriting views applied.
+//!
+//! This follows `ir.ui.view._get_combined_arch` and `odoo.tools.template_inheritance`, operating on
+//! a minimal DOM where every element remembers the view that introduced it.
+
+use std::borrow::Cow;
+use std::collections::{HashSet, VecDeque};
+use std::fmt::{Display, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use anyhow::bail;
+use tower_lsp_server::ls_types::{Diagnostic, DiagnosticSeverity, Location};
+use xmlparser::{ElementEnd, StrSpan, Token, Tokenizer};
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::index::{Index, ModuleName, RecordId};
+
+use super::xpath::{NodeAddr, XPath};
+
+/// The URI scheme of virtual documents holding computed archs, as `odoo-lsp-arch:module.xml_id.xml`.
+pub(crate) const VIEW_ARCH_SCHEME: &str = "odoo-lsp-arch";
+
+/// The priority of views which don't specify one.
+const DEFAULT_PRIORITY: i32 = 16;
+
+/// The arch an inheriting view applies its specs to, as cached by [`Index::locate_view_specs`].
+pub(crate) struct ParentArch {
+	document: ArchElement,
+	/// Files of the views applied to `document`.
+	paths: HashSet<PathSymbol>,
+}
+
+/// The view which introduced an element.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Origin {
+	pub module: ModuleName,
+	pub record: RecordId,
+}
+
+impl Display for Origin {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}: {}", _R(self.module), _R(self.record))
+	}
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ArchElement {
+	/// The qualified name, or `#document` for the document node.
+	pub name: String,
+	pub attributes: Vec<(String, String)>,
+	pub children: Vec<ArchNode>,
+	pub origin: Origin,
+	/// The start tag, relative to the file of [`Origin::record`].
+	pub span: ByteRange,
+	/// Attributes changed by `position="attributes"` specs, and by whom.
+	pub changed: Vec<(String, Origin)>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum ArchNode {
+	Element(ArchElement),
+	Text(String),
+	Comment(String),
+}
+
+/// An inheritance spec which could not be applied.
+#[derive(Debug)]
+pub(crate) struct SpecError {
+	pub origin: Origin,
+	/// The start tag of the spec, as written back by [`ArchElement::start_tag`].
+	pub spec: String,
+	pub kind: SpecErrorKind,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum SpecErrorKind {
+	NotFound,
+	Invalid(String),
+}
+
+impl SpecError {
+	fn new(spec: &ArchElement, kind: SpecErrorKind) -> Self {
+		SpecError {
+			origin: spec.origin,
+			spec: spec.start_tag(),
+			kind,
+		}
+	}
+}
+
+impl Display for SpecError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.kind {
+			SpecErrorKind::NotFound => write!(f, "{} from {} cannot be located", self.spec, self.origin),
+			SpecErrorKind::Invalid(reason) => write!(f, "{} from {}: {reason}", self.spec, self.origin),
+		}
+	}
+}
+
+impl ArchNode {
+	fn into_element(self) -> Option<ArchElement> {
+		match self {
+			ArchNode::Element(element) => Some(element),
+			ArchNode::Text(_) | ArchNode::Comment(_) => None,
+		}
+	}
+	fn is_blank(&self) -> bool {
+		matches!(self, ArchNode::Text(text) if text.trim().is_empty())
+	}
+}
+
+impl ArchElement {
+	fn new(name: String, origin: Origin, span: ByteRange) -> Self {
+		ArchElement {
+			name,
+			attributes: vec![],
+			children: vec![],
+			origin,
+			span,
+			changed: vec![],
+		}
+	}
+	/// A document node holding `root`.
+	fn document(root: ArchElement) -> Self {
+		let mut document = ArchElement::new("#document".to_string(), root.origin, root.span.clone());
+		document.children.push(ArchNode::Element(root));
+		document
+	}
+	pub fn attr(&self, name: &str) -> Option<&str> {
+		(self.attributes.iter()).find_map(|(key, value)| (key == name).then_some(value.as_str()))
+	}
+	fn set_attr(&mut self, name: &str, value: Option<String>) {
+		let idx = self.attributes.iter().position(|(key, _)| key == name);
+		match (idx, value) {
+			(Some(idx), Some(value)) => self.attributes[idx].1 = value,
+			(None, Some(value)) => self.attributes.push((name.to_string(), value)),
+			(Some(idx), None) => _ = self.attributes.remove(idx),
+			(None, None) => {}
+		}
+	}
+	fn child_elements(&self) -> impl Iterator<Item = &ArchElement> {
+		self.children.iter().filter_map(|child| match child {
+			ArchNode::Element(element) => Some(element),
+			ArchNode::Text(_) | ArchNode::Comment(_) => None,
+		})
+	}
+	/// The element at `addr`, relative to this one.
+	pub fn at(&self, addr: &[usize]) -> &ArchElement {
+		addr.iter().fold(self, |element, &idx| match &element.children[idx] {
+			ArchNode::Element(child) => child,
+			ArchNode::Text(_) | ArchNode::Comment(_) => panic!("{addr:?} is not an element"),
+		})
+	}
+	fn at_mut(&mut self, addr: &[usize]) -> &mut ArchElement {
+		addr.iter()
+			.fold(self, |element, &idx| match &mut element.children[idx] {
+				ArchNode::Element(child) => child,
+				ArchNode::Text(_) | ArchNode::Comment(_) => panic!("{addr:?} is not an element"),
+			})
+	}
+	/// Appends the addresses of all descendant elements in document order, given that this element is at `addr`.
+	pub fn descendants(&self, addr: &[usize], out: &mut Vec<NodeAddr>) {
+		for (idx, child) in self.children.iter().enumerate() {
+			if let ArchNode::Element(child) = child {
+				let mut child_addr = addr.to_vec();
+				child_addr.push(idx);
+				out.push(child_addr.clone());
+				child.descendants(&child_addr, out);
+			}
+		}
+	}
+	/// All text within this element.
+	pub fn text(&self) -> String {
+		let mut out = String::new();
+		self.collect_text(&mut out);
+		out
+	}
+	fn collect_text(&self, out: &mut String) {
+		for child in &self.children {
+			match child {
+				ArchNode::Text(text) => out.push_str(text),
+				ArchNode::Element(child) => child.collect_text(out),
+				ArchNode::Comment(_) => {}
+			}
+		}
+	}
+	/// Text directly within this element.
+	pub fn own_text(&self) -> String {
+		(self.children.iter())
+			.filter_map(|child| match child {
+				ArchNode::Text(text) => Some(text.as_str()),
+				ArchNode::Element(_) | ArchNode::Comment(_) => None,
+			})
+			.collect()
+	}
+	pub fn start_tag(&self) -> String {
+		let mut out = String::new();
+		self.write_start_tag(&mut out);
+		out.push('>');
+		out
+	}
+	fn write_start_tag(&self, out: &mut String) {
+		_ = write!(out, "<{}", self.name);
+		for (key, value) in &self.attributes {
+			_ = write!(out, " {key}=\"{}\"", value.replace('"', "&quot;"));
+		}
+	}
+	/// The address of the root element of this document node.
+	fn root(&self) -> Option<NodeAddr> {
+		let idx = (self.children.iter()).position(|child| matches!(child, ArchNode::Element(_)))?;
+		Some(vec![idx])
+	}
+	/// Finds the element targeted by `spec` within this document node, as per `locate_node`.
+	pub(crate) fn locate(&self, spec: &ArchElement) -> anyhow::Result<Option<NodeAddr>> {
+		let root = some!(self.root());
+		if spec.name == "xpath" {
+			let expr = ok!(spec.attr("expr"), "<xpath> without expr");
+			return Ok(XPath::parse(expr)?.select(self, &root).into_iter().next());
+		}
+		let mut candidates = vec![root.clone()];
+		self.at(&root).descendants(&root, &mut candidates);
+		Ok(candidates.into_iter().find(|addr| {
+			let node = self.at(addr);
+			if node.name != spec.name {
+				return false;
+			}
+			// only the name is compared, as a field appears at most once per level
+			if spec.name == "field" {
+				return node.attr("name") == spec.attr("name");
+			}
+			(spec.attributes.iter())
+				.filter(|(key, _)| key != "position" && key != "version")
+				.all(|(key, value)| node.attr(key) == Some(value.as_str()))
+		}))
+	}
+	/// Applies an inheritance spec to this document node, as per `apply_inheritance_specs`.
+	pub(crate) fn apply_inheritance(&mut self, spec: ArchElement, errors: &mut Vec<SpecError>) {
+		for spec in flatten_specs(spec) {
+			let target = match self.locate(&spec) {
+				Ok(Some(target)) => target,
+				Ok(None) => {
+					errors.push(SpecError::new(&spec, SpecErrorKind::NotFound));
+					continue;
+				}
+				Err(err) => {
+					errors.push(SpecError::new(&spec, SpecErrorKind::Invalid(err.to_string())));
+					continue;
+				}
+			};
+			match spec.attr("position").unwrap_or("inside") {
+				"inside" => {
+					let mut at = target.clone();
+					at.push(self.at(&target).children.len());
+					self.insert(at, spec.children, errors);
+				}
+				"after" => {
+					let mut at = target;
+					*at.last_mut().unwrap() += 1;
+					self.insert(at, spec.children, errors);
+				}
+				"before" => _ = self.insert(target, spec.children, errors),
+				"replace" if spec.attr("mode") == Some("inner") => {
+					let mut at = target.clone();
+					at.push(0);
+					self.at_mut(&target).children.clear();
+					self.insert(at, spec.children, errors);
+				}
+				"replace" => {
+					// the target cannot be moved into its own place, as it is removed afterwards
+					let mut children = spec.children;
+					children.retain(|child| match child {
+						ArchNode::Element(child)
+							if child.attr("position") == Some("move")
+								&& matches!(self.locate(child), Ok(Some(addr)) if addr == target) =>
+						{
+							let reason = "cannot move the element being replaced".to_string();
+							errors.push(SpecError::new(child, SpecErrorKind::Invalid(reason)));
+							false
+						}
+						_ => true,
+					});
+					// the target now sits right after the inserted nodes
+					let target = self.insert(target, children, errors);
+					let (idx, parent) = target.split_last().unwrap();
+					self.at_mut(parent).children.remove(*idx);
+				}
+				"attributes" => self.at_mut(&target).apply_attributes(&spec),
+				position => {
+					let reason = format!("invalid position `{position}`");
+					errors.push(SpecError::new(&spec, SpecErrorKind::Invalid(reason)));
+				}
+			}
+		}
+	}
+	/// Inserts `nodes` starting at `at`, moving existing elements for `position="move"` specs.
+	///
+	/// Returns the address following the inserted nodes.
+	fn insert(&mut self, mut at: NodeAddr, nodes: Vec<ArchNode>, errors: &mut Vec<SpecError>) -> NodeAddr {
+		for node in nodes {
+			let node = match node {
+				ArchNode::Element(spec) if spec.attr("position") == Some("move") => {
+					let Some(element) = self.extract(&spec, &mut at, errors) else {
+						continue;
+					};
+					ArchNode::Element(element)
+				}
+				node => node,
+			};
+			let (idx, parent) = at.split_last().unwrap();
+			self.at_mut(parent).children.insert(*idx, node);
+			*at.last_mut().unwrap() += 1;
+		}
+		at
+	}
+	/// Removes the element targeted by `spec`, keeping `at` pointed at the same place.
+	fn extract(&mut self, spec: &ArchElement, at: &mut NodeAddr, errors: &mut Vec<SpecError>) -> Option<ArchElement> {
+		let addr = match self.locate(spec) {
+			Ok(Some(addr)) => addr,
+			Ok(None) => {
+				errors.push(SpecError::new(spec, SpecErrorKind::NotFound));
+				return None;
+			}
+			Err(err) => {
+				errors.push(SpecError::new(spec, SpecErrorKind::Invalid(err.to_string())));
+				return None;
+			}
+		};
+		if at.len() > addr.len() && at.starts_with(&addr) {
+			let reason = "cannot move an element into itself".to_string();
+			errors.push(SpecError::new(spec, SpecErrorKind::Invalid(reason)));
+			return None;
+		}
+		let (idx, parent) = addr.split_last()?;
+		let element = self.at_mut(parent).children.remove(*idx).into_element();
+		let level = parent.len();
+		if at.len() > level && at[..level] == *parent && at[level] > *idx {
+			at[level] -= 1;
+		}
+		element
+	}
+	fn apply_attributes(&mut self, spec: &ArchElement) {
+		for attribute in spec.child_elements().filter(|child| child.name == "attribute") {
+			let Some(name) = attribute.attr("name") else {
+				continue;
+			};
+			let mut value = attribute.own_text();
+			if attribute.attr("add").is_some() || attribute.attr("remove").is_some() {
+				let separator = attribute.attr("separator").unwrap_or(",");
+				// a single space squashes all whitespace
+				let split = |value: &str| -> Vec<String> {
+					if separator == " " {
+						value.split_whitespace().map(str::to_string).collect()
+					} else {
+						(value.split(separator).map(str::trim))
+							.filter(|value| !value.is_empty())
+							.map(str::to_string)
+							.collect()
+					}
+				};
+				let to_add = split(attribute.attr("add").unwrap_or_default());
+				let to_remove = split(attribute.attr("remove").unwrap_or_default());
+				let values = split(self.attr(name).unwrap_or_default());
+				let values = (values.into_iter())
+					.filter(|value| !to_remove.contains(value))
+					.chain(to_add)
+					.collect::<Vec<_>>();
+				value = values.join(separator);
+			}
+			self.set_attr(name, (!value.is_empty()).then_some(value));
+			let change = (name.to_string(), spec.origin);
+			if !self.changed.contains(&change) {
+				self.changed.push(change);
+			}
+		}
+	}
+	/// Serializes the contents of this document node, annotated with the views that introduced them.
+	pub(crate) fn to_xml(&self) -> String {
+		let mut out = String::new();
+		for child in &self.children {
+			write_node(&mut out, child, None, 0);
+		}
+		out
+	}
+}
+
+/// Expands `<data>` specs, in the order they are applied.
+fn flatten_specs(spec: ArchElement) -> Vec<ArchElement> {
+	let mut out = vec![];
+	let mut specs = VecDeque::from([spec]);
+	while let Some(spec) = specs.pop_front() {
+		if spec.name == "data" {
+			specs.extend(spec.children.into_iter().filter_map(ArchNode::into_element));
+		} else {
+			out.push(spec);
+		}
+	}
+	out
+}
+
+fn write_node(out: &mut String, node: &ArchNode, parent: Option<Origin>, depth: usize) {
+	let indent = "\t".repeat(depth);
+	let element = match node {
+		ArchNode::Text(text) => {
+			let text = text.trim();
+			if !text.is_empty() {
+				_ = writeln!(out, "{indent}{text}");
+			}
+			return;
+		}
+		ArchNode::Comment(text) => {
+			_ = writeln!(out, "{indent}<!--{text}-->");
+			return;
+		}
+		ArchNode::Element(element) => element,
+	};
+	if parent != Some(element.origin) {
+		_ = writeln!(out, "{indent}<!-- {} -->", element.origin);
+	}
+	let mut changed_by = vec![];
+	for (_, origin) in &element.changed {
+		if !changed_by.contains(origin) {
+			changed_by.push(*origin);
+		}
+	}
+	for origin in changed_by {
+		let attributes = (element.changed.iter())
+			.filter(|(_, by)| *by == origin)
+			.map(|(name, _)| name.as_str())
+			.collect::<Vec<_>>();
+		_ = writeln!(out, "{indent}<!-- attributes {}: {origin} -->", attributes.join(", "));
+	}
+	out.push_str(&indent);
+	element.write_start_tag(out);
+	let children = element
+		.children
+		.iter()
+		.filter(|child| !child.is_blank())
+		.collect::<Vec<_>>();
+	match children.as_slice() {
+		[] => _ = writeln!(out, "/>"),
+		[ArchNode::Text(text)] => _ = writeln!(out, ">{}</{}>", text.trim(), element.name),
+		children => {
+			_ = writeln!(out, ">");
+			for child in children {
+				write_node(out, child, Some(element.origin), depth + 1);
+			}
+			_ = writeln!(out, "{indent}</{}>", element.name);
+		}
+	}
+}
+
+fn qualified_name(prefix: StrSpan, local: StrSpan) -> String {
+	if prefix.as_str().is_empty() {
+		local.as_str().to_string()
+	} else {
+		format!("{}:{}", prefix.as_str(), local.as_str())
+	}
+}
+
+/// Reads nodes up to the closing tag of the enclosing element.
+///
+/// `offset` is the offset of the tokenized text relative to the start of the file.
+fn read_children(reader: &mut Tokenizer, offset: usize, origin: Origin) -> anyhow::Result<Vec<ArchNode>> {
+	let mut children = vec![];
+	while let Some(token) = reader.next() {
+		match token? {
+			Token::ElementStart { prefix, local, span } => {
+				let name = qualified_name(prefix, local);
+				children.push(ArchNode::Element(read_element(
+					reader,
+					name,
+					offset + span.start(),
+					offset,
+					origin,
+				)?));
+			}
+			Token::Text { text } => children.push(ArchNode::Text(text.as_str().to_string())),
+			Token::Cdata { text, .. } => children.push(ArchNode::Text(text.as_str().to_string())),
+			Token::Comment { text, .. } => children.push(ArchNode::Comment(text.as_str().to_string())),
+			Token::ElementEnd {
+				end: ElementEnd::Close(..),
+				..
+			} => break,
+			_ => {}
+		}
+	}
+	Ok(children)
+}
+
+/// Reads the rest of an element whose start tag began at `start`.
+fn read_element(
+	reader: &mut Tokenizer,
+	name: String,
+	start: usize,
+	offset: usize,
+	origin: Origin,
+) -> anyhow::Result<ArchElement> {
+	let mut element = ArchElement::new(name, origin, ByteOffset(start)..ByteOffset(start));
+	while let Some(token) = reader.next() {
+		match token? {
+			Token::Attribute {
+				prefix, local, value, ..
+			} => {
+				(element.attributes).push((qualified_name(prefix, local), value.as_str().to_string()));
+			}
+			Token::ElementEnd { end, span } => {
+				element.span.end = ByteOffset(offset + span.end());
+				if let ElementEnd::Open = end {
+					element.children = read_children(reader, offset, origin)?;
+				}
+				return Ok(element);
+			}
+			_ => {}
+		}
+	}
+	bail!("<{}> is not closed", element.name)
+}
+
+/// The arch of a view, and how it takes part in inheritance.
+struct ViewSource {
+	arch: Option<ArchElement>,
+	priority: i32,
+	/// Whether this view starts a new arch instead of extending its parent's.
+	primary: bool,
+}
+
+/// Reads the `<record>` or `<template>` element starting at `offset`.
+fn read_view(contents: &str, offset: usize, origin: Origin) -> anyhow::Result<ViewSource> {
+	let mut reader = Tokenizer::from(&contents[offset..]);
+	let mut view = loop {
+		match reader.next() {
+			Some(Ok(Token::ElementStart { prefix, local, span })) => {
+				let name = qualified_name(prefix, local);
+				break read_element(&mut reader, name, offset + span.start(), offset, origin)?;
+			}
+			Some(Ok(_)) => {}
+			Some(Err(err)) => return Err(err.into()),
+			None => bail!("No view at {offset}"),
+		}
+	};
+	let mut source = ViewSource {
+		arch: None,
+		priority: DEFAULT_PRIORITY,
+		primary: false,
+	};
+	match view.name.as_str() {
+		"record" => {
+			for field in view.children.into_iter().filter_map(ArchNode::into_element) {
+				if field.name != "field" {
+					continue;
+				}
+				match field.attr("name") {
+					Some("arch") => source.arch = field.children.into_iter().find_map(ArchNode::into_element),
+					Some("priority") => source.priority = field.own_text().trim().parse().unwrap_or(DEFAULT_PRIORITY),
+					Some("mode") => source.primary = field.own_text().trim() == "primary",
+					_ => {}
+				}
+			}
+		}
+		"template" => {
+			source.priority =
+				(view.attr("priority").and_then(|priority| priority.parse().ok())).unwrap_or(DEFAULT_PRIORITY);
+			source.primary = matches!(view.attr("primary"), Some("True" | "true" | "1"));
+			// as done by `_tag_template`
+			let arch = if view.attr("inherit_id").is_some() {
+				let mut arch = ArchElement::new("data".to_string(), origin, view.span.clone());
+				arch.children = view.children;
+				arch
+			} else {
+				view.name = "t".to_string();
+				view.attributes = vec![("t-name".to_string(), _R(origin.record).to_string())];
+				view
+			};
+			source.arch = Some(arch);
+		}
+		_ => bail!("<{}> is not a view", view.name),
+	}
+	Ok(source)
+}
+
+/// The arch of a view with all of its inheriting views applied.
+pub(crate) struct ComputedArch {
+	pub document: ArchElement,
+	pub errors: Vec<SpecError>,
+}
+
+impl ComputedArch {
+	pub(crate) fn to_xml(&self) -> String {
+		let mut out = String::new();
+		for error in &self.errors {
+			_ = writeln!(&mut out, "<!-- error: {error} -->");
+		}
+		out.push_str(&self.document.to_xml());
+		out
+	}
+}
+
+/// Where a spec of an inheriting view lands in the arch it is applied to.
+#[derive(Debug)]
+pub(crate) struct SpecTarget {
+	/// The start tag of the spec, relative to the file of the inheriting view.
+	pub span: ByteRange,
+	/// The start tag of the spec, as written back by [`ArchElement::start_tag`].
+	pub spec: String,
+	/// The element located by the spec, and its start tag relative to the file of [`Origin::record`].
+	pub target: Result<(Origin, ByteRange), SpecErrorKind>,
+}
+
+impl Index {
+	/// Computes the arch of the view `id` as Odoo would, starting from its closest primary ancestor.
+	///
+	/// `read` provides the contents of each file, so that unsaved changes can be taken into account.
+	pub(crate) fn compute_view_arch(
+		&self,
+		id: RecordId,
+		read: &dyn Fn(&Path) -> Option<Rope>,
+	) -> anyhow::Result<ComputedArch> {
+		let mut builder = ArchBuilder::new(self, read);
+		let (document, _) = builder.build(id)?;
+		Ok(ComputedArch {
+			document,
+			errors: builder.errors,
+		})
+	}
+	/// Locates each spec of the inheriting view `id` in the arch it is applied to, that is the arch
+	/// computed from the views of its module and its dependencies which come before it.
+	pub(crate) fn locate_view_specs(
+		&self,
+		id: RecordId,
+		read: &dyn Fn(&Path) -> Option<Rope>,
+	) -> anyhow::Result<Vec<SpecTarget>> {
+		let cached = self.parent_archs.get(&id).map(|parent| parent.document.clone());
+		let (mut document, arch) = match cached {
+			Some(document) => (document, ArchBuilder::new(self, read).load(id)?.source.arch),
+			None => {
+				let module = ok!(self.records.get(&id), "No view with ID {}", _R(id)).module;
+				let modules = self.resolve_transitive_dependencies(module);
+				let mut builder = ArchBuilder::new(self, read);
+				builder.stop_at = Some(id);
+				builder.modules = Some(modules);
+				let (document, ControlFlow::Break(arch)) = builder.build(id)? else {
+					return Ok(vec![]);
+				};
+				let parent = ParentArch {
+					document: document.clone(),
+					paths: builder.paths,
+				};
+				self.parent_archs.insert(id, parent);
+				(document, arch)
+			}
+		};
+		let Some(arch) = arch else {
+			return Ok(vec![]);
+		};
+		let mut targets = vec![];
+		for spec in flatten_specs(arch) {
+			let target = match document.locate(&spec) {
+				Ok(Some(addr)) => {
+					let element = document.at(&addr);
+					Ok((element.origin, element.span.clone()))
+				}
+				Ok(None) => Err(SpecErrorKind::NotFound),
+				Err(err) => Err(SpecErrorKind::Invalid(err.to_string())),
+			};
+			targets.push(SpecTarget {
+				span: spec.span.clone(),
+				spec: spec.start_tag(),
+				target,
+			});
+			// later specs may target elements added by earlier ones
+			document.apply_inheritance(spec, &mut vec![]);
+		}
+		Ok(targets)
+	}
+	/// Forgets the parent archs computed from the views in `path`.
+	pub(crate) fn invalidate_parent_archs(&self, path: PathSymbol) {
+		self.parent_archs.retain(|_, parent| !parent.paths.contains(&path));
+	}
+}
+
+struct ArchBuilder<'a> {
+	index: &'a Index,
+	read: &'a dyn Fn(&Path) -> Option<Rope>,
+	/// The requested view and its ancestors, the only primary views to be applied.
+	chain: HashSet<RecordId>,
+	visited: HashSet<RecordId>,
+	errors: Vec<SpecError>,
+	/// If set, the view at which to stop, returning its arch instead of applying it.
+	stop_at: Option<RecordId>,
+	/// If set, the only modules whose views are applied.
+	modules: Option<HashSet<ModuleName>>,
+	/// Files of the views applied so far.
+	paths: HashSet<PathSymbol>,
+}
+
+struct View {
+	id: RecordId,
+	path: PathSymbol,
+	source: ViewSource,
+	/// Siblings are applied by priority, then in module dependency order.
+	order: (i32, usize, String, u32),
+}
+
+impl<'a> ArchBuilder<'a> {
+	fn new(index: &'a Index, read: &'a dyn Fn(&Path) -> Option<Rope>) -> Self {
+		ArchBuilder {
+			index,
+			read,
+			chain: HashSet::new(),
+			visited: HashSet::new(),
+			errors: vec![],
+			stop_at: None,
+			modules: None,
+			paths: HashSet::new(),
+		}
+	}
+	fn load(&self, id: RecordId) -> anyhow::Result<View> {
+		let (module, location) = {
+			let record = ok!(self.index.records.get(&id), "No view with ID {}", _R(id));
+			(record.module, record.location.clone())
+		};
+		let path = location.path.to_path();
+		let rope = ok!((self.read)(&path), "Could not read {}", path.display());
+		let ByteOffset(offset) = rope_conv(location.range.start, rope.slice(..));
+		let contents = Cow::from(rope.slice(..));
+		let source = read_view(&contents, offset, Origin { module, record: id })?;
+		// a module depends on strictly fewer modules than its dependents
+		let rank = self.index.resolve_transitive_dependencies(module).len();
+		Ok(View {
+			id,
+			path: location.path,
+			order: (
+				source.priority,
+				rank,
+				location.path.to_string(),
+				location.range.start.line,
+			),
+			source,
+		})
+	}
+	fn build(&mut self, id: RecordId) -> anyhow::Result<(ArchElement, ControlFlow<Option<ArchElement>>)> {
+		let mut root = id;
+		while let Some(parent) = self.index.records.get(&root).and_then(|record| record.inherit_id) {
+			if !self.chain.insert(root) {
+				bail!("Inheritance cycle at {}", _R(root));
+			}
+			root = parent;
+		}
+		self.chain.insert(root);
+		self.visited.insert(root);
+		let mut view = self.load(root)?;
+		self.paths.insert(view.path);
+		let arch = ok!(view.source.arch.take(), "View {} has no arch", _R(root));
+		let mut document = ArchElement::document(arch);
+		let flow = self.apply_children(&mut document, root);
+		Ok((document, flow))
+	}
+	/// Applies the views inheriting from `parent`, and their own inheriting views, depth-first.
+	///
+	/// Breaks with the arch of [`ArchBuilder::stop_at`] once it is reached.
+	fn apply_children(&mut self, document: &mut ArchElement, parent: RecordId) -> ControlFlow<Option<ArchElement>> {
+		let children = (self.index.records.by_inherit_id(&parent))
+			.filter(|record| !record.deleted)
+			.filter(|record| {
+				self.modules
+					.as_ref()
+					.is_none_or(|modules| modules.contains(&record.module))
+			})
+			.map(|record| *record.key())
+			.collect::<Vec<_>>();
+		let children = children
+			.into_iter()
+			.filter(|id| self.visited.insert(*id))
+			.collect::<Vec<_>>();
+		let mut views = (children.into_iter())
+			.filter_map(|id| {
+				self.load(id)
+					.inspect_err(|err| debug!("(compute_view_arch) {err}"))
+					.ok()
+			})
+			.filter(|view| !view.source.primary || self.chain.contains(&view.id))
+			.collect::<Vec<_>>();
+		views.sort_by(|lhs, rhs| lhs.order.cmp(&rhs.order));
+		for mut view in views {
+			let arch = view.source.arch.take();
+			if self.stop_at == Some(view.id) {
+				return ControlFlow::Break(arch);
+			}
+			if let Some(arch) = arch {
+				self.paths.insert(view.path);
+				document.apply_inheritance(arch, &mut self.errors);
+			}
+			self.apply_children(document, view.id)?;
+		}
+		ControlFlow::Continue(())
+	}
+}
+
+#[derive(Deserialize)]
+pub struct ViewArchParams {
+	/// The qualified XML ID of the view.
+	pub id: String,
+}
+
+impl Backend {
+	#[allow(clippy::unused_async)] // reason: custom method
+	pub async fn view_arch(&self, params: ViewArchParams) -> tower_lsp_server::jsonrpc::Result<Option<String>> {
+		let id = some!(_G(&params.id));
+		let read = |path: &Path| self.rope_of(path);
+		match self.index.compute_view_arch(id.into(), &read) {
+			Ok(arch) => Ok(Some(arch.to_xml())),
+			Err(err) => {
+				warn!("(view_arch) {err}");
+				Ok(None)
+			}
+		}
+	}
+	/// Contents of files for [`Index::locate_view_specs`], with `rope` standing in for the file at `current`.
+	fn view_source<'a>(&'a self, current: &'a Path, rope: RopeSlice<'a>) -> impl Fn(&Path) -> Option<Rope> + 'a {
+		move |path: &Path| {
+			if path == current {
+				Some(Rope::from(rope))
+			} else {
+				self.rope_of_unlocked(path)
+			}
+		}
+	}
+	/// Reports the specs of the inheriting view in `slice` which match nothing in its parent's arch.
+	///
+	/// `relative_offset` is the offset of `slice` relative to the start of `rope`.
+	pub(crate) fn diagnose_view_specs(
+		&self,
+		uri: &Uri,
+		slice: RopeSlice<'_>,
+		current_module: ModuleName,
+		rope: RopeSlice<'_>,
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
+		let Some(id) = self.inheriting_view_id(&Cow::from(slice), current_module) else {
+			return;
+		};
+		let Some(path) = uri.to_file_path() else {
+			return;
+		};
+		let read = self.view_source(&path, rope);
+		let targets = match self.index.locate_view_specs(id, &read) {
+			Ok(targets) => targets,
+			Err(err) => {
+				debug!("(diagnose_view_specs) {err}");
+				return;
+			}
+		};
+		for target in targets {
+			// unsupported expressions may still be valid
+			let Err(SpecErrorKind::NotFound) = target.target else {
+				continue;
+			};
+			diagnostics.push(Diagnostic {
+				range: rope_conv(target.span, rope),
+				message: format!("`{}` matches nothing in the parent view", target.spec),
+				severity: Some(DiagnosticSeverity::ERROR),
+				..Default::default()
+			});
+		}
+	}
+	/// Jumps from the start tag of a spec to the element it targets.
+	///
+	/// `offset_at_cursor` is relative to `slice`, whose offset relative to the start of `rope` is `relative_offset`.
+	pub(crate) fn jump_def_spec_target(
+		&self,
+		uri: &Uri,
+		slice: &str,
+		offset_at_cursor: ByteOffset,
+		relative_offset: usize,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<Location>> {
+		if !is_spec_at(slice, offset_at_cursor.0) {
+			return Ok(None);
+		}
+		let path = some!(uri.to_file_path());
+		let current_module = some!(self.index.find_module_of(&path));
+		let id = some!(self.inheriting_view_id(slice, current_module));
+		let read = self.view_source(&path, rope);
+		let offset = relative_offset + offset_at_cursor.0;
+		// fall back to other references if the arch can't be computed
+		let targets = (self.index.locate_view_specs(id, &read))
+			.inspect_err(|err| debug!("(jump_def_spec_target) {err}"))
+			.unwrap_or_default();
+		let target =
+			some!((targets.into_iter()).find(|target| target.span.start.0 <= offset && offset <= target.span.end.0));
+		let (origin, span) = some!(target.target.ok());
+		let target_path = {
+			let record = some!(self.index.records.get(&origin.record));
+			record.location.path.to_path()
+		};
+		let target_rope = some!(read(&target_path));
+		Ok(Some(Location {
+			uri: some!(Uri::from_file_path(&target_path)),
+			range: rope_conv(span, target_rope.slice(..)),
+		}))
+	}
+	/// The ID of the record or template in `slice`, if it inherits from another view.
+	fn inheriting_view_id(&self, slice: &str, current_module: ModuleName) -> Option<RecordId> {
+		for token in Tokenizer::from(slice) {
+			match token.ok()? {
+				Token::ElementStart { local, .. } if !matches!(local.as_str(), "record" | "template") => return None,
+				Token::Attribute { local, value, .. } if local.as_str() == "id" => {
+					// records are keyed by the module defining them, as in `Record::from_reader`
+					let id = value.split_once('.').map_or(value.as_str(), |(_, id)| id);
+					let id = RecordId::from(_G(format!("{}.{id}", _R(current_module)))?);
+					let record = self.index.records.get(&id)?;
+					return record.inherit_id.is_some().then_some(id);
+				}
+				Token::ElementEnd { .. } => return None,
+				_ => {}
+			}
+		}
+		None
+	}
+}
+
+/// Whether the cursor at `offset` is on the start tag of an `<xpath>`, or of any element with a `position`.
+fn is_spec_at(slice: &str, offset: usize) -> bool {
+	let mut in_tag = false;
+	let mut is_spec = false;
+	for token in Tokenizer::from(slice) {
+		match token {
+			Ok(Token::ElementStart { local, span, .. }) => {
+				if span.start() > offset {
+					return false;
+				}
+				in_tag = true;
+				is_spec = local.as_str() == "xpath";
+			}
+			Ok(Token::Attribute { local, .. }) if in_tag && local.as_str() == "position" => is_spec = true,
+			Ok(Token::ElementEnd {
+				end: ElementEnd::Open | ElementEnd::Empty,
+				span,
+			}) if in_tag => {
+				if offset <= span.end() {
+					return is_spec;
+				}
+				in_tag = false;
+			}
+			Ok(_) => {}
+			Err(_) => return false,
+		}
+	}
+	false
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use std::path::Path;
+
+	use pretty_assertions::assert_eq;
+	use xmlparser::{Token, Tokenizer};
+
+	use super::{ArchElement, Origin, SpecErrorKind, read_children};
+	use crate::index::Index;
+	use crate::prelude::*;
+	use crate::record::Record;
+
+	fn parse(xml: &str, module: &str, record: &str) -> ArchElement {
+		let origin = Origin {
+			module: _I(module).into(),
+			record: _I(record).into(),
+		};
+		let mut reader = Tokenizer::from(xml);
+		let mut root = read_children(&mut reader, 0, origin).unwrap().into_iter();
+		ArchElement::document(root.find_map(super::ArchNode::into_element).unwrap())
+	}
+
+	/// Parses `xml` into a document node, with the root element at `[0]`.
+	pub(crate) fn parse_doc(xml: &str) -> ArchElement {
+		parse(xml, "base", "base.view_test")
+	}
+
+	#[test]
+	fn test_locate_view_specs() {
+		let xml = r#"<odoo>
+<record id="view_form" model="ir.ui.view">
+	<field name="arch" type="xml">
+		<form><field name="partner_id"/></form>
+	</field>
+</record>
+<record id="view_form_inherit" model="ir.ui.view">
+	<field name="inherit_id" ref="view_form"/>
+	<field name="arch" type="xml">
+		<data>
+			<xpath expr="//field[@name='partner_id']" position="after"><field name="date"/></xpath>
+			<field name="date" position="attributes"/>
+			<field name="missing" position="replace"/>
+		</data>
+	</field>
+</record>
+</odoo>"#;
+		let index = Index::default();
+		let rope = Rope::from_str(xml);
+		let mut reader = Tokenizer::from(xml);
+		let mut records = vec![];
+		while let Some(Ok(token)) = reader.next() {
+			if let Token::ElementStart { local, span, .. } = token
+				&& local.as_str() == "record"
+			{
+				let offset = ByteOffset(span.start());
+				let record = Record::from_reader(
+					offset,
+					_I("test").into(),
+					PathSymbol::empty(),
+					&mut reader,
+					rope.slice(..),
+				);
+				records.extend(record.unwrap());
+			}
+		}
+		index.records.append(records);
+
+		let read = |_: &Path| Some(rope.clone());
+		let targets = index
+			.locate_view_specs(_I("test.view_form_inherit").into(), &read)
+			.unwrap();
+		let targets = (targets.iter())
+			.map(|target| {
+				let located = target.target.as_ref();
+				let located = located.map(|(origin, span)| (_R(origin.record), &xml[span.start.0..span.end.0]));
+				(target.spec.as_str(), located)
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(
+			targets,
+			[
+				(
+					r#"<xpath expr="//field[@name='partner_id']" position="after">"#,
+					Ok(("test.view_form", r#"<field name="partner_id"/>"#))
+				),
+				(
+					r#"<field name="date" position="attributes">"#,
+					Ok(("test.view_form_inherit", r#"<field name="date"/>"#))
+				),
+				(
+					r#"<field name="missing" position="replace">"#,
+					Err(&SpecErrorKind::NotFound)
+				),
+			]
+		);
+
+		// the parent arch is reused until its file changes
+		let renamed = Rope::from_str(&xml.replace(r#"<form><field name="partner_id"/>"#, "<form>"));
+		let read = |_: &Path| Some(renamed.clone());
+		let located = |index: &Index| {
+			let targets = index.locate_view_specs(_I("test.view_form_inherit").into(), &read);
+			targets.unwrap()[0].target.is_ok()
+		};
+		assert!(located(&index));
+		index.invalidate_parent_archs(PathSymbol::empty());
+		assert!(!located(&index));
+	}
+
+	#[test]
+	fn test_apply_inheritance() {
+		let mut doc = parse_doc(
+			r#"<form>
+	<sheet>
+		<group>
+			<field name="partner_id"/>
+			<field name="date"/>
+		</group>
+	</sheet>
+</form>"#,
+		);
+		let spec = parse(
+			r#"<data>
+	<xpath expr="//field[@name='partner_id']" position="after">
+		<field name="order_id"/>
+	</xpath>
+	<field name="date" position="attributes">
+		<attribute name="invisible">1</attribute>
+		<attribute name="class" add="o_bold"/>
+	</field>
+	<field name="missing" position="replace"/>
+	<field name="date" position="after">
+		<field name="partner_id" position="move"/>
+	</field>
+	<sheet position="before">
+		<header><button name="action_confirm" string="Confirm"/></header>
+	</sheet>
+</data>"#,
+			"sale",
+			"sale.view_test_inherit",
+		);
+		let mut errors = vec![];
+		doc.apply_inheritance(
+			spec.children.into_iter().next().unwrap().into_element().unwrap(),
+			&mut errors,
+		);
+		let errors = errors
+			.iter()
+			.map(|error| (error.spec.as_str(), &error.kind))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			errors,
+			[(r#"<field name="missing" position="replace">"#, &SpecErrorKind::NotFound)]
+		);
+		assert_eq!(
+			doc.to_xml(),
+			r#"<!-- base: base.view_test -->
+<form>
+	<!-- sale: sale.view_test_inherit -->
+	<header>
+		<button name="action_confirm" string="Confirm"/>
+	</header>
+	<sheet>
+		<group>
+			<!-- sale: sale.view_test_inherit -->
+			<field name="order_id"/>
+			<!-- attributes invisible, class: sale: sale.view_test_inherit -->
+			<field name="date" invisible="1" class="o_bold"/>
+			<field name="partner_id"/>
+		</group>
+	</sheet>
+</form>
+"#
+		);
+	}
+
+	/// Applies the first spec of `specs` to `doc`, returning the errors and the names of the resulting fields.
+	fn apply_replace(doc: &str, specs: &str) -> (Vec<(String, SpecErrorKind)>, Vec<String>) {
+		let mut doc = parse_doc(doc);
+		let spec = parse(specs, "sale", "sale.view_test_inherit");
+		let mut errors = vec![];
+		doc.apply_inheritance(
+			spec.children.into_iter().next().unwrap().into_element().unwrap(),
+			&mut errors,
+		);
+		let errors = (errors.into_iter()).map(|error| (error.spec, error.kind)).collect();
+		let mut addrs = vec![];
+		doc.descendants(&[], &mut addrs);
+		let fields = (addrs.iter())
+			.map(|addr| doc.at(addr))
+			.filter(|element| element.name == "field")
+			.filter_map(|element| element.attr("name").map(String::from))
+			.collect();
+		(errors, fields)
+	}
+
+	#[test]
+	fn test_replace_moving_later_sibling() {
+		let (errors, fields) = apply_replace(
+			r#"<form><field name="a"/><field name="b"/><field name="c"/></form>"#,
+			r#"<data><field name="a" position="replace"><field name="c" position="move"/><field name="d"/></field></data>"#,
+		);
+		assert!(errors.is_empty(), "{errors:?}");
+		assert_eq!(fields, ["c", "d", "b"]);
+	}
+
+	#[test]
+	fn test_replace_moving_target() {
+		let (errors, fields) = apply_replace(
+			r#"<form><field name="a"/><field name="b"/><field name="c"/></form>"#,
+			r#"<data><field name="c" position="replace"><field name="c" position="move"/><field name="d"/></field></data>"#,
+		);
+		let reason = "cannot move the element being replaced".to_string();
+		assert_eq!(
+			errors,
+			[(
+				r#"<field name="c" position="move">"#.to_string(),
+				SpecErrorKind::Invalid(reason)
+			)]
+		);
+		assert_eq!(fields, ["a", "b", "d"]);
+	}
+}
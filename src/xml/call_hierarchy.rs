@@ -0,0 +1,202 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use xmlparser::{ElementEnd, Token, Tokenizer};
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::model::ModelName;
+
+/// A record calling model methods through `<button type="object">`.
+#[derive(Debug)]
+pub(crate) struct XmlCall {
+	/// The ID of the record, as written.
+	pub id: ImStr,
+	pub range: ByteRange,
+	pub id_range: ByteRange,
+	/// Ranges of the method name within the record.
+	pub calls: Vec<ByteRange>,
+}
+
+/// XML extensions for [`crate::call_hierarchy`].
+impl Backend {
+	/// Records among `records` with buttons calling `method` on any of `models`.
+	pub(crate) fn xml_method_calls(
+		&self,
+		rope: RopeSlice<'_>,
+		records: &[(Range, ModelName)],
+		models: &HashSet<ModelName>,
+		method: &str,
+	) -> Vec<XmlCall> {
+		let mut out = vec![];
+		for &(range, model) in records {
+			let range: ByteRange = rope_conv(range, rope);
+			let Ok(slice) = rope.try_slice(range.erase()) else {
+				continue;
+			};
+			let slice = Cow::from(slice);
+			let buttons = object_buttons(&slice, method);
+			if buttons.is_empty() {
+				continue;
+			}
+			let start = range.start.0;
+			let mut calls = self.xml_property_edits(rope, range.clone(), model, models, method);
+			calls.retain(|call| buttons.contains(&(call.start.0 - start)));
+			if calls.is_empty() {
+				continue;
+			}
+			let (id, id_range) = record_id(&slice).unwrap_or(("", 0..0));
+			out.push(XmlCall {
+				id: ImStr::from(id),
+				id_range: ByteOffset(start + id_range.start)..ByteOffset(start + id_range.end),
+				range,
+				calls,
+			});
+		}
+		out
+	}
+}
+
+/// Offsets of the `name` attributes of `<button type="object">` calling `method`.
+fn object_buttons(contents: &str, method: &str) -> Vec<usize> {
+	let mut out = vec![];
+	if !contents.contains(method) {
+		return out;
+	}
+	let mut in_button = false;
+	let mut is_object = false;
+	let mut name = None;
+	for token in Tokenizer::from(contents) {
+		match token {
+			Ok(Token::ElementStart { local, .. }) => {
+				in_button = local.as_str() == "button";
+				is_object = false;
+				name = None;
+			}
+			Ok(Token::Attribute { local, value, .. }) if in_button => match local.as_str() {
+				"type" => is_object = value.as_str() == "object",
+				"name" if value.as_str() == method => name = Some(value.start()),
+				_ => {}
+			},
+			Ok(Token::ElementEnd {
+				end: ElementEnd::Open | ElementEnd::Empty,
+				..
+			}) if in_button => {
+				in_button = false;
+				if is_object && let Some(name) = name.take() {
+					out.push(name);
+				}
+			}
+			Ok(_) => {}
+			Err(_) => break,
+		}
+	}
+	out
+}
+
+/// The `id` attribute of the first element in `contents`, along with its range.
+fn record_id(contents: &str) -> Option<(&str, core::ops::Range<usize>)> {
+	for token in Tokenizer::from(contents) {
+		match token.ok()? {
+			Token::Attribute { local, value, .. } if local.as_str() == "id" => {
+				return Some((value.as_str(), value.start()..value.end()));
+			}
+			Token::ElementEnd { .. } => return None,
+			_ => {}
+		}
+	}
+	None
+}
+
+/// Ranges of the `name` attributes of `<function>` tags calling `method` on any of `models`.
+pub(crate) fn function_tag_calls(contents: &str, models: &HashSet<ModelName>, method: &str) -> Vec<ByteRange> {
+	let mut out = vec![];
+	if !contents.contains(method) {
+		return out;
+	}
+	let mut in_function = false;
+	let mut model = None;
+	let mut name = None;
+	for token in Tokenizer::from(contents) {
+		match token {
+			Ok(Token::ElementStart { local, .. }) => {
+				in_function = local.as_str() == "function";
+				model = None;
+				name = None;
+			}
+			Ok(Token::Attribute { local, value, .. }) if in_function => match local.as_str() {
+				"model" => model = Some(value),
+				"name" => name = Some(value),
+				_ => {}
+			},
+			Ok(Token::ElementEnd {
+				end: ElementEnd::Open | ElementEnd::Empty,
+				..
+			}) if in_function => {
+				in_function = false;
+				let Some(name) = name.take().filter(|name| name.as_str() == method) else {
+					continue;
+				};
+				let Some(model) = model.take().and_then(|model| _G(model.as_str())) else {
+					continue;
+				};
+				if models.contains(&ModelName::from(model)) {
+					out.push(ByteOffset(name.start())..ByteOffset(name.end()));
+				}
+			}
+			Ok(_) => {}
+			Err(_) => break,
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashSet;
+
+	use pretty_assertions::assert_eq;
+
+	use super::{function_tag_calls, object_buttons, record_id};
+	use crate::model::ModelName;
+	use crate::prelude::*;
+
+	#[test]
+	fn test_function_tag_calls() {
+		let contents = r#"<odoo>
+	<function model="res.partner" name="_init_data"/>
+	<function model="res.users" name="_init_data">
+		<value eval="[]"/>
+	</function>
+	<record id="foo" model="res.partner">
+		<field name="_init_data"/>
+	</record>
+</odoo>"#;
+		let models = HashSet::from([ModelName::from(_I("res.partner"))]);
+		let start = contents.find("_init_data").unwrap();
+		assert_eq!(
+			function_tag_calls(contents, &models, "_init_data"),
+			[ByteOffset(start)..ByteOffset(start + "_init_data".len())]
+		);
+	}
+
+	#[test]
+	fn test_object_buttons() {
+		let contents = r#"<record id="view_order_form" model="ir.ui.view">
+	<field name="arch" type="xml">
+		<form>
+			<button name="action_confirm" type="object"/>
+			<button type="action" name="action_confirm"/>
+			<button type="object" name="action_cancel"/>
+		</form>
+	</field>
+</record>"#;
+		let start = contents.find("action_confirm").unwrap();
+		assert_eq!(object_buttons(contents, "action_confirm"), [start]);
+		assert_eq!(
+			record_id(contents),
+			Some(("view_order_form", 12..12 + "view_order_form".len()))
+		);
+	}
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+
+use tower_lsp_server::ls_types::*;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::index::Index;
+use crate::model::ModelName;
+
+use super::domain::{ExpressionKind, parse_xml_domain};
+
+/// A key of a context dictionary within a record.
+struct XmlContextKey {
+	/// Range of the key relative to the record, excluding quotes.
+	range: Range<usize>,
+	key: String,
+	model: ModelName,
+}
+
+/// XML extensions for [`crate::context`].
+impl Index {
+	/// Keys of the contexts of view fields and filters, and of action `context` fields.
+	fn xml_context_keys(&self, slice: &str) -> Vec<XmlContextKey> {
+		let mut out = vec![];
+		let contexts =
+			(self.xml_expressions(slice).into_iter()).filter(|expression| expression.kind == ExpressionKind::Context);
+		for context in contexts {
+			let Some(parsed) = parse_xml_domain(slice, context) else {
+				continue;
+			};
+			for node in PreTravel::new(parsed.tree.root_node()) {
+				if node.kind() != "string" {
+					continue;
+				}
+				// `{'key'}` while the value is yet to be typed
+				let is_key = node.parent().is_some_and(|parent| match parent.kind() {
+					"pair" => parent.child_by_field_name("key") == Some(node),
+					"dictionary" | "set" => true,
+					_ => false,
+				});
+				if !is_key {
+					continue;
+				}
+				let range = node.byte_range().shrink(1);
+				out.push(XmlContextKey {
+					key: parsed.source[range.clone()].to_string(),
+					range: parsed.record_range(range.map_unit(ByteOffset), 0).erase(),
+					model: parsed.model,
+				});
+			}
+		}
+		out
+	}
+	/// The named filters of the search views of `model`, along with where they are declared.
+	///
+	/// `read` provides the contents of each view file.
+	pub(crate) fn search_filters(
+		&self,
+		model: ModelName,
+		read: &dyn Fn(&Path) -> Option<Rope>,
+	) -> Vec<(String, MinLoc)> {
+		let views = (self.records.views_by_model(&model))
+			.filter(|view| !view.deleted && view.inherit_id.is_none())
+			.map(|view| *view.key())
+			.collect::<Vec<_>>();
+		let mut ropes = HashMap::new();
+		let mut filters = Vec::<(String, MinLoc)>::new();
+		for view in views {
+			let arch = match self.compute_view_arch(view, read) {
+				Ok(arch) => arch,
+				Err(err) => {
+					debug!("(search_filters) {err}");
+					continue;
+				}
+			};
+			let mut addrs = vec![];
+			arch.document.descendants(&[], &mut addrs);
+			for addr in addrs {
+				let element = arch.document.at(&addr);
+				if element.name != "filter" {
+					continue;
+				}
+				let Some(name) = element.attr("name") else { continue };
+				if filters.iter().any(|(known, _)| known == name) {
+					continue;
+				}
+				let Some(path) = (self.records.get(&element.origin.record)).map(|record| record.location.path) else {
+					continue;
+				};
+				let rope = ropes.entry(path).or_insert_with(|| read(&path.to_path()));
+				let Some(rope) = rope else { continue };
+				let range = rope_conv(element.span.clone(), rope.slice(..));
+				filters.push((name.to_string(), MinLoc { path, range }));
+			}
+		}
+		filters
+	}
+}
+
+impl Backend {
+	pub(super) fn xml_context_completions(
+		&self,
+		slice: &str,
+		offset: usize,
+		relative_offset: usize,
+		rope: RopeSlice<'_>,
+		completions_limit: usize,
+	) -> anyhow::Result<Option<CompletionResponse>> {
+		let key = some!((self.index.xml_context_keys(slice).into_iter()).find(|key| key.range.contains_end(offset)));
+		// escaped characters make the raw key longer than the parsed one
+		let needle = (key.key.get(..offset - key.range.start)).unwrap_or(&key.key);
+		let range = key.range.map_unit(|unit| ByteOffset(unit + relative_offset));
+		let read = |path: &Path| self.rope_of_unlocked(path);
+		let mut items = MaxVec::new(completions_limit);
+		self.index
+			.complete_context_key(needle, Some(key.model), rope_conv(range, rope), &read, &mut items);
+		Ok(Some(CompletionResponse::List(CompletionList {
+			is_incomplete: !items.has_space(),
+			items: items.into_inner(),
+		})))
+	}
+	pub(super) fn xml_context_jump_def(&self, slice: &str, offset: usize) -> anyhow::Result<Option<Location>> {
+		let key = some!((self.index.xml_context_keys(slice).into_iter()).find(|key| key.range.contains_end(offset)));
+		let read = |path: &Path| self.rope_of_unlocked(path);
+		self.index.jump_def_context_key(&key.key, Some(key.model), &read)
+	}
+	pub(super) fn xml_context_references(&self, slice: &str, offset: usize) -> Option<Vec<Location>> {
+		let key = (self.index.xml_context_keys(slice).into_iter()).find(|key| key.range.contains_end(offset))?;
+		self.index.context_key_references(&key.key)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+
+	use crate::index::Index;
+	use crate::prelude::*;
+	use crate::test_utils::index::index_models_with_properties;
+
+	#[test]
+	fn test_xml_context_keys() {
+		let mut index = Index::default();
+		let py = r#"
+class ResPartner(models.Model):
+    _name = 'res.partner'
+    user_id = fields.Many2one('res.users')
+
+class ResUsers(models.Model):
+    _name = 'res.users'
+    login = fields.Char()
+"#;
+		index_models_with_properties(&mut index, Some(py), None, None);
+		let view = r#"<record id="view" model="ir.ui.view">
+	<field name="model">res.partner</field>
+	<field name="arch" type="xml">
+		<form>
+			<field name="user_id" context="{'default_login': 'admin', 'active_test': False}"/>
+			<filter name="mine" context="{&quot;group_by&quot;: 'user_id'}"/>
+		</form>
+	</field>
+</record>"#;
+		let action = r#"<record id="action" model="ir.actions.act_window">
+	<field name="res_model">res.users</field>
+	<field name="context">{'search_default_'}</field>
+</record>"#;
+		let keys = |xml: &str| {
+			(index.xml_context_keys(xml).into_iter())
+				.map(|key| (xml[key.range].to_string(), key.key, _R(key.model)))
+				.collect::<Vec<_>>()
+		};
+		let key = |raw: &str, key: &str, model: &'static str| (raw.to_string(), key.to_string(), model);
+		assert_eq!(
+			keys(view),
+			[
+				key("default_login", "default_login", "res.users"),
+				key("active_test", "active_test", "res.users"),
+				key("group_by", "group_by", "res.partner"),
+			]
+		);
+		assert_eq!(keys(action), [key("search_default_", "search_default_", "res.users")]);
+	}
+}
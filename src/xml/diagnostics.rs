@@ -0,0 +1,457 @@
+use std::borrow::Cow;
+
+use tower_lsp_server::ls_types::{Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location};
+use tracing::debug;
+use xmlparser::{ElementEnd, Token, Tokenizer};
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::index::{Index, ModuleName, RecordId, TemplateName};
+use crate::model::ModelName;
+use crate::rename::csv_xml_ids;
+
+use super::{RefKind, XmlRefs};
+
+/// Fields provided by `BaseModel` itself, valid on any model.
+#[rustfmt::skip]
+pub(crate) static MAGIC_FIELDS: phf::Set<&str> = phf::phf_set!(
+	"id", "display_name", "create_date", "write_date", "create_uid", "write_uid",
+);
+
+/// XML extensions.
+impl Backend {
+	pub fn diagnose_xml(
+		&self,
+		uri: &Uri,
+		rope: RopeSlice<'_>,
+		damage_zone: Option<ByteRange>,
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
+		let Some(record_ranges) = self.record_ranges.get(uri.path().as_str()) else {
+			debug!("Did not build record ranges for {}", uri.path().as_str());
+			return;
+		};
+		self.diagnose_xml_ranges(uri, rope, &record_ranges, damage_zone, diagnostics);
+	}
+	/// Like [`Backend::diagnose_xml`], but for record ranges that are not kept in `record_ranges`.
+	pub(crate) fn diagnose_xml_ranges(
+		&self,
+		uri: &Uri,
+		rope: RopeSlice<'_>,
+		record_ranges: &[ByteRange],
+		damage_zone: Option<ByteRange>,
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
+		let in_zone = |range: &ByteRange| {
+			damage_zone
+				.as_ref()
+				.is_none_or(|zone| range.start <= zone.end && zone.start <= range.end)
+		};
+		if damage_zone.is_some() {
+			let before_count = diagnostics.len();
+			diagnostics.retain(|diag| {
+				let start = rope_conv(diag.range.start, rope);
+				!record_ranges
+					.iter()
+					.any(|range| in_zone(range) && range.contains_end(start))
+			});
+			debug!(
+				"Retained {}/{} diagnostics after damage zone check",
+				diagnostics.len(),
+				before_count
+			);
+		} else {
+			debug!("Clearing all diagnostics - no damage zone");
+			diagnostics.clear();
+		}
+
+		let current_module = uri.to_file_path().and_then(|path| self.index.find_module_of(&path));
+		for range in record_ranges.iter().filter(|range| in_zone(range)) {
+			let Ok(slice) = rope.try_slice(range.erase()) else {
+				continue;
+			};
+			self.index
+				.diagnose_xml_record(slice, range.start.0, current_module, rope, diagnostics);
+			if let Some(current_module) = current_module {
+				self.diagnose_view_specs(uri, slice, current_module, rope, diagnostics);
+			}
+		}
+	}
+}
+
+impl Index {
+	/// Checks each reference within a record or template against the index.
+	///
+	/// `relative_offset` is the offset of `slice` relative to the start of `rope`.
+	pub(crate) fn diagnose_xml_record(
+		&self,
+		slice: RopeSlice<'_>,
+		relative_offset: usize,
+		current_module: Option<ModuleName>,
+		rope: RopeSlice<'_>,
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
+		let slice_str = Cow::from(slice);
+		for offset in reference_candidates(&slice_str) {
+			let mut reader = Tokenizer::from(slice_str.as_ref());
+			let Ok(XmlRefs {
+				ref_at_cursor: Some((needle, range)),
+				ref_kind: Some(ref_kind),
+				model_filter,
+				..
+			}) = self.gather_refs(ByteOffset(offset), &mut reader, slice)
+			else {
+				continue;
+			};
+
+			// `groups` subgroups and text contents may carry surrounding whitespace and negations.
+			let trimmed = needle.trim_start().trim_start_matches('!').trim_start();
+			let start = range.start + (needle.len() - trimmed.len());
+			let needle = trimmed.trim_end();
+			if needle.is_empty() {
+				continue;
+			}
+
+			let mut related_information = None;
+			let (message, severity) = match ref_kind {
+				RefKind::Ref(_) | RefKind::Id => {
+					if self.has_xml_id(needle, current_module) {
+						continue;
+					}
+					(
+						format!("No XML record with ID `{needle}` found"),
+						DiagnosticSeverity::WARNING,
+					)
+				}
+				RefKind::Model => {
+					if _G(needle).is_some_and(|model| self.models.contains_key(&ModelName::from(model))) {
+						continue;
+					}
+					(
+						format!("`{needle}` is not a valid model name"),
+						DiagnosticSeverity::ERROR,
+					)
+				}
+				RefKind::TCall => {
+					// dynamic calls like `t-call="{{ template }}"` cannot be checked statically
+					if needle.contains(['{', ' '])
+						|| _G(needle).is_some_and(|name| self.templates.contains_key(&TemplateName::from(name)))
+						|| self.has_xml_id(needle, current_module)
+					{
+						continue;
+					}
+					(
+						format!("No template named `{needle}` found"),
+						DiagnosticSeverity::WARNING,
+					)
+				}
+				// Only meaningful once the registries have been indexed from Javascript sources.
+				RefKind::Widget => {
+					if self.widgets.is_empty() || self.widgets.contains_key(needle.as_bytes()) {
+						continue;
+					}
+					(format!("No widget named `{needle}` found"), DiagnosticSeverity::WARNING)
+				}
+				RefKind::ActionTag => {
+					if self.actions.is_empty() || self.actions.contains_key(needle.as_bytes()) {
+						continue;
+					}
+					(
+						format!("No client action with tag `{needle}` found"),
+						DiagnosticSeverity::WARNING,
+					)
+				}
+				RefKind::PropertyName(access) => {
+					let Some(model) = model_filter.as_ref().and_then(|models| models.first()).and_then(_G) else {
+						continue;
+					};
+					// inheriting views without a `model` field of their own
+					if _R(model) == "ir.ui.view" {
+						continue;
+					}
+					// if a parent field can't be resolved, it has been reported on its own
+					let Some(model) = self.resolve_access(model.into(), &access) else {
+						continue;
+					};
+					let Some((message, related)) = self.diagnose_arch_field(model, needle, current_module) else {
+						continue;
+					};
+					related_information = related;
+					(message, DiagnosticSeverity::ERROR)
+				}
+				RefKind::MethodName(_)
+				| RefKind::TName
+				| RefKind::TInherit
+				| RefKind::PropOf(_)
+				| RefKind::PyExpr(_)
+				| RefKind::Component => continue,
+			};
+			let start = relative_offset + start;
+			let range = ByteOffset(start)..ByteOffset(start + needle.len());
+			diagnostics.push(Diagnostic {
+				range: rope_conv(range, rope),
+				message,
+				severity: Some(severity),
+				related_information,
+				..Default::default()
+			});
+		}
+		self.diagnose_xml_domains(&slice_str, relative_offset, rope, diagnostics);
+		self.diagnose_xml_selections(&slice_str, relative_offset, rope, diagnostics);
+	}
+	/// Checks that `field` is declared on `model` by `current_module` or one of its dependencies.
+	fn diagnose_arch_field(
+		&self,
+		model: ModelName,
+		field: &str,
+		current_module: Option<ModuleName>,
+	) -> Option<(String, Option<Vec<DiagnosticRelatedInformation>>)> {
+		if MAGIC_FIELDS.contains(field) {
+			return None;
+		}
+		// unknown models are reported on their own
+		let entry = self.models.populate_properties(model, &[])?;
+		let location = _G(field)
+			.and_then(|key| entry.fields.as_ref()?.get(&key))
+			.map(|field| field.location.clone());
+		drop(entry);
+		let Some(location) = location else {
+			return Some((format!("Model `{}` has no field `{field}`", _R(model)), None));
+		};
+
+		let current_module = current_module?;
+		let dependencies = self.resolve_transitive_dependencies(current_module);
+		let module = self.find_module_of(&location.path.to_path())?;
+		if dependencies.contains(&module) {
+			return None;
+		}
+		// the field may be declared again by another module, only one of which is tracked
+		let mut declarations = vec![];
+		for declaration in self.models.field_declarations(model, field) {
+			let Some(module) = self.find_module_of(&declaration.path.to_path()) else {
+				continue;
+			};
+			if dependencies.contains(&module) {
+				return None;
+			}
+			declarations.push((module, declaration));
+		}
+		if declarations.is_empty() {
+			declarations.push((module, MinLoc::clone(&location)));
+		}
+		let mut modules = declarations
+			.iter()
+			.map(|(module, _)| format!("`{}`", _R(*module)))
+			.collect::<Vec<_>>();
+		modules.sort();
+		modules.dedup();
+		let message = match modules.as_slice() {
+			[module] => format!(
+				"Field `{field}` of `{}` is only defined in {module}, which is not a dependency of `{}`",
+				_R(model),
+				_R(current_module)
+			),
+			_ => format!(
+				"Field `{field}` of `{}` is only defined in {}, none of which are dependencies of `{}`",
+				_R(model),
+				modules.join(", "),
+				_R(current_module)
+			),
+		};
+		let related = (declarations.into_iter())
+			.filter_map(|(module, declaration)| {
+				Some(DiagnosticRelatedInformation {
+					location: Location {
+						uri: Uri::from_file_path(declaration.path.to_path())?,
+						range: declaration.range,
+					},
+					message: format!("This field is defined in `{}`", _R(module)),
+				})
+			})
+			.collect::<Vec<_>>();
+		let related = (!related.is_empty()).then_some(related);
+		Some((message, related))
+	}
+	/// Whether `xml_id` resolves to a record, qualifying it with `current_module` if needed.
+	///
+	/// Unqualified IDs outside of any module are given the benefit of the doubt.
+	pub(crate) fn has_xml_id(&self, xml_id: &str, current_module: Option<ModuleName>) -> bool {
+		let qualified = match current_module {
+			_ if xml_id.contains('.') => Cow::from(xml_id),
+			Some(module) => Cow::from(format!("{}.{xml_id}", _R(module))),
+			None => return true,
+		};
+		_G(qualified).is_some_and(|id| self.records.contains_key(&RecordId::from(id)))
+	}
+}
+
+/// Offsets of attribute values and text contents within a record that may hold a reference,
+/// to be classified by [`Index::gather_refs`].
+pub(super) fn reference_candidates(slice: &str) -> Vec<usize> {
+	let mut out = vec![];
+	let mut tag = "";
+	let mut expect_text = false;
+	let mut depth = 0;
+	// (depth of `<field name="arch">`, whether its fields belong to the view's model)
+	let mut arch = None::<(usize, bool)>;
+	for token in Tokenizer::from(slice) {
+		match token {
+			Ok(Token::ElementStart { local, .. }) => {
+				depth += 1;
+				tag = local.as_str();
+				expect_text = false;
+				if let Some((arch_depth, ref mut is_view)) = arch
+					&& depth == arch_depth + 1
+				{
+					*is_view = matches!(
+						tag,
+						"form" | "list" | "tree" | "kanban" | "search" | "data" | "xpath" | "field"
+					);
+				}
+			}
+			Ok(Token::Attribute { local, value, .. }) => match (tag, local.as_str()) {
+				("field", "name") if arch.is_some_and(|(_, is_view)| is_view) => out.push(value.start()),
+				("field", "name") if arch.is_none() && value.as_str() == "arch" => arch = Some((depth, false)),
+				(_, "ref" | "inherit_id" | "t-call" | "widget")
+				| ("record", "model")
+				| ("menuitem", "parent" | "action") => out.push(value.start()),
+				("button", "name") => out.push(value.start()),
+				("field", "name") => expect_text = matches!(value.as_str(), "model" | "res_model" | "tag"),
+				(_, "groups") => out.extend(
+					csv_xml_ids(value.as_str())
+						.into_iter()
+						.map(|(_, range)| value.start() + range.start),
+				),
+				_ => {}
+			},
+			Ok(Token::Text { text }) if expect_text => {
+				expect_text = false;
+				if !text.trim().is_empty() {
+					out.push(text.start());
+				}
+			}
+			Ok(Token::ElementEnd {
+				end: ElementEnd::Close(..) | ElementEnd::Empty,
+				..
+			}) => {
+				if arch.is_some_and(|(arch_depth, _)| arch_depth == depth) {
+					arch = None;
+				}
+				depth -= 1;
+			}
+			Ok(_) => {}
+			Err(_) => break,
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+	use ropey::Rope;
+
+	use crate::index::Index;
+	use crate::test_utils::index::index_models_with_properties;
+
+	use super::reference_candidates;
+
+	#[test]
+	fn test_reference_candidates() {
+		let xml = r#"<record id="foo" model="ir.ui.view">
+	<field name="model">res.partner</field>
+	<field name="inherit_id" ref="base.view_partner_form"/>
+	<field name="arch" type="xml">
+		<form>
+			<field name="bar" widget="many2one_avatar" groups="base.group_user, !base.group_portal"/>
+		</form>
+	</field>
+	<field name="priority">20</field>
+</record>"#;
+		let candidates = reference_candidates(xml)
+			.into_iter()
+			.map(|offset| {
+				let rest = &xml[offset..];
+				&rest[..rest.find(['"', '<', ',']).unwrap()]
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(
+			candidates,
+			[
+				"ir.ui.view",
+				"res.partner",
+				"base.view_partner_form",
+				"bar",
+				"many2one_avatar",
+				"base.group_user",
+				"base.group_portal",
+			]
+		);
+	}
+
+	#[test]
+	fn test_diagnose_xml_record() {
+		let mut index = Index::default();
+		let py = r#"
+class ResPartner(models.Model):
+    _name = 'res.partner'
+    parent_id = fields.Many2one('res.partner')
+"#;
+		index_models_with_properties(&mut index, Some(py), None, None);
+		let xml = r#"<record id="foo" model="res.partnr"><field name="parent_id" ref="base.missing"/></record>"#;
+		let rope = Rope::from_str(xml);
+		let mut diagnostics = vec![];
+		index.diagnose_xml_record(rope.slice(..), 0, None, rope.slice(..), &mut diagnostics);
+		let messages = diagnostics.iter().map(|diag| diag.message.as_str()).collect::<Vec<_>>();
+		assert_eq!(
+			messages,
+			[
+				"`res.partnr` is not a valid model name",
+				"No XML record with ID `base.missing` found"
+			]
+		);
+		assert_eq!(
+			diagnostics[1].range.start.character as usize,
+			xml.find("base.missing").unwrap()
+		);
+	}
+
+	#[test]
+	fn test_diagnose_arch_fields() {
+		let mut index = Index::default();
+		let py = r#"
+class ResPartner(models.Model):
+    _name = 'res.partner'
+    name = fields.Char()
+    child_ids = fields.One2many('res.partner', 'parent_id')
+"#;
+		index_models_with_properties(&mut index, Some(py), None, None);
+		let xml = r#"<record id="foo" model="ir.ui.view">
+	<field name="model">res.partner</field>
+	<field name="arch" type="xml">
+		<form>
+			<field name="name"/>
+			<field name="nmae"/>
+			<field name="child_ids">
+				<list>
+					<field name="display_name"/>
+					<field name="emial"/>
+				</list>
+			</field>
+		</form>
+	</field>
+</record>"#;
+		let rope = Rope::from_str(xml);
+		let mut diagnostics = vec![];
+		index.diagnose_xml_record(rope.slice(..), 0, None, rope.slice(..), &mut diagnostics);
+		let messages = diagnostics.iter().map(|diag| diag.message.as_str()).collect::<Vec<_>>();
+		assert_eq!(
+			messages,
+			[
+				"Model `res.partner` has no field `nmae`",
+				"Model `res.partner` has no field `emial`"
+			]
+		);
+	}
+}
@@ -0,0 +1,471 @@
+use std::ops::Range;
+
+use tower_lsp_server::ls_types::*;
+use tree_sitter::Tree;
+use xmlparser::{ElementEnd, StrSpan, Token, Tokenizer};
+
+use crate::prelude::*;
+
+use crate::domain::{DomainCursor, domain_list};
+use crate::index::Index;
+use crate::model::{ModelName, PropertyKind};
+
+/// Where the model of a domain comes from.
+enum DomainOwner<'a> {
+	/// `<field domain=".."/>` or `<field context=".."/>` in a view, applied to the comodel of `field`.
+	Field {
+		access: Vec<(usize, &'a str)>,
+		field: &'a str,
+	},
+	/// `<filter domain=".."/>`, `<filter context=".."/>` or `<field filter_domain=".."/>` in a view,
+	/// applied to the model at that point.
+	View { access: Vec<(usize, &'a str)> },
+	/// `<field name="domain">` or `<field name="context">` of an action, applied to its `res_model`.
+	Action,
+	/// `invisible=".."` and other modifiers in a view, evaluated on the model at that point.
+	Modifier { access: Vec<(usize, &'a str)> },
+}
+
+/// What an [`XmlDomain`] holds.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(super) enum ExpressionKind {
+	Domain,
+	/// A Python expression, e.g. `invisible="state == 'done'"`.
+	Modifier,
+	/// A dictionary passed as context, e.g. `context="{'default_user_id': uid}"`.
+	Context,
+}
+
+/// A domain, modifier or context expression within a record, along with the model it applies to.
+pub(super) struct XmlDomain {
+	/// Range of the raw, XML-escaped domain relative to the record.
+	range: Range<usize>,
+	model: ModelName,
+	pub(super) kind: ExpressionKind,
+}
+
+/// An [`XmlDomain`] unescaped and parsed as Python.
+pub(super) struct ParsedDomain {
+	pub(super) source: String,
+	/// Offsets into the raw domain for each byte of `source`, and its end.
+	offsets: Vec<usize>,
+	pub(super) tree: Tree,
+	start: usize,
+	pub(super) model: ModelName,
+}
+
+/// The attributes of an element relevant to domains.
+#[derive(Default)]
+struct Element<'a> {
+	tag: &'a str,
+	name: Option<StrSpan<'a>>,
+	domain: Option<StrSpan<'a>>,
+	filter_domain: Option<StrSpan<'a>>,
+	context: Option<StrSpan<'a>>,
+	eval: Option<StrSpan<'a>>,
+	modifiers: Vec<StrSpan<'a>>,
+	/// `<field name=.. position=.. />` only locates a field in an inherited view.
+	position: bool,
+}
+
+/// XML extensions for [`crate::domain`].
+impl Index {
+	/// Domains held by view fields and filters, and action `domain` fields within a record.
+	fn xml_domains(&self, slice: &str) -> Vec<XmlDomain> {
+		(self.xml_expressions(slice).into_iter())
+			.filter(|domain| domain.kind == ExpressionKind::Domain)
+			.collect()
+	}
+	/// Like [`Index::xml_domains`], along with the modifiers of view elements, e.g. `invisible="state == 'done'"`,
+	/// and the contexts of view fields and actions.
+	pub(super) fn xml_expressions(&self, slice: &str) -> Vec<XmlDomain> {
+		let mut found = vec![];
+		let mut view_model = None;
+		let mut res_model = None;
+		let mut element = Element::default();
+		let mut expect_text = None;
+		let mut depth = 0;
+		let mut arch_depth = None;
+		// enclosing elements of the arch, with the fields that change the model of their children
+		let mut stack: Vec<Option<(usize, &str)>> = vec![];
+		for token in Tokenizer::from(slice) {
+			match token {
+				Ok(Token::ElementStart { local, .. }) => {
+					depth += 1;
+					expect_text = None;
+					element = Element {
+						tag: local.as_str(),
+						..Default::default()
+					};
+				}
+				Ok(Token::Attribute { local, value, .. }) => match local.as_str() {
+					"name" => element.name = Some(value),
+					"domain" => element.domain = Some(value),
+					"filter_domain" => element.filter_domain = Some(value),
+					"context" => element.context = Some(value),
+					"eval" => element.eval = Some(value),
+					"invisible" | "readonly" | "required" | "column_invisible" => element.modifiers.push(value),
+					"position" => element.position = true,
+					_ => {}
+				},
+				Ok(Token::ElementEnd {
+					end: end @ (ElementEnd::Open | ElementEnd::Empty),
+					..
+				}) => {
+					let open = matches!(end, ElementEnd::Open);
+					let name = element.name.filter(|_| element.tag == "field" && !element.position);
+					if arch_depth.is_some() {
+						let access = stack.iter().flatten().copied().collect::<Vec<_>>();
+						if let (Some(domain), Some(field)) = (element.domain.filter(|_| element.tag == "field"), name) {
+							let owner = DomainOwner::Field {
+								access: access.clone(),
+								field: field.as_str(),
+							};
+							found.push((domain.range(), owner, ExpressionKind::Domain));
+						}
+						if let (Some(context), Some(field)) = (element.context.filter(|_| element.tag == "field"), name)
+						{
+							let owner = DomainOwner::Field {
+								access: access.clone(),
+								field: field.as_str(),
+							};
+							found.push((context.range(), owner, ExpressionKind::Context));
+						}
+						let filter_domain = match element.tag {
+							"filter" => element.domain,
+							"field" => element.filter_domain,
+							_ => None,
+						};
+						if let Some(domain) = filter_domain {
+							let owner = DomainOwner::View { access: access.clone() };
+							found.push((domain.range(), owner, ExpressionKind::Domain));
+						}
+						if let Some(context) = element.context.filter(|_| element.tag == "filter") {
+							let owner = DomainOwner::View { access: access.clone() };
+							found.push((context.range(), owner, ExpressionKind::Context));
+						}
+						for modifier in &element.modifiers {
+							let owner = DomainOwner::Modifier { access: access.clone() };
+							found.push((modifier.range(), owner, ExpressionKind::Modifier));
+						}
+						if open {
+							stack.push(name.map(|name| (name.start(), name.as_str())));
+						}
+					} else {
+						match (name.map(|name| name.as_str()), element.eval) {
+							(Some("domain"), Some(eval)) => {
+								found.push((eval.range(), DomainOwner::Action, ExpressionKind::Domain))
+							}
+							(Some("context"), Some(eval)) => {
+								found.push((eval.range(), DomainOwner::Action, ExpressionKind::Context))
+							}
+							(Some("arch"), _) if open => arch_depth = Some(depth),
+							(Some(name @ ("model" | "res_model" | "domain" | "context")), None) if open => {
+								expect_text = Some(name)
+							}
+							_ => {}
+						}
+					}
+					if !open {
+						depth -= 1;
+					}
+				}
+				Ok(Token::ElementEnd {
+					end: ElementEnd::Close(..),
+					..
+				}) => {
+					if arch_depth == Some(depth) {
+						arch_depth = None;
+					} else if arch_depth.is_some() {
+						stack.pop();
+					}
+					depth -= 1;
+				}
+				Ok(Token::Text { text }) if expect_text.is_some() => {
+					let trimmed = text.as_str().trim_start();
+					let start = text.start() + text.as_str().len() - trimmed.len();
+					let range = start..start + trimmed.trim_end().len();
+					match expect_text.take() {
+						Some("model") => view_model = Some(&slice[range]),
+						Some("res_model") => res_model = Some(&slice[range]),
+						Some("context") => found.push((range, DomainOwner::Action, ExpressionKind::Context)),
+						_ => found.push((range, DomainOwner::Action, ExpressionKind::Domain)),
+					}
+				}
+				Ok(_) => {}
+				Err(_) => break,
+			}
+		}
+
+		let view_model = view_model.and_then(_G).map(ModelName::from);
+		let res_model = res_model.and_then(_G).map(ModelName::from);
+		(found.into_iter())
+			.filter_map(|(range, owner, kind)| {
+				let model = match owner {
+					DomainOwner::Field { access, field } => {
+						let model = self.resolve_access(view_model?, &access)?;
+						self.resolve_domain_path(model, field)?.relation?
+					}
+					DomainOwner::View { access } | DomainOwner::Modifier { access } => {
+						self.resolve_access(view_model?, &access)?
+					}
+					DomainOwner::Action => res_model?,
+				};
+				Some(XmlDomain { range, model, kind })
+			})
+			.collect()
+	}
+	/// Parses the domain at `offset` within a record, if any.
+	fn xml_domain_at(&self, slice: &str, offset: usize) -> Option<ParsedDomain> {
+		let domain = (self.xml_domains(slice).into_iter()).find(|domain| domain.range.contains_end(offset))?;
+		parse_xml_domain(slice, domain)
+	}
+	pub(super) fn diagnose_xml_domains(
+		&self,
+		slice: &str,
+		relative_offset: usize,
+		rope: RopeSlice<'_>,
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
+		for domain in self.xml_domains(slice) {
+			let Some(domain) = parse_xml_domain(slice, domain) else {
+				continue;
+			};
+			let Some(list) = domain.list() else { continue };
+			let mut found = vec![];
+			self.diagnose_domain(list, domain.model, &domain.source, true, &mut found);
+			diagnostics.extend(found.into_iter().map(|diagnostic| Diagnostic {
+				range: rope_conv(domain.record_range(diagnostic.range, relative_offset), rope),
+				severity: Some(diagnostic.severity),
+				message: diagnostic.message,
+				..Default::default()
+			}));
+		}
+	}
+	/// Completes field paths, operators and selection values inside domains.
+	pub(super) fn xml_domain_completions(
+		&self,
+		slice: &str,
+		offset: usize,
+		relative_offset: usize,
+		rope: RopeSlice<'_>,
+		completions_limit: usize,
+	) -> anyhow::Result<Option<CompletionResponse>> {
+		let domain = some!(self.xml_domain_at(slice, offset));
+		let offset = domain.domain_offset(offset);
+		let cursor = some!(self.domain_cursor(some!(domain.list()), domain.model, &domain.source, offset));
+		let range = domain.record_range(cursor.range(), relative_offset);
+		let mut items = MaxVec::new(completions_limit);
+		match &cursor {
+			DomainCursor::Path { model, range: path, .. } => {
+				let needle = &domain.source[path.start.0..offset];
+				self.complete_property_name(
+					needle,
+					range,
+					_R(*model).into(),
+					rope,
+					Some(PropertyKind::Field),
+					None,
+					true,
+					false,
+					&mut items,
+				)?;
+			}
+			DomainCursor::Operator { .. } | DomainCursor::Value { .. } => {
+				self.complete_domain(&cursor, rope_conv(range, rope), &mut items);
+			}
+		}
+		Ok(Some(CompletionResponse::List(CompletionList {
+			is_incomplete: !items.has_space(),
+			items: items.into_inner(),
+		})))
+	}
+	pub(super) fn xml_domain_hover(
+		&self,
+		slice: &str,
+		offset: usize,
+		relative_offset: usize,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<Hover>> {
+		let domain = some!(self.xml_domain_at(slice, offset));
+		let offset = domain.domain_offset(offset);
+		let cursor = some!(self.domain_cursor(some!(domain.list()), domain.model, &domain.source, offset));
+		let range = domain.record_range(cursor.range(), relative_offset);
+		self.hover_domain(&cursor, &domain.source, rope_conv(range, rope))
+	}
+	pub(super) fn xml_domain_jump_def(&self, slice: &str, offset: usize) -> anyhow::Result<Option<Location>> {
+		let domain = some!(self.xml_domain_at(slice, offset));
+		let offset = domain.domain_offset(offset);
+		let cursor = some!(self.domain_cursor(some!(domain.list()), domain.model, &domain.source, offset));
+		let DomainCursor::Path { model, range, .. } = cursor else {
+			return Ok(None);
+		};
+		self.jump_def_property_name(&domain.source[range.erase()], _R(model))
+	}
+}
+
+pub(super) fn parse_xml_domain(slice: &str, domain: XmlDomain) -> Option<ParsedDomain> {
+	let (source, offsets) = unescape(&slice[domain.range.clone()]);
+	let mut parser = Parser::new();
+	parser
+		.set_language(&tree_sitter_python::LANGUAGE.into())
+		.expect("bug: failed to init python parser");
+	let tree = parser.parse(&source, None)?;
+	Some(ParsedDomain {
+		source,
+		offsets,
+		tree,
+		start: domain.range.start,
+		model: domain.model,
+	})
+}
+
+impl ParsedDomain {
+	fn list(&self) -> Option<Node<'_>> {
+		let statement = self.tree.root_node().named_child(0)?;
+		domain_list(statement.named_child(0)?)
+	}
+	/// Converts an offset within the record into an offset within [`ParsedDomain::source`].
+	pub(super) fn domain_offset(&self, offset: usize) -> usize {
+		let raw = offset.saturating_sub(self.start);
+		self.offsets.partition_point(|&it| it < raw)
+	}
+	/// Converts a range within [`ParsedDomain::source`] into a range within the document.
+	pub(super) fn record_range(&self, range: ByteRange, relative_offset: usize) -> ByteRange {
+		let anchor = self.start + relative_offset;
+		range.map_unit(|ByteOffset(unit)| ByteOffset(self.offsets[unit] + anchor))
+	}
+}
+
+/// Expands the predefined XML entities of `raw`, along with the offset into `raw` of each byte of the result.
+fn unescape(raw: &str) -> (String, Vec<usize>) {
+	let mut source = String::with_capacity(raw.len());
+	let mut offsets = Vec::with_capacity(raw.len() + 1);
+	let mut rest = raw;
+	while let Some(char) = rest.chars().next() {
+		let offset = raw.len() - rest.len();
+		let (char, len) = match char {
+			'&' => entity(rest).unwrap_or(('&', 1)),
+			_ => (char, char.len_utf8()),
+		};
+		offsets.extend(std::iter::repeat_n(offset, char.len_utf8()));
+		source.push(char);
+		rest = &rest[len..];
+	}
+	offsets.push(raw.len());
+	(source, offsets)
+}
+
+fn entity(rest: &str) -> Option<(char, usize)> {
+	let end = rest.find(';')?;
+	let char = match &rest[1..end] {
+		"lt" => '<',
+		"gt" => '>',
+		"amp" => '&',
+		"quot" => '"',
+		"apos" => '\'',
+		_ => return None,
+	};
+	Some((char, end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+	use ropey::Rope;
+
+	use crate::index::Index;
+	use crate::prelude::*;
+	use crate::test_utils::index::index_models_with_properties;
+
+	use super::unescape;
+
+	#[test]
+	fn test_unescape() {
+		let (source, offsets) = unescape("[('a', '&lt;=', 1)]");
+		assert_eq!(source, "[('a', '<=', 1)]");
+		assert_eq!(offsets.len(), source.len() + 1);
+		assert_eq!(offsets[source.find('<').unwrap()], 7);
+		assert_eq!(offsets[source.find('=').unwrap()], 11);
+		assert_eq!(offsets[source.len()], 19);
+	}
+
+	#[test]
+	fn test_xml_domains() {
+		let mut index = Index::default();
+		let py = r#"
+class ResPartner(models.Model):
+    _name = 'res.partner'
+    name = fields.Char()
+    user_id = fields.Many2one('res.users')
+
+class ResUsers(models.Model):
+    _name = 'res.users'
+    login = fields.Char()
+"#;
+		index_models_with_properties(&mut index, Some(py), None, None);
+		let view = r#"<record id="foo" model="ir.ui.view">
+	<field name="model">res.partner</field>
+	<field name="arch" type="xml">
+		<search>
+			<field name="user_id" domain="[('login', '!=', False)]" filter_domain="[('user_id.login', 'ilike', self)]"/>
+			<filter name="mine" domain="[('user_id', '=', uid)]"/>
+		</search>
+	</field>
+</record>"#;
+		let action = r#"<record id="bar" model="ir.actions.act_window">
+	<field name="domain">[('name', '!=', False)]</field>
+	<field name="res_model">res.partner</field>
+</record>"#;
+		let domains = |xml: &str| {
+			(index.xml_domains(xml).into_iter())
+				.map(|domain| (xml[domain.range].to_string(), _R(domain.model).to_string()))
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(
+			domains(view),
+			[
+				("[('login', '!=', False)]".to_string(), "res.users".to_string()),
+				(
+					"[('user_id.login', 'ilike', self)]".to_string(),
+					"res.partner".to_string()
+				),
+				("[('user_id', '=', uid)]".to_string(), "res.partner".to_string()),
+			]
+		);
+		assert_eq!(
+			domains(action),
+			[("[('name', '!=', False)]".to_string(), "res.partner".to_string())]
+		);
+	}
+
+	#[test]
+	fn test_diagnose_xml_domains() {
+		let mut index = Index::default();
+		let py = r#"
+class ResPartner(models.Model):
+    _name = 'res.partner'
+    active = fields.Boolean()
+"#;
+		index_models_with_properties(&mut index, Some(py), None, None);
+		let xml = r#"<record id="foo" model="ir.actions.act_window">
+	<field name="res_model">res.partner</field>
+	<field name="domain" eval="['|', ('actve', '=', True), ('active', '&gt;', 1)]"/>
+</record>"#;
+		let rope = Rope::from_str(xml);
+		let mut diagnostics = vec![];
+		index.diagnose_xml_domains(xml, 0, rope.slice(..), &mut diagnostics);
+		let messages = diagnostics.iter().map(|diag| diag.message.as_str()).collect::<Vec<_>>();
+		assert_eq!(
+			messages,
+			[
+				"Model `res.partner` has no field `actve`",
+				"Operator `>` does not apply to Boolean field `active`"
+			]
+		);
+		assert_eq!(
+			diagnostics[1].range.start.character as usize,
+			xml.lines().nth(2).unwrap().find("&gt;").unwrap()
+		);
+	}
+}
@@ -0,0 +1,212 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use tower_lsp_server::ls_types::Position;
+use xmlparser::{ElementEnd, Token, Tokenizer};
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::index::{Index, ModuleName};
+use crate::model::ModelName;
+use crate::rename::{PropertyTarget, RecordTarget, csv_xml_ids, embedded_xml_ids};
+
+use super::{RefKind, XmlRefs};
+
+/// XML extensions for [`crate::rename`].
+impl Backend {
+	pub(crate) fn xml_property_target(
+		&self,
+		uri: &Uri,
+		position: Position,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<PropertyTarget>> {
+		let (slice, offset_at_cursor, relative_offset) = self.record_slice(rope, uri, position)?;
+		let slice_str = Cow::from(slice);
+		let mut reader = Tokenizer::from(slice_str.as_ref());
+		let XmlRefs {
+			ref_at_cursor,
+			ref_kind,
+			model_filter,
+			..
+		} = self.index.gather_refs(offset_at_cursor, &mut reader, slice)?;
+
+		let (needle, range) = some!(ref_at_cursor);
+		let (Some(RefKind::PropertyName(access)) | Some(RefKind::MethodName(access))) = ref_kind else {
+			return Ok(None);
+		};
+		let model = some!(model_filter.as_ref().and_then(|models| models.first()));
+		let model = some!(_G(model));
+		let model = some!(self.index.resolve_access(model.into(), &access));
+		Ok(Some(PropertyTarget {
+			model,
+			prop: ImStr::from(needle),
+			range: range.map_unit(|offset| ByteOffset(offset + relative_offset)),
+		}))
+	}
+	/// Ranges of `name` attributes within a record that refer to `prop` on any of `models`.
+	///
+	/// `record_model` is used for views that don't declare their own model, i.e. inheriting views.
+	pub(crate) fn xml_property_edits(
+		&self,
+		rope: RopeSlice<'_>,
+		record_range: ByteRange,
+		record_model: ModelName,
+		models: &HashSet<ModelName>,
+		prop: &str,
+	) -> Vec<ByteRange> {
+		let Ok(slice) = rope.try_slice(record_range.erase()) else {
+			return vec![];
+		};
+		let slice_str = Cow::from(slice);
+		if !slice_str.contains(prop) {
+			return vec![];
+		}
+
+		// (offset of the attribute value, whether it is inside an arch)
+		let mut candidates = vec![];
+		let mut depth = 0;
+		let mut arch_depth = None;
+		let mut in_field = false;
+		for token in Tokenizer::from(slice_str.as_ref()) {
+			match token {
+				Ok(Token::ElementStart { local, .. }) => {
+					depth += 1;
+					in_field = local.as_str() == "field";
+				}
+				Ok(Token::Attribute { local, value, .. }) if local.as_str() == "name" => {
+					if in_field && arch_depth.is_none() && value.as_str() == "arch" {
+						arch_depth = Some(depth);
+					} else if value.as_str() == prop {
+						candidates.push((value.start(), arch_depth.is_some()));
+					}
+				}
+				Ok(Token::ElementEnd { end, .. }) => {
+					if let ElementEnd::Close(..) | ElementEnd::Empty = end {
+						if arch_depth == Some(depth) {
+							arch_depth = None;
+						}
+						depth -= 1;
+					}
+				}
+				Ok(_) => {}
+				Err(_) => break,
+			}
+		}
+
+		let mut out = vec![];
+		for (offset, in_arch) in candidates {
+			let mut reader = Tokenizer::from(slice_str.as_ref());
+			let Ok(XmlRefs {
+				ref_at_cursor: Some((needle, range)),
+				ref_kind: Some(RefKind::PropertyName(access) | RefKind::MethodName(access)),
+				model_filter,
+				..
+			}) = self.index.gather_refs(ByteOffset(offset), &mut reader, slice)
+			else {
+				continue;
+			};
+			if needle != prop {
+				continue;
+			}
+			let model = model_filter.as_ref().and_then(|models| models.first());
+			let model = match model.and_then(_G) {
+				// an arch without its own `model` field is evaluated against the inherited view's model
+				Some(model) if in_arch && _R(model) == "ir.ui.view" => record_model,
+				Some(model) => model.into(),
+				None => record_model,
+			};
+			if let Some(model) = self.index.resolve_access(model, &access)
+				&& models.contains(&model)
+			{
+				let start = record_range.start.0;
+				out.push(range.map_unit(|offset| ByteOffset(offset + start)));
+			}
+		}
+		out
+	}
+	pub(crate) fn xml_record_target(
+		&self,
+		uri: &Uri,
+		position: Position,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<RecordTarget>> {
+		let (slice, offset_at_cursor, relative_offset) = self.record_slice(rope, uri, position)?;
+		let slice_str = Cow::from(slice);
+		let mut reader = Tokenizer::from(slice_str.as_ref());
+		let XmlRefs {
+			ref_at_cursor,
+			ref_kind,
+			..
+		} = self.index.gather_refs(offset_at_cursor, &mut reader, slice)?;
+
+		let (needle, range) = some!(ref_at_cursor);
+		let (Some(RefKind::Ref(_)) | Some(RefKind::Id)) = ref_kind else {
+			return Ok(None);
+		};
+		let path = some!(uri.to_file_path());
+		let current_module = self.index.find_module_of(&path);
+		let range = range.map_unit(|offset| ByteOffset(offset + relative_offset));
+		Ok(self.index.record_target(needle, range, current_module))
+	}
+	/// Ranges of references to `target` in an XML file belonging to `module`.
+	pub(crate) fn xml_record_edits(
+		&self,
+		rope: RopeSlice<'_>,
+		module: Option<ModuleName>,
+		target: &RecordTarget,
+	) -> Vec<ByteRange> {
+		let contents = Cow::from(rope);
+		let mut out = vec![];
+		if !contents.contains(target.id.as_str()) {
+			return out;
+		}
+		let mut push = |written: &str, start: usize| {
+			if let Some(range) = target.reference_in(written, module) {
+				out.push(ByteOffset(start + range.start)..ByteOffset(start + range.end));
+			}
+		};
+
+		let mut tag = "";
+		for token in Tokenizer::from(contents.as_ref()) {
+			match token {
+				Ok(Token::ElementStart { local, .. }) => tag = local.as_str(),
+				Ok(Token::Attribute { local, value, .. }) => match (tag, local.as_str()) {
+					("record" | "template" | "menuitem", "id")
+					| (_, "ref" | "inherit_id" | "t-call")
+					| ("menuitem", "parent" | "action") => push(value.as_str(), value.start()),
+					(_, "groups") => {
+						for (group, range) in csv_xml_ids(value.as_str()) {
+							push(group, value.start() + range.start);
+						}
+					}
+					_ => {
+						for range in embedded_xml_ids(value.as_str()) {
+							push(&value.as_str()[range.clone()], value.start() + range.start);
+						}
+					}
+				},
+				Ok(Token::Text { text }) => {
+					for range in embedded_xml_ids(text.as_str()) {
+						push(&text.as_str()[range.clone()], text.start() + range.start);
+					}
+				}
+				Ok(_) => {}
+				Err(_) => break,
+			}
+		}
+		out
+	}
+}
+
+impl Index {
+	/// Resolves the model that a property nested in `access` is evaluated against,
+	/// e.g. for fields within a One2many's sub-view.
+	pub(super) fn resolve_access(&self, mut model: ModelName, access: &[(usize, &str)]) -> Option<ModelName> {
+		for &(_, field) in access {
+			let field = _G(field)?;
+			model = self.models.resolve_related_field(field.into(), model.into())?.into();
+		}
+		Some(model)
+	}
+}
@@ -0,0 +1,251 @@
+use std::ops::Range;
+
+use tower_lsp_server::ls_types::*;
+use xmlparser::{ElementEnd, Token, Tokenizer};
+
+use crate::prelude::*;
+
+use crate::index::Index;
+use crate::model::ModelName;
+use crate::selection::diagnose_selection_value;
+
+use super::domain::{ExpressionKind, parse_xml_domain};
+
+/// A string within a record which should be a key of a selection field.
+struct XmlSelectionValue {
+	/// Range of the key relative to the record, excluding quotes.
+	range: Range<usize>,
+	value: String,
+	model: ModelName,
+	field: String,
+}
+
+/// XML extensions for [`crate::selection`].
+impl Index {
+	/// Values of selection fields in record data, e.g. `<field name="state">draft</field>`,
+	/// and in view modifiers, e.g. `invisible="state == 'done'"`.
+	fn xml_selection_values(&self, slice: &str) -> Vec<XmlSelectionValue> {
+		let mut out = vec![];
+		let mut depth = 0;
+		let mut record_depth = None;
+		let mut record_model = None;
+		let mut tag = "";
+		let mut name = None;
+		let mut literal = true;
+		// the field whose text is expected next, and where that text starts
+		let mut pending = None::<(&str, usize)>;
+		let mut data = vec![];
+		for token in Tokenizer::from(slice) {
+			match token {
+				Ok(Token::ElementStart { local, .. }) => {
+					depth += 1;
+					tag = local.as_str();
+					name = None;
+					literal = true;
+					pending = None;
+				}
+				Ok(Token::Attribute { local, value, .. }) => match (tag, local.as_str()) {
+					("record", "model") => record_model = Some(value.as_str()),
+					("field", "name") => name = Some(value.as_str()),
+					("field", "eval" | "ref" | "search" | "type" | "file") => literal = false,
+					_ => {}
+				},
+				Ok(Token::ElementEnd { end, span }) => match end {
+					ElementEnd::Open => {
+						if tag == "record" {
+							record_depth = Some(depth);
+						} else if tag == "field"
+							&& literal && record_depth.is_some_and(|record| depth == record + 1)
+							&& let Some(name) = name
+						{
+							pending = Some((name, span.end()));
+						}
+					}
+					ElementEnd::Empty => depth -= 1,
+					ElementEnd::Close(..) => {
+						// `<field name="state"></field>`
+						if let Some((name, start)) = pending.take() {
+							data.push((start..start, name));
+						}
+						if record_depth == Some(depth) {
+							record_depth = None;
+						}
+						depth -= 1;
+					}
+				},
+				Ok(Token::Text { text }) => {
+					if let Some((name, _)) = pending.take() {
+						let trimmed = text.as_str().trim_start();
+						let start = text.start() + text.as_str().len() - trimmed.len();
+						data.push((start..start + trimmed.trim_end().len(), name));
+					}
+				}
+				Ok(_) => {}
+				Err(_) => break,
+			}
+		}
+		let record_model = record_model.and_then(_G).map(ModelName::from);
+		if let Some(model) = record_model.filter(|model| self.models.contains_key(model)) {
+			out.extend(data.into_iter().map(|(range, field)| XmlSelectionValue {
+				value: slice[range.clone()].to_string(),
+				range,
+				model,
+				field: field.to_string(),
+			}));
+		}
+
+		let modifiers =
+			(self.xml_expressions(slice).into_iter()).filter(|expression| expression.kind == ExpressionKind::Modifier);
+		for modifier in modifiers {
+			let Some(parsed) = parse_xml_domain(slice, modifier) else {
+				continue;
+			};
+			let source = parsed.source.as_str();
+			for node in PreTravel::new(parsed.tree.root_node()) {
+				if node.kind() != "comparison_operator" || node.named_child_count() != 2 {
+					continue;
+				}
+				let (Some(field), Some(compared)) = (node.named_child(0), node.named_child(1)) else {
+					continue;
+				};
+				let operator = source[field.end_byte()..compared.start_byte()].trim();
+				if field.kind() != "identifier" || !matches!(operator, "==" | "!=" | "in" | "not in") {
+					continue;
+				}
+				let strings = match compared.kind() {
+					"string" => vec![compared],
+					"tuple" | "list" | "set" => (compared.named_children(&mut compared.walk()))
+						.filter(|item| item.kind() == "string")
+						.collect(),
+					_ => continue,
+				};
+				for string in strings {
+					let range = string.byte_range().shrink(1);
+					out.push(XmlSelectionValue {
+						value: source[range.clone()].to_string(),
+						range: parsed.record_range(range.map_unit(ByteOffset), 0).erase(),
+						model: parsed.model,
+						field: source[field.byte_range()].to_string(),
+					});
+				}
+			}
+		}
+		out
+	}
+	pub(super) fn diagnose_xml_selections(
+		&self,
+		slice: &str,
+		relative_offset: usize,
+		rope: RopeSlice<'_>,
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
+		for value in self.xml_selection_values(slice) {
+			let Some(selection) = self.selection_of(value.model, &value.field) else {
+				continue;
+			};
+			let Some(message) = diagnose_selection_value(&value.field, &selection, &value.value) else {
+				continue;
+			};
+			let range = value.range.map_unit(|unit| ByteOffset(unit + relative_offset));
+			diagnostics.push(Diagnostic {
+				range: rope_conv(range, rope),
+				severity: Some(DiagnosticSeverity::ERROR),
+				message,
+				..Default::default()
+			});
+		}
+	}
+	pub(super) fn xml_selection_completions(
+		&self,
+		slice: &str,
+		offset: usize,
+		relative_offset: usize,
+		rope: RopeSlice<'_>,
+		completions_limit: usize,
+	) -> anyhow::Result<Option<CompletionResponse>> {
+		let value =
+			some!((self.xml_selection_values(slice).into_iter()).find(|value| value.range.contains_end(offset)));
+		let selection = some!(self.selection_of(value.model, &value.field));
+		let range = value.range.map_unit(|unit| ByteOffset(unit + relative_offset));
+		let mut items = MaxVec::new(completions_limit);
+		self.complete_selection(&selection, rope_conv(range, rope), &mut items);
+		Ok(Some(CompletionResponse::List(CompletionList {
+			is_incomplete: !items.has_space(),
+			items: items.into_inner(),
+		})))
+	}
+	pub(super) fn xml_selection_hover(
+		&self,
+		slice: &str,
+		offset: usize,
+		relative_offset: usize,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<Hover>> {
+		let value =
+			some!((self.xml_selection_values(slice).into_iter()).find(|value| value.range.contains_end(offset)));
+		let selection = some!(self.selection_of(value.model, &value.field));
+		let range = value.range.map_unit(|unit| ByteOffset(unit + relative_offset));
+		Ok(self.hover_selection_value(&selection, &value.value, rope_conv(range, rope)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+
+	use crate::index::Index;
+	use crate::prelude::*;
+	use crate::test_utils::index::index_models_with_properties;
+
+	#[test]
+	fn test_xml_selection_values() {
+		let mut index = Index::default();
+		let py = r#"
+class Order(models.Model):
+    _name = 'sale.order'
+    state = fields.Selection([('draft', "Quotation"), ('sale', "Sales Order")])
+    note = fields.Text()
+"#;
+		index_models_with_properties(&mut index, Some(py), None, None);
+		let view = r#"<record id="view" model="ir.ui.view">
+	<field name="model">sale.order</field>
+	<field name="arch" type="xml">
+		<form>
+			<button name="action_confirm" invisible="state not in ('draft', 'sent')"/>
+		</form>
+	</field>
+</record>"#;
+		let data = r#"<record id="order" model="sale.order">
+	<field name="state">sale</field>
+	<field name="note"></field>
+</record>"#;
+		let values = |xml: &str| {
+			(index.xml_selection_values(xml).into_iter())
+				.map(|value| (xml[value.range].to_string(), value.value, _R(value.model), value.field))
+				.collect::<Vec<_>>()
+		};
+		let value = |raw: &str, value: &str, model: &'static str, field: &str| {
+			(raw.to_string(), value.to_string(), model, field.to_string())
+		};
+		assert_eq!(
+			values(view),
+			[
+				value("draft", "draft", "sale.order", "state"),
+				value("sent", "sent", "sale.order", "state"),
+			]
+		);
+		assert_eq!(
+			values(data),
+			[
+				value("sale", "sale", "sale.order", "state"),
+				value("", "", "sale.order", "note"),
+			]
+		);
+
+		let rope = Rope::from_str(view);
+		let mut diagnostics = vec![];
+		index.diagnose_xml_selections(view, 0, rope.slice(..), &mut diagnostics);
+		let messages = diagnostics.iter().map(|diag| diag.message.as_str()).collect::<Vec<_>>();
+		assert_eq!(messages, ["Selection field `state` has no key `sent`"]);
+	}
+}
@@ -0,0 +1,152 @@
+use std::borrow::Cow;
+
+use xmlparser::Tokenizer;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::index::{Index, ModuleName};
+use crate::model::ModelName;
+use crate::semantic_tokens::{Token, TokenKind};
+
+use super::diagnostics::reference_candidates;
+use super::{RefKind, XmlRefs};
+
+/// XML extensions for [`crate::semantic_tokens`].
+impl Backend {
+	pub(crate) fn xml_semantic_tokens(&self, uri: &Uri, rope: RopeSlice<'_>) -> anyhow::Result<Vec<Token>> {
+		let record_ranges = ok!(
+			self.record_ranges.get(uri.path().as_str()),
+			"Did not build record ranges for {}",
+			uri.path().as_str()
+		);
+		let current_module = uri.to_file_path().and_then(|path| self.index.find_module_of(&path));
+		let mut tokens = vec![];
+		for range in record_ranges.iter() {
+			let Ok(slice) = rope.try_slice(range.erase()) else {
+				continue;
+			};
+			self.index
+				.xml_record_tokens(slice, range.start.0, current_module, &mut tokens);
+		}
+		Ok(tokens)
+	}
+}
+
+impl Index {
+	/// Classifies the same references as [`Index::diagnose_xml_record`].
+	///
+	/// `relative_offset` is the offset of `slice` relative to the start of the document.
+	pub(crate) fn xml_record_tokens(
+		&self,
+		slice: RopeSlice<'_>,
+		relative_offset: usize,
+		current_module: Option<ModuleName>,
+		tokens: &mut Vec<Token>,
+	) {
+		let slice_str = Cow::from(slice);
+		for offset in reference_candidates(&slice_str) {
+			let mut reader = Tokenizer::from(slice_str.as_ref());
+			let Ok(XmlRefs {
+				ref_at_cursor: Some((needle, range)),
+				ref_kind: Some(ref_kind),
+				model_filter,
+				..
+			}) = self.gather_refs(ByteOffset(offset), &mut reader, slice)
+			else {
+				continue;
+			};
+
+			let trimmed = needle.trim_start().trim_start_matches('!').trim_start();
+			let start = range.start + (needle.len() - trimmed.len());
+			let needle = trimmed.trim_end();
+			if needle.is_empty() {
+				continue;
+			}
+
+			let (kind, resolved) = match ref_kind {
+				RefKind::Ref(_) | RefKind::Id => (TokenKind::Record, self.has_xml_id(needle, current_module)),
+				RefKind::Model => (
+					TokenKind::Model,
+					_G(needle).is_some_and(|model| self.models.contains_key(&ModelName::from(model))),
+				),
+				RefKind::PropertyName(ref access) | RefKind::MethodName(ref access) => {
+					let is_field = matches!(ref_kind, RefKind::PropertyName(_));
+					let model = model_filter.as_ref().and_then(|models| models.first()).and_then(_G);
+					let resolved = match model {
+						// inheriting views without a `model` field of their own can't be checked
+						None => true,
+						Some(model) if _R(model) == "ir.ui.view" => true,
+						Some(model) => self
+							.resolve_access(model.into(), access)
+							.is_some_and(|model| self.has_property(model, needle, is_field)),
+					};
+					let kind = if is_field { TokenKind::Field } else { TokenKind::Method };
+					(kind, resolved)
+				}
+				RefKind::TCall
+				| RefKind::Widget
+				| RefKind::ActionTag
+				| RefKind::TName
+				| RefKind::TInherit
+				| RefKind::PropOf(_)
+				| RefKind::PyExpr(_)
+				| RefKind::Component => continue,
+			};
+			let start = relative_offset + start;
+			tokens.push(Token {
+				range: ByteOffset(start)..ByteOffset(start + needle.len()),
+				kind,
+				resolved,
+			});
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+	use ropey::Rope;
+
+	use crate::index::Index;
+	use crate::semantic_tokens::TokenKind;
+	use crate::test_utils::index::index_models_with_properties;
+
+	#[test]
+	fn test_xml_record_tokens() {
+		let mut index = Index::default();
+		let py = r#"
+class ResPartner(models.Model):
+    _name = 'res.partner'
+    name = fields.Char()
+"#;
+		index_models_with_properties(&mut index, Some(py), None, None);
+		let xml = r#"<record id="foo" model="ir.ui.view">
+	<field name="model">res.partner</field>
+	<field name="inherit_id" ref="base.missing"/>
+	<field name="arch" type="xml">
+		<form>
+			<field name="name"/>
+			<field name="nmae"/>
+		</form>
+	</field>
+</record>"#;
+		let rope = Rope::from_str(xml);
+		let mut tokens = vec![];
+		index.xml_record_tokens(rope.slice(..), 0, None, &mut tokens);
+		let tokens = tokens
+			.iter()
+			.map(|token| (&xml[token.range.start.0..token.range.end.0], token.kind, token.resolved))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			tokens,
+			[
+				("ir.ui.view", TokenKind::Model, false),
+				("res.partner", TokenKind::Model, true),
+				("base.missing", TokenKind::Record, false),
+				("name", TokenKind::Field, true),
+				("nmae", TokenKind::Field, false),
+			]
+		);
+	}
+}
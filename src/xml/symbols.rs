@@ -0,0 +1,106 @@
+use std::borrow::Cow;
+
+use tower_lsp_server::ls_types::{DocumentSymbol, SymbolKind};
+use tracing::debug;
+use xmlparser::{Token, Tokenizer};
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::index::PathSymbol;
+use crate::record::Record;
+use crate::symbols::document_symbol;
+use crate::template::{NewTemplate, gather_templates};
+
+/// XML extensions for [`crate::symbols`].
+impl Backend {
+	/// Lists records, templates and menuitems in the order they are declared.
+	pub(crate) fn xml_document_symbols(
+		&self,
+		uri: &Uri,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<Vec<DocumentSymbol>>> {
+		let path = some!(uri.to_file_path());
+		let current_module = some!(self.index.find_module_of(&path));
+		let root = some!(self.index.find_root_of(&path));
+		let path_symbol = PathSymbol::strip_root(_P(root), &path);
+		let contents = Cow::from(rope);
+		let mut reader = Tokenizer::from(contents.as_ref());
+
+		let mut symbols = vec![];
+		loop {
+			match reader.next() {
+				Some(Ok(Token::ElementStart { local, span, .. })) => {
+					let offset = ByteOffset(span.start());
+					let record = match local.as_str() {
+						"record" => Record::from_reader(offset, current_module, path_symbol, &mut reader, rope),
+						"template" => Record::template(offset, current_module, path_symbol, &mut reader, rope),
+						"menuitem" => Record::menuitem(offset, current_module, path_symbol, &mut reader, rope),
+						"templates" => {
+							let mut entries = vec![];
+							match gather_templates(path_symbol, &mut reader, rope, &mut entries, false) {
+								Ok(()) => symbols.extend(entries.iter().filter_map(template_symbol)),
+								Err(err) => debug!("(xml_document_symbols) gather_templates failed: {err}"),
+							}
+							continue;
+						}
+						_ => continue,
+					};
+					match record {
+						Ok(Some((record, _))) => symbols.push(record_symbol(local.as_str(), &record)),
+						Ok(None) if local.as_str() == "template" => {
+							// legacy <templates /> container without id=
+							let mut entries = vec![];
+							match gather_templates(path_symbol, &mut reader, rope, &mut entries, true) {
+								Ok(()) => symbols.extend(entries.iter().filter_map(template_symbol)),
+								Err(err) => debug!("(xml_document_symbols) gather_templates failed: {err}"),
+							}
+						}
+						Ok(None) => {}
+						Err(err) => debug!("(xml_document_symbols) {local} could not be completely parsed: {err}"),
+					}
+				}
+				None => break,
+				Some(Err(err)) => {
+					debug!("error parsing xml:\n{err}");
+					break;
+				}
+				_ => {}
+			}
+		}
+		Ok(Some(symbols))
+	}
+}
+
+fn record_symbol(tag: &str, record: &Record) -> DocumentSymbol {
+	let model = match (tag, record.model) {
+		("menuitem", _) => Some("ir.ui.menu"),
+		("template", _) => Some("ir.ui.view"),
+		(_, model) => model.map(_R),
+	};
+	let detail = match (model, record.inherit_id) {
+		(Some(model), Some(inherit_id)) => Some(format!("{model}, inherits {}", _R(inherit_id))),
+		(Some(model), None) => Some(model.to_string()),
+		(None, Some(inherit_id)) => Some(format!("inherits {}", _R(inherit_id))),
+		(None, None) => None,
+	};
+	let kind = match tag {
+		"menuitem" => SymbolKind::ENUM_MEMBER,
+		_ => SymbolKind::VARIABLE,
+	};
+	let range = record.location.range;
+	document_symbol(record.id.as_str(), detail, kind, range, range, vec![])
+}
+
+fn template_symbol(entry: &NewTemplate) -> Option<DocumentSymbol> {
+	let range = entry.template.location.as_ref()?.range;
+	let detail = if entry.base { "t-name" } else { "t-inherit" };
+	Some(document_symbol(
+		_R(entry.name),
+		Some(detail.to_string()),
+		SymbolKind::CLASS,
+		range,
+		range,
+		vec![],
+	))
+}
@@ -0,0 +1,41 @@
+use std::borrow::Cow;
+
+use tower_lsp_server::ls_types::Position;
+use xmlparser::Tokenizer;
+
+use crate::prelude::*;
+
+use crate::backend::Backend;
+use crate::type_hierarchy::ModelTarget;
+
+use super::{RefKind, XmlRefs};
+
+/// XML extensions for [`crate::type_hierarchy`].
+impl Backend {
+	/// The model named under the cursor, e.g. in `<record model="...">`.
+	pub(crate) fn xml_model_target(
+		&self,
+		uri: &Uri,
+		position: Position,
+		rope: RopeSlice<'_>,
+	) -> anyhow::Result<Option<ModelTarget>> {
+		let (slice, offset_at_cursor, _) = self.record_slice(rope, uri, position)?;
+		let slice_str = Cow::from(slice);
+		let mut reader = Tokenizer::from(slice_str.as_ref());
+		let XmlRefs {
+			ref_at_cursor,
+			ref_kind,
+			..
+		} = self.index.gather_refs(offset_at_cursor, &mut reader, slice)?;
+
+		let (needle, _) = some!(ref_at_cursor);
+		let Some(RefKind::Model) = ref_kind else {
+			return Ok(None);
+		};
+		let model = some!(_G(needle));
+		Ok(Some(ModelTarget {
+			model: model.into(),
+			class: None,
+		}))
+	}
+}
@@ -0,0 +1,544 @@
+//! A subset of XPath 1.0, enough for the expressions found in view inheritance specs.
+//!
+//! Nodes are addressed by their path of child indices from the document node, which is also
+//! their document order when compared lexicographically. Only elements are selectable; text is
+//! available through `text()`, `string()` and `normalize-space()` within predicates. Expressions
+//! using anything else fail to parse, so that callers can tell them apart from expressions
+//! that select nothing.
+
+use anyhow::bail;
+
+use super::arch::{ArchElement, ArchNode};
+
+/// Child indices leading from the document node to an element.
+pub(crate) type NodeAddr = Vec<usize>;
+
+#[derive(Debug)]
+pub(crate) struct XPath {
+	absolute: bool,
+	steps: Vec<Step>,
+}
+
+#[derive(Debug)]
+struct Step {
+	axis: Axis,
+	test: NodeTest,
+	predicates: Vec<Expr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+	Child,
+	Descendant,
+	DescendantOrSelf,
+	Self_,
+	Parent,
+	Ancestor,
+	FollowingSibling,
+	PrecedingSibling,
+}
+
+#[derive(Debug)]
+enum NodeTest {
+	Any,
+	Name(String),
+}
+
+#[derive(Debug)]
+enum Expr {
+	Or(Box<Expr>, Box<Expr>),
+	And(Box<Expr>, Box<Expr>),
+	/// `lhs = rhs` if true, otherwise `lhs != rhs`
+	Compare(Box<Expr>, bool, Box<Expr>),
+	Attribute(String),
+	Literal(String),
+	Number(f64),
+	Path(XPath),
+	Call(String, Vec<Expr>),
+}
+
+enum Value {
+	Nodes(Vec<NodeAddr>),
+	/// Values of an attribute, empty if it is missing.
+	Strings(Vec<String>),
+	String(String),
+	Number(f64),
+	Bool(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Tok<'a> {
+	Slash,
+	DoubleSlash,
+	LBracket,
+	RBracket,
+	LParen,
+	RParen,
+	At,
+	Comma,
+	Eq,
+	NotEq,
+	Dot,
+	DotDot,
+	Star,
+	Axis(&'a str),
+	Name(&'a str),
+	Literal(&'a str),
+	Number(f64),
+}
+
+#[rustfmt::skip]
+static FUNCTIONS: phf::Set<&str> = phf::phf_set!(
+	"not", "contains", "starts-with", "hasclass", "text", "string", "normalize-space", "position", "last", "count",
+);
+
+impl XPath {
+	pub fn parse(expr: &str) -> anyhow::Result<Self> {
+		let mut parser = Parser {
+			tokens: tokenize(expr)?,
+			pos: 0,
+		};
+		let path = parser.path()?;
+		if let Some(tok) = parser.peek() {
+			bail!("unexpected {tok:?} in `{expr}`");
+		}
+		Ok(path)
+	}
+	/// Elements selected from `context`, in document order.
+	pub fn select(&self, doc: &ArchElement, context: &[usize]) -> Vec<NodeAddr> {
+		let mut nodes = vec![if self.absolute { vec![] } else { context.to_vec() }];
+		for step in &self.steps {
+			let mut next = vec![];
+			for node in &nodes {
+				let mut candidates = step.candidates(doc, node);
+				for predicate in &step.predicates {
+					let size = candidates.len();
+					candidates = (candidates.into_iter().enumerate())
+						.filter(|(idx, candidate)| predicate.matches(doc, candidate, idx + 1, size))
+						.map(|(_, candidate)| candidate)
+						.collect();
+				}
+				next.extend(candidates);
+			}
+			next.sort();
+			next.dedup();
+			nodes = next;
+		}
+		nodes
+	}
+}
+
+impl Step {
+	/// Elements along the axis that pass the node test, nearest first for reverse axes.
+	fn candidates(&self, doc: &ArchElement, addr: &[usize]) -> Vec<NodeAddr> {
+		let mut out = vec![];
+		match self.axis {
+			Axis::Child => out.extend(child_elements(doc.at(addr), addr)),
+			Axis::Descendant => doc.at(addr).descendants(addr, &mut out),
+			Axis::DescendantOrSelf => {
+				out.push(addr.to_vec());
+				doc.at(addr).descendants(addr, &mut out);
+			}
+			Axis::Self_ => out.push(addr.to_vec()),
+			Axis::Parent => out.extend(addr.split_last().map(|(_, parent)| parent.to_vec())),
+			Axis::Ancestor => out.extend((0..addr.len()).rev().map(|len| addr[..len].to_vec())),
+			Axis::FollowingSibling | Axis::PrecedingSibling => {
+				if let Some((&idx, parent)) = addr.split_last() {
+					let siblings = child_elements(doc.at(parent), parent);
+					if self.axis == Axis::FollowingSibling {
+						out.extend(siblings.filter(|sibling| sibling[parent.len()] > idx));
+					} else {
+						let mut preceding = siblings
+							.filter(|sibling| sibling[parent.len()] < idx)
+							.collect::<Vec<_>>();
+						preceding.reverse();
+						out.extend(preceding);
+					}
+				}
+			}
+		}
+		out.retain(|addr| match &self.test {
+			NodeTest::Any => true,
+			NodeTest::Name(name) => doc.at(addr).name == *name,
+		});
+		out
+	}
+}
+
+fn child_elements<'a>(element: &'a ArchElement, addr: &'a [usize]) -> impl Iterator<Item = NodeAddr> + 'a {
+	(element.children.iter().enumerate()).filter_map(move |(idx, child)| {
+		matches!(child, ArchNode::Element(_)).then(|| {
+			let mut child = addr.to_vec();
+			child.push(idx);
+			child
+		})
+	})
+}
+
+impl Expr {
+	fn matches(&self, doc: &ArchElement, node: &[usize], position: usize, size: usize) -> bool {
+		match self.eval(doc, node, position, size) {
+			Value::Number(number) => number == position as f64,
+			value => value.to_bool(),
+		}
+	}
+	fn eval(&self, doc: &ArchElement, node: &[usize], position: usize, size: usize) -> Value {
+		let eval = |expr: &Expr| expr.eval(doc, node, position, size);
+		match self {
+			Expr::Or(lhs, rhs) => Value::Bool(eval(lhs).to_bool() || eval(rhs).to_bool()),
+			Expr::And(lhs, rhs) => Value::Bool(eval(lhs).to_bool() && eval(rhs).to_bool()),
+			Expr::Compare(lhs, equal, rhs) => {
+				let (lhs, rhs) = (eval(lhs).items(doc), eval(rhs).items(doc));
+				let numeric = |lhs: &str, rhs: &str| match (lhs.trim().parse::<f64>(), rhs.trim().parse::<f64>()) {
+					(Ok(lhs), Ok(rhs)) => lhs == rhs,
+					_ => lhs == rhs,
+				};
+				Value::Bool(lhs.iter().any(|lhs| rhs.iter().any(|rhs| numeric(lhs, rhs) == *equal)))
+			}
+			Expr::Attribute(name) => Value::Strings(doc.at(node).attr(name).map(str::to_string).into_iter().collect()),
+			Expr::Literal(literal) => Value::String(literal.clone()),
+			Expr::Number(number) => Value::Number(*number),
+			Expr::Path(path) => Value::Nodes(path.select(doc, node)),
+			Expr::Call(name, args) => match (name.as_str(), args.as_slice()) {
+				("not", [arg]) => Value::Bool(!eval(arg).to_bool()),
+				("contains", [haystack, needle]) => {
+					Value::Bool(eval(haystack).to_string(doc).contains(&eval(needle).to_string(doc)))
+				}
+				("starts-with", [haystack, needle]) => {
+					Value::Bool(eval(haystack).to_string(doc).starts_with(&eval(needle).to_string(doc)))
+				}
+				("hasclass", classes) => {
+					let class = doc.at(node).attr("class").unwrap_or_default();
+					Value::Bool(classes.iter().all(|expected| {
+						let expected = eval(expected).to_string(doc);
+						class.split_whitespace().any(|class| class == expected)
+					}))
+				}
+				("text", []) => Value::String(doc.at(node).own_text()),
+				("string", []) => Value::String(doc.at(node).text()),
+				("string", [arg]) => Value::String(eval(arg).to_string(doc)),
+				("normalize-space", []) => Value::String(normalize_space(&doc.at(node).text())),
+				("normalize-space", [arg]) => Value::String(normalize_space(&eval(arg).to_string(doc))),
+				("position", []) => Value::Number(position as f64),
+				("last", []) => Value::Number(size as f64),
+				("count", [arg]) => Value::Number(eval(arg).items(doc).len() as f64),
+				_ => Value::Bool(false),
+			},
+		}
+	}
+}
+
+fn normalize_space(text: &str) -> String {
+	text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl Value {
+	fn to_bool(&self) -> bool {
+		match self {
+			Value::Nodes(nodes) => !nodes.is_empty(),
+			Value::Strings(values) => !values.is_empty(),
+			Value::String(value) => !value.is_empty(),
+			Value::Number(number) => *number != 0.0 && !number.is_nan(),
+			Value::Bool(value) => *value,
+		}
+	}
+	fn to_string(&self, doc: &ArchElement) -> String {
+		self.items(doc).into_iter().next().unwrap_or_default()
+	}
+	/// The string value of each item, or of the value itself if it is not a set.
+	fn items(&self, doc: &ArchElement) -> Vec<String> {
+		match self {
+			Value::Nodes(nodes) => nodes.iter().map(|node| doc.at(node).text()).collect(),
+			Value::Strings(values) => values.clone(),
+			Value::String(value) => vec![value.clone()],
+			Value::Number(number) => vec![number.to_string()],
+			Value::Bool(value) => vec![value.to_string()],
+		}
+	}
+}
+
+fn tokenize(expr: &str) -> anyhow::Result<Vec<Tok<'_>>> {
+	let mut out = vec![];
+	let mut idx = 0;
+	while let Some(rest) = expr.get(idx..)
+		&& let Some(first) = rest.chars().next()
+	{
+		let (tok, len) = match first {
+			' ' | '\t' | '\r' | '\n' => {
+				idx += 1;
+				continue;
+			}
+			'/' if rest.starts_with("//") => (Tok::DoubleSlash, 2),
+			'/' => (Tok::Slash, 1),
+			'[' => (Tok::LBracket, 1),
+			']' => (Tok::RBracket, 1),
+			'(' => (Tok::LParen, 1),
+			')' => (Tok::RParen, 1),
+			'@' => (Tok::At, 1),
+			',' => (Tok::Comma, 1),
+			'=' => (Tok::Eq, 1),
+			'*' => (Tok::Star, 1),
+			'!' if rest.starts_with("!=") => (Tok::NotEq, 2),
+			'.' if rest.starts_with("..") => (Tok::DotDot, 2),
+			'.' => (Tok::Dot, 1),
+			quote @ ('\'' | '"') => {
+				let Some(end) = rest[1..].find(quote) else {
+					bail!("unterminated string in `{expr}`");
+				};
+				(Tok::Literal(&rest[1..1 + end]), end + 2)
+			}
+			'0'..='9' => {
+				let len = rest
+					.find(|c: char| !c.is_ascii_digit() && c != '.')
+					.unwrap_or(rest.len());
+				(Tok::Number(rest[..len].parse()?), len)
+			}
+			c if c.is_alphabetic() || c == '_' => {
+				let len = rest
+					.find(|c: char| !c.is_alphanumeric() && !matches!(c, '_' | '-' | '.'))
+					.unwrap_or(rest.len());
+				match rest[len..].strip_prefix("::") {
+					Some(_) => (Tok::Axis(&rest[..len]), len + 2),
+					None => (Tok::Name(&rest[..len]), len),
+				}
+			}
+			_ => bail!("unexpected `{first}` in `{expr}`"),
+		};
+		out.push(tok);
+		idx += len;
+	}
+	Ok(out)
+}
+
+struct Parser<'a> {
+	tokens: Vec<Tok<'a>>,
+	pos: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn peek(&self) -> Option<Tok<'a>> {
+		self.tokens.get(self.pos).copied()
+	}
+	fn next(&mut self) -> Option<Tok<'a>> {
+		let tok = self.peek();
+		self.pos += 1;
+		tok
+	}
+	fn eat(&mut self, tok: Tok) -> bool {
+		let matches = self.peek() == Some(tok);
+		if matches {
+			self.pos += 1;
+		}
+		matches
+	}
+	fn expect(&mut self, tok: Tok) -> anyhow::Result<()> {
+		match self.next() {
+			Some(next) if next == tok => Ok(()),
+			next => bail!("expected {tok:?}, found {next:?}"),
+		}
+	}
+	fn path(&mut self) -> anyhow::Result<XPath> {
+		let mut absolute = false;
+		let mut descendant = false;
+		match self.peek() {
+			Some(Tok::Slash) => {
+				self.pos += 1;
+				absolute = true;
+				// `/` alone selects the document node
+				if !matches!(
+					self.peek(),
+					Some(Tok::Dot | Tok::DotDot | Tok::Star | Tok::Axis(_) | Tok::Name(_))
+				) {
+					return Ok(XPath {
+						absolute,
+						steps: vec![],
+					});
+				}
+			}
+			Some(Tok::DoubleSlash) => {
+				self.pos += 1;
+				absolute = true;
+				descendant = true;
+			}
+			_ => {}
+		}
+		let mut steps = vec![];
+		loop {
+			// `//` is short for `/descendant-or-self::node()/`
+			if descendant {
+				steps.push(Step {
+					axis: Axis::DescendantOrSelf,
+					test: NodeTest::Any,
+					predicates: vec![],
+				});
+			}
+			steps.push(self.step()?);
+			match self.peek() {
+				Some(Tok::Slash) => descendant = false,
+				Some(Tok::DoubleSlash) => descendant = true,
+				_ => break,
+			}
+			self.pos += 1;
+		}
+		Ok(XPath { absolute, steps })
+	}
+	fn step(&mut self) -> anyhow::Result<Step> {
+		let (axis, test) = match self.next() {
+			Some(Tok::Dot) => (Axis::Self_, NodeTest::Any),
+			Some(Tok::DotDot) => (Axis::Parent, NodeTest::Any),
+			Some(Tok::Axis(axis)) => {
+				let axis = match axis {
+					"child" => Axis::Child,
+					"descendant" => Axis::Descendant,
+					"descendant-or-self" => Axis::DescendantOrSelf,
+					"self" => Axis::Self_,
+					"parent" => Axis::Parent,
+					"ancestor" => Axis::Ancestor,
+					"following-sibling" => Axis::FollowingSibling,
+					"preceding-sibling" => Axis::PrecedingSibling,
+					_ => bail!("unsupported axis `{axis}`"),
+				};
+				(axis, self.node_test()?)
+			}
+			Some(Tok::Star | Tok::Name(_)) => {
+				self.pos -= 1;
+				(Axis::Child, self.node_test()?)
+			}
+			tok => bail!("expected a step, found {tok:?}"),
+		};
+		let mut predicates = vec![];
+		while self.eat(Tok::LBracket) {
+			predicates.push(self.or_expr()?);
+			self.expect(Tok::RBracket)?;
+		}
+		Ok(Step { axis, test, predicates })
+	}
+	fn node_test(&mut self) -> anyhow::Result<NodeTest> {
+		let test = match self.next() {
+			Some(Tok::Star) => NodeTest::Any,
+			Some(Tok::Name(name)) => NodeTest::Name(name.to_string()),
+			tok => bail!("expected a node test, found {tok:?}"),
+		};
+		if self.peek() == Some(Tok::LParen) {
+			bail!("unsupported node type test");
+		}
+		Ok(test)
+	}
+	fn or_expr(&mut self) -> anyhow::Result<Expr> {
+		let mut lhs = self.and_expr()?;
+		while self.eat(Tok::Name("or")) {
+			lhs = Expr::Or(Box::new(lhs), Box::new(self.and_expr()?));
+		}
+		Ok(lhs)
+	}
+	fn and_expr(&mut self) -> anyhow::Result<Expr> {
+		let mut lhs = self.compare_expr()?;
+		while self.eat(Tok::Name("and")) {
+			lhs = Expr::And(Box::new(lhs), Box::new(self.compare_expr()?));
+		}
+		Ok(lhs)
+	}
+	fn compare_expr(&mut self) -> anyhow::Result<Expr> {
+		let lhs = self.primary()?;
+		let equal = match self.peek() {
+			Some(Tok::Eq) => true,
+			Some(Tok::NotEq) => false,
+			_ => return Ok(lhs),
+		};
+		self.pos += 1;
+		Ok(Expr::Compare(Box::new(lhs), equal, Box::new(self.primary()?)))
+	}
+	fn primary(&mut self) -> anyhow::Result<Expr> {
+		match self.peek() {
+			Some(Tok::At) => {
+				self.pos += 1;
+				match self.next() {
+					Some(Tok::Name(name)) => Ok(Expr::Attribute(name.to_string())),
+					tok => bail!("expected an attribute name, found {tok:?}"),
+				}
+			}
+			Some(Tok::Literal(literal)) => {
+				self.pos += 1;
+				Ok(Expr::Literal(literal.to_string()))
+			}
+			Some(Tok::Number(number)) => {
+				self.pos += 1;
+				Ok(Expr::Number(number))
+			}
+			Some(Tok::LParen) => {
+				self.pos += 1;
+				let expr = self.or_expr()?;
+				self.expect(Tok::RParen)?;
+				Ok(expr)
+			}
+			Some(Tok::Name(name)) if self.tokens.get(self.pos + 1) == Some(&Tok::LParen) => {
+				if !FUNCTIONS.contains(name) {
+					bail!("unsupported function `{name}`");
+				}
+				self.pos += 2;
+				let mut args = vec![];
+				if !self.eat(Tok::RParen) {
+					loop {
+						args.push(self.or_expr()?);
+						if self.eat(Tok::RParen) {
+							break;
+						}
+						self.expect(Tok::Comma)?;
+					}
+				}
+				Ok(Expr::Call(name.to_string(), args))
+			}
+			_ => Ok(Expr::Path(self.path()?)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use pretty_assertions::assert_eq;
+
+	use super::XPath;
+	use crate::xml::arch::tests::parse_doc;
+
+	#[test]
+	fn test_xpath_select() {
+		let doc = parse_doc(
+			r#"<form>
+	<sheet>
+		<group name="main" class="o_inner_group grid">
+			<field name="partner_id"/>
+			<field name="date"/>
+		</group>
+		<notebook>
+			<page string="Lines"><field name="line_ids"/></page>
+		</notebook>
+	</sheet>
+</form>"#,
+		);
+		let select = |expr: &str| {
+			let xpath = XPath::parse(expr).unwrap();
+			(xpath.select(&doc, &[0]).into_iter())
+				.map(|addr| {
+					let element = doc.at(&addr);
+					match element.attr("name") {
+						Some(name) => format!("{}[{name}]", element.name),
+						None => element.name.clone(),
+					}
+				})
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(select("//field[@name='partner_id']"), ["field[partner_id]"]);
+		assert_eq!(select("/form/sheet/group/field"), ["field[partner_id]", "field[date]"]);
+		assert_eq!(select("sheet//field[2]"), ["field[date]"]);
+		assert_eq!(select("//div[hasclass('grid')]"), Vec::<String>::new());
+		assert_eq!(select("//group[hasclass('grid')]/.."), ["sheet"]);
+		assert_eq!(select("//page[@string='Lines']/field"), ["field[line_ids]"]);
+		assert_eq!(
+			select("//field[@name='partner_id']/following-sibling::field"),
+			["field[date]"]
+		);
+		assert_eq!(select("//group[field[@name='date'] and @name]"), ["group[main]"]);
+		assert!(XPath::parse("//field[@name='x'").is_err());
+		assert!(XPath::parse("//field[matches(@name, 'x')]").is_err());
+	}
+}
@@ -0,0 +1,4 @@
+class Base(Model):
+    _name = "arch.base"
+
+    name = fields.Char()
@@ -0,0 +1 @@
+{"name": "c", "depends": ["a"]}
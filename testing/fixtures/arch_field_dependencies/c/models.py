@@ -0,0 +1,4 @@
+class Base(Model):
+    _inherit = "arch.base"
+
+    extra = fields.Char()
@@ -0,0 +1 @@
+{"name": "d", "depends": ["c"]}
@@ -0,0 +1 @@
+{"name": "e", "depends": ["a"]}
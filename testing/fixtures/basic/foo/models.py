@@ -6,8 +6,10 @@ class Foo(Model):
     #                          ^complete bar derived.bar foo foob
     foo_o2m = fields.One2many("foo")
     #                          ^complete bar derived.bar foo foob
-    foo_m2m = fields.Many2many("foo", 'bah', related='foo_m2o.foo_m2o')
+    foo_m2m = fields.Many2many("foo", 'bah',
     #                           ^complete bar derived.bar foo foob
+                               related='foo_m2o.foo_m2o')
+    #                                   ^diag Related field `foo_m2o.foo_m2o` is a `Many2one` field, not `Many2many`
 
     def completions(self):
         #^type Method(Symbol<ModelEntry>("foo"), "completions")
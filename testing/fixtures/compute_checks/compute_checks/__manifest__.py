@@ -0,0 +1 @@
+{"name": "compute_checks"}
@@ -0,0 +1,33 @@
+class Order(Model):
+    _name = "compute.order"
+
+    amount = fields.Float()
+    tax = fields.Float()
+    total = fields.Float(compute="_compute_total", store=True)
+    #                             ^diag Stored field `total` is computed by `_compute_total` without `@api.depends`
+    label = fields.Char(compute="_compute_label")
+    #                            ^diag `_compute_label` never assigns `label`
+    doubled = fields.Float(compute="_compute_doubled")
+    halved = fields.Float(compute="_compute_doubled")
+    looped = fields.Float(compute="_compute_looped")
+
+    def _compute_total(self):
+        for order in self:
+            order.total = order.amount + order.tax
+
+    def _compute_label(self):
+        for order in self:
+            order.tax = 0.0
+
+    @api.depends("amount")
+    def _compute_doubled(self):
+        for order in self:
+            order.doubled = order.amount * 2
+            order.halved = order.tax / 2
+            #                    ^diag `_compute_doubled` computes several fields and reads `tax`, which is missing from its `@api.depends`
+
+    @api.depends("looped")
+    #             ^diag Field `looped` cannot depend on itself
+    def _compute_looped(self):
+        for order in self:
+            order.looped = order.amount
@@ -0,0 +1 @@
+{"name": "context_keys"}
@@ -0,0 +1,14 @@
+class Task(Model):
+    _name = "ctx.task"
+
+    name = fields.Char()
+    done = fields.Boolean()
+
+    def read_context(self):
+        return self.env.context.get("skip_check")
+
+    def check(self):
+        self.with_context({"default_name": "x"})
+        #                   ^complete default_done default_name search_default_finished skip_check
+        self.with_context(skip_check=True)
+        #                      ^complete skip_check
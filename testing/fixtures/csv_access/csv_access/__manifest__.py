@@ -0,0 +1 @@
+{"name": "csv_access"}
@@ -0,0 +1,12 @@
+class Order(Model):
+    _name = "access.order"
+
+    def refs(self):
+        self.env.ref("csv_access.access_order_user")
+        self.env.ref("csv_access.access_line_user")
+        #             ^diag No XML record with ID `csv_access.access_line_user` found
+
+
+class Line(Model):
+    _name = "access.line"
+    #        ^diag Model `access.line` has no access rules
@@ -0,0 +1,30 @@
+class Partner(Model):
+    _name = "delegate.partner"
+
+    name = fields.Char()
+    email = fields.Char()
+
+
+class User(Model):
+    _name = "delegate.user"
+    _inherits = {"delegate.partner": "partner_id"}
+
+    partner_id = fields.Many2one("delegate.partner")
+    login = fields.Char()
+
+    def check(self):
+        self.email
+        self.mapped("email")
+        #            ^complete email login name partner_id
+        self.mapped("phone")
+        #            ^diag Model `delegate.user` has no field `phone`
+
+
+class Employee(Model):
+    _name = "delegate.employee"
+
+    user_id = fields.Many2one("delegate.user", delegate=True)
+
+    def check(self):
+        self.mapped("login")
+        #            ^complete email login name partner_id user_id
@@ -0,0 +1,17 @@
+{
+    "name": "manifest_paths",
+    "data": [
+    #^diag `views/extra.xml` is not listed in the manifest
+        "views/views.xml",
+        #^complete static/ views/
+        "data/missing.xml",
+        #^diag File `data/missing.xml` does not exist
+    ],
+    "assets": {
+        "web.assets_backend": [
+            "manifest_paths/static/src/js/*.js",
+            "manifest_paths/static/src/scss/*.scss",
+            #^diag `manifest_paths/static/src/scss/*.scss` matches no files
+        ],
+    },
+}
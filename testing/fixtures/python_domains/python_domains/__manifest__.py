@@ -0,0 +1 @@
+{"name": "python_domains"}
@@ -0,0 +1,20 @@
+class Partner(Model):
+    _name = "domain.partner"
+
+    name = fields.Char()
+    active = fields.Boolean()
+    age = fields.Integer()
+    parent_id = fields.Many2one("domain.partner")
+    child_ids = fields.One2many("domain.partner", "parent_id")
+
+    def domains(self):
+        self.search(['|', ('name', '=', 'a')])
+        #            ^diag `|` expects 2 operand(s), found 1
+        self.search([('age', 'bigger', 3)])
+        #                     ^diag Unknown domain operator `bigger`
+        self.search([('active', '>', True)])
+        #                        ^diag Operator `>` does not apply to Boolean field `active`
+        self.search([('child_ids', 'any', [('nmae', '=', 'a')])])
+        #                                    ^diag Model `domain.partner` has no field `nmae`
+        self.search([('child_ids', 'any', [('age', '=', 3)])])
+        #                                    ^complete active age child_ids name parent_id
@@ -0,0 +1 @@
+{"name": "reference_fields"}
@@ -0,0 +1,30 @@
+class Document(Model):
+    _name = "ref.doc"
+
+    name = fields.Char()
+    pages = fields.Integer()
+
+
+class Invoice(Model):
+    _name = "ref.invoice"
+
+    name = fields.Char()
+    amount = fields.Float()
+
+
+class Link(Model):
+    _name = "ref.link"
+
+    res_ref = fields.Reference(selection=[("ref.doc", "Document"), ("ref.invoice", "Invoice")])
+    res_model = fields.Selection([("ref.doc", "Document"), ("ref.invoice", "Invoice")])
+    res_id = fields.Many2oneReference(model_field="res_model")
+    bad_ref = fields.Reference(selection=[("ref.doc", "Document"), ("ref.bogus", "Bogus")])
+    #                                                                ^diag `ref.bogus` is not a valid model name
+
+    def check(self):
+        self.mapped("res_ref.name")
+        #                    ^complete name
+        self.mapped("res_id.name")
+        #                   ^complete name
+        self.mapped("res_id.pages")
+        #                   ^diag Model `ref.doc | ref.invoice` has no field `pages`
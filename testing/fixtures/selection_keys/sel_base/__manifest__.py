@@ -0,0 +1 @@
+{"name": "sel_base"}
@@ -0,0 +1,4 @@
+class Order(Model):
+    _name = "sel.order"
+
+    state = fields.Selection([("draft", "Draft"), ("done", "Done")])
@@ -0,0 +1 @@
+{"name": "sel_ext", "depends": ["sel_base"]}
@@ -0,0 +1,12 @@
+class Order(Model):
+    _inherit = "sel.order"
+
+    state = fields.Selection(selection_add=[("sent", "Sent")])
+
+    def check(self):
+        self.write({"state": "sent"})
+        #                     ^complete done draft sent
+        self.state == "cancel"
+        #              ^diag Selection field `state` has no key `cancel`
+        self.search([("state", "=", "bogus")])
+        #                            ^diag Selection field `state` has no key `bogus`
@@ -0,0 +1 @@
+{"name": "xml_domains"}
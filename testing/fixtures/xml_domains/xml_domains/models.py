@@ -0,0 +1,7 @@
+class Partner(Model):
+    _name = "domain.partner"
+
+    name = fields.Char()
+    active = fields.Boolean()
+    age = fields.Integer()
+    parent_id = fields.Many2one("domain.partner")
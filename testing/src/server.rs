@@ -8,6 +8,8 @@ use odoo_lsp::backend::Backend;
 use odoo_lsp::utils::CatchPanic;
 
 pub fn setup_lsp_server(concurrency_level: Option<usize>) -> async_lsp::ServerSocket {
+	// always index fixtures from scratch, and keep them out of the user's cache
+	odoo_lsp::index::set_cache_dir(None);
 	let (service, socket) = LspService::build(Backend::new)
 		.custom_method("odoo-lsp/inspect-type", Backend::debug_inspect_type)
 		.finish();